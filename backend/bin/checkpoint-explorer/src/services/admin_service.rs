@@ -0,0 +1,254 @@
+use crate::services::audit_service::audit_checkpoints;
+use crate::services::checkpoint_service::reconcile_checkpoint_status;
+use crate::services::integrity_service::{repair_blocks, repair_checkpoints};
+use crate::services::lifecycle_service::L1BlockTimes;
+use crate::services::webhook_service::WebhookNotifier;
+use crate::utils::config::AdminCommand;
+use database::repository::{
+    BlockRepository, CheckpointRepository, MaintenanceRepository, SyncStateRepository,
+};
+use fullnode_client::fetcher::StrataFetcher;
+use model::checkpoint::CheckpointState;
+use model::integrity::MissingRange;
+use model::pgu64::PgU64;
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Run a maintenance command to completion, or until `token` is cancelled.
+///
+/// Deletions are transactional and rewind the sync cursors, re-fetching goes through the
+/// same continuity checks as the indexer. A live indexer therefore either ignores the
+/// affected range or re-fetches it as well, whatever is left over after a failure is picked
+/// up by the integrity checker.
+pub async fn run_admin_command<R>(
+    command: AdminCommand,
+    fetcher: &StrataFetcher,
+    repository: &R,
+    notifier: &WebhookNotifier,
+    l1_times: &L1BlockTimes,
+    token: &CancellationToken,
+) -> anyhow::Result<()>
+where
+    R: CheckpointRepository + BlockRepository + SyncStateRepository + MaintenanceRepository,
+{
+    match command {
+        AdminCommand::Reindex {
+            from,
+            to,
+            with_reorg_events,
+        } => {
+            reindex(
+                fetcher,
+                repository,
+                notifier,
+                from,
+                to,
+                with_reorg_events,
+                token,
+            )
+            .await
+        }
+        AdminCommand::RefetchBlocks { idx } => {
+            refetch_blocks(fetcher, repository, idx, token).await
+        }
+        AdminCommand::Purge {
+            after,
+            with_reorg_events,
+        } => purge(repository, after, with_reorg_events).await,
+        AdminCommand::RefreshStatus { from, to } => {
+            refresh_status(fetcher, repository, notifier, l1_times, from, to, token).await
+        }
+        AdminCommand::Audit {
+            from,
+            to,
+            output,
+            concurrency,
+        } => audit(fetcher, repository, from, to, output, concurrency, token).await,
+    }
+}
+
+async fn reindex<R: CheckpointRepository + BlockRepository + MaintenanceRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    notifier: &WebhookNotifier,
+    from: u64,
+    to: u64,
+    with_reorg_events: bool,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    if from > to {
+        anyhow::bail!("Checkpoint {} is after checkpoint {}", from, to);
+    }
+    let purged = repository
+        .delete_checkpoints(
+            PgU64(from).to_i64(),
+            Some(PgU64(to).to_i64()),
+            with_reorg_events,
+        )
+        .await?;
+    info!(
+        "Deleted {} checkpoints, {} blocks, {} L1 blocks and {} reorg events",
+        purged.checkpoints, purged.blocks, purged.l1_blocks, purged.reorg_events
+    );
+
+    let checkpoints = MissingRange {
+        start: from,
+        end: to,
+    };
+    if !repair_checkpoints(fetcher, repository, notifier, &checkpoints, token).await {
+        anyhow::bail!(
+            "Failed to re-index checkpoints {} to {}, the indexer will fetch the rest",
+            from,
+            to
+        );
+    }
+    let first = repository
+        .get_checkpoint_by_idx(PgU64(from).to_i64())
+        .await?;
+    let last = repository.get_checkpoint_by_idx(PgU64(to).to_i64()).await?;
+    let (Some(first), Some(last)) = (first, last) else {
+        anyhow::bail!("Checkpoints {} to {} were deleted again", from, to);
+    };
+
+    let blocks = MissingRange {
+        start: first.l2_range.0,
+        end: last.l2_range.1,
+    };
+    if !repair_blocks(fetcher, repository, &blocks, token).await {
+        anyhow::bail!(
+            "Failed to re-fetch blocks {} to {}, the integrity checker will fetch the rest",
+            blocks.start,
+            blocks.end
+        );
+    }
+    info!(
+        "Re-indexed checkpoints {} to {} with blocks {} to {}",
+        from, to, blocks.start, blocks.end
+    );
+    Ok(())
+}
+
+async fn refetch_blocks<R: CheckpointRepository + BlockRepository + MaintenanceRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    idx: u64,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let Some(checkpoint) = repository
+        .get_checkpoint_by_idx(PgU64(idx).to_i64())
+        .await?
+    else {
+        anyhow::bail!("Checkpoint {} is not stored", idx);
+    };
+    let deleted = repository
+        .delete_checkpoint_blocks(PgU64(idx).to_i64())
+        .await?;
+    info!("Deleted {} blocks of checkpoint {}", deleted, idx);
+
+    let blocks = MissingRange {
+        start: checkpoint.l2_range.0,
+        end: checkpoint.l2_range.1,
+    };
+    if !repair_blocks(fetcher, repository, &blocks, token).await {
+        anyhow::bail!(
+            "Failed to re-fetch blocks {} to {}, the integrity checker will fetch the rest",
+            blocks.start,
+            blocks.end
+        );
+    }
+    info!(
+        "Re-fetched blocks {} to {} of checkpoint {}",
+        blocks.start, blocks.end, idx
+    );
+    Ok(())
+}
+
+async fn purge<R: MaintenanceRepository>(
+    repository: &R,
+    after: u64,
+    with_reorg_events: bool,
+) -> anyhow::Result<()> {
+    let Some(from) = after.checked_add(1) else {
+        anyhow::bail!("There is no checkpoint after {}", after);
+    };
+    let purged = repository
+        .delete_checkpoints(PgU64(from).to_i64(), None, with_reorg_events)
+        .await?;
+    info!(
+        "Deleted {} checkpoints, {} blocks, {} L1 blocks and {} reorg events after checkpoint {}",
+        purged.checkpoints, purged.blocks, purged.l1_blocks, purged.reorg_events, after
+    );
+    Ok(())
+}
+
+async fn refresh_status<R: CheckpointRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    notifier: &WebhookNotifier,
+    l1_times: &L1BlockTimes,
+    from: Option<u64>,
+    to: Option<u64>,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let statuses: Vec<&str> = CheckpointState::ALL
+        .iter()
+        .map(CheckpointState::status)
+        .collect();
+    let from = from.map_or(i64::MIN, |from| PgU64(from).to_i64());
+    let to = to.map_or(i64::MAX, |to| PgU64(to).to_i64());
+    let idxs: Vec<i64> = repository
+        .get_checkpoint_idxs_by_status(&statuses)
+        .await?
+        .into_iter()
+        .filter(|idx| (from..=to).contains(idx))
+        .collect();
+
+    let mut failed = 0;
+    for idx in &idxs {
+        if token.is_cancelled() {
+            anyhow::bail!("Interrupted");
+        }
+        if !reconcile_checkpoint_status(fetcher, repository, notifier, l1_times, *idx).await {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("Failed to refresh {} of {} checkpoints", failed, idxs.len());
+    }
+    info!("Refreshed the status of {} checkpoints", idxs.len());
+    Ok(())
+}
+
+async fn audit<R: CheckpointRepository + BlockRepository + SyncStateRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    from: Option<u64>,
+    to: Option<u64>,
+    output: Option<PathBuf>,
+    concurrency: usize,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let Some(report) = audit_checkpoints(fetcher, repository, from, to, concurrency, token).await?
+    else {
+        anyhow::bail!("No stored checkpoints to audit in the requested range");
+    };
+
+    let json = serde_json::to_string_pretty(&report)?;
+    match output {
+        Some(path) => std::fs::write(&path, json)?,
+        None => println!("{}", json),
+    }
+    if !report.is_clean() {
+        anyhow::bail!(
+            "Audit found {} differences and {} failures",
+            report.diffs.len(),
+            report.failures.len()
+        );
+    }
+    info!(
+        "Audited {} checkpoints and {} blocks, no drift found",
+        report.checked_checkpoints, report.checked_blocks
+    );
+    Ok(())
+}
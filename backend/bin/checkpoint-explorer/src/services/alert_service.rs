@@ -0,0 +1,435 @@
+use crate::services::api_service::fullnode_checkpoint_tip;
+use crate::services::webhook_service::WebhookNotifier;
+use crate::utils::supervisor::report_success;
+use database::repository::{CheckpointRepository, SyncStateRepository, WebhookDeliveryRepository};
+use database::services::utils::unix_timestamp;
+use fullnode_client::fetcher::StrataFetcher;
+use model::checkpoint::CheckpointState;
+use model::pgu64::PgU64;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Most stalled checkpoints reported per kind of alert
+const STALLED_ALERT_LIMIT: u64 = 100;
+
+/// Thresholds above which alerts are raised, `None` disables a check
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct AlertThresholds {
+    /// Longest time a checkpoint may stay pending (or without a status), in seconds
+    pub pending_seconds: Option<u64>,
+    /// Longest time a checkpoint may stay confirmed before being finalized, in seconds
+    pub confirmed_seconds: Option<u64>,
+    /// Largest number of checkpoints the index may be behind the fullnode tip
+    pub checkpoint_lag: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum AlertKind {
+    /// A checkpoint is pending for longer than the threshold
+    #[serde(rename = "checkpoint_pending")]
+    StalledPending,
+    /// A checkpoint is confirmed for longer than the threshold
+    #[serde(rename = "checkpoint_confirmed")]
+    StalledConfirmed,
+    /// The index is too far behind the fullnode
+    #[serde(rename = "checkpoint_lag")]
+    Lag,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 3] = [
+        AlertKind::StalledPending,
+        AlertKind::StalledConfirmed,
+        AlertKind::Lag,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertKind::StalledPending => "checkpoint_pending",
+            AlertKind::StalledConfirmed => "checkpoint_confirmed",
+            AlertKind::Lag => "checkpoint_lag",
+        }
+    }
+}
+
+/// A threshold currently crossed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// The stalled checkpoint, or the latest checkpoint stored for a lag alert
+    pub checkpoint_idx: u64,
+    /// Seconds spent in the status, or number of checkpoints behind
+    pub value: u64,
+    pub threshold: u64,
+    /// UNIX timestamp (seconds) of when the threshold was crossed, unknown for a lag alert
+    pub since: Option<i64>,
+    pub message: String,
+}
+
+impl Alert {
+    /// Identity of the alert across evaluations, a lag alert stays the same alert while the
+    /// index moves
+    fn key(&self) -> (AlertKind, Option<u64>) {
+        match self.kind {
+            AlertKind::Lag => (self.kind, None),
+            _ => (self.kind, Some(self.checkpoint_idx)),
+        }
+    }
+}
+
+/// Alerts currently raised, and why the lag could not be checked if so
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AlertReport {
+    pub alerts: Vec<Alert>,
+    pub fullnode_error: Option<String>,
+}
+
+/// Compare the stored checkpoints and the fullnode tip against `thresholds`
+pub async fn evaluate_alerts<R: CheckpointRepository + SyncStateRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    thresholds: &AlertThresholds,
+) -> anyhow::Result<AlertReport> {
+    let now = unix_timestamp();
+    let mut report = AlertReport::default();
+
+    let stalled_checks = [
+        (
+            AlertKind::StalledPending,
+            thresholds.pending_seconds,
+            [CheckpointState::Unknown, CheckpointState::Pending].as_slice(),
+        ),
+        (
+            AlertKind::StalledConfirmed,
+            thresholds.confirmed_seconds,
+            [CheckpointState::Confirmed].as_slice(),
+        ),
+    ];
+    for (kind, threshold, states) in stalled_checks {
+        let Some(threshold) = threshold else {
+            continue;
+        };
+        let statuses: Vec<&str> = states.iter().map(CheckpointState::status).collect();
+        let entered_before = now.saturating_sub(threshold.min(i64::MAX as u64) as i64);
+        let stalled = repository
+            .get_stalled_checkpoints(&statuses, entered_before, STALLED_ALERT_LIMIT)
+            .await?;
+        for checkpoint in stalled {
+            let Some(entered_at) = checkpoint.status_updated_at else {
+                continue;
+            };
+            let idx = PgU64::i64_to_u64(checkpoint.idx);
+            let state = CheckpointState::from_status(&checkpoint.status);
+            let value = now.saturating_sub(entered_at).max(0) as u64;
+            report.alerts.push(Alert {
+                kind,
+                checkpoint_idx: idx,
+                value,
+                threshold,
+                since: Some(entered_at.saturating_add(threshold as i64)),
+                message: format!(
+                    "Checkpoint {} has been {} for {}s, more than {}s",
+                    idx, state, value, threshold
+                ),
+            });
+        }
+    }
+
+    if let Some(threshold) = thresholds.checkpoint_lag {
+        match fullnode_checkpoint_tip(fetcher).await {
+            Ok(Some(tip)) => {
+                let index_range = repository.get_index_range().await?;
+                let lag = index_range.checkpoint_lag(tip);
+                if lag > threshold {
+                    report.alerts.push(Alert {
+                        kind: AlertKind::Lag,
+                        checkpoint_idx: index_range
+                            .last_checkpoint
+                            .unwrap_or(index_range.start_checkpoint),
+                        value: lag,
+                        threshold,
+                        since: None,
+                        message: format!(
+                            "The index is {} checkpoints behind the fullnode tip {}, more than {}",
+                            lag, tip, threshold
+                        ),
+                    });
+                }
+            }
+            Ok(None) => (),
+            Err(e) => report.fullnode_error = Some(e),
+        }
+    }
+    Ok(report)
+}
+
+/// This function periodically evaluates the alerts and reports the ones raised and resolved
+/// since the previous evaluation to the logs, the metrics and the webhooks
+/// It will run in a loop with a delay of `check_interval` seconds until `token` is cancelled
+pub async fn start_alert_monitor<R>(
+    fetcher: Arc<StrataFetcher>,
+    repository: Arc<R>,
+    notifier: WebhookNotifier,
+    thresholds: AlertThresholds,
+    check_interval: u64,
+    token: CancellationToken,
+) where
+    R: CheckpointRepository + SyncStateRepository + WebhookDeliveryRepository + 'static,
+{
+    info!("Starting alert monitor...");
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(check_interval));
+    // alerts raised before a restart are raised again
+    let mut active: HashMap<(AlertKind, Option<u64>), Alert> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        record_index_gauges(fetcher.as_ref(), repository.as_ref()).await;
+        let report = match evaluate_alerts(fetcher.as_ref(), repository.as_ref(), &thresholds).await
+        {
+            Ok(report) => report,
+            Err(e) => {
+                error!("Error evaluating alerts: {}", e);
+                continue;
+            }
+        };
+
+        let mut current: HashMap<(AlertKind, Option<u64>), Alert> = report
+            .alerts
+            .into_iter()
+            .map(|alert| (alert.key(), alert))
+            .collect();
+        // an unreachable fullnode neither raises nor resolves the lag alert
+        if report.fullnode_error.is_some() {
+            if let Some(alert) = active.get(&(AlertKind::Lag, None)) {
+                current.insert(alert.key(), alert.clone());
+            }
+        }
+
+        for (key, alert) in &current {
+            if !active.contains_key(key) {
+                warn!("Alert raised: {}", alert.message);
+                metrics::counter!("explorer_alerts_raised_total", "kind" => alert.kind.name())
+                    .increment(1);
+                notifier
+                    .alert_changed(repository.as_ref(), alert, true)
+                    .await;
+            }
+        }
+        for (key, alert) in &active {
+            if !current.contains_key(key) {
+                info!("Alert resolved: {}", alert.message);
+                notifier
+                    .alert_changed(repository.as_ref(), alert, false)
+                    .await;
+            }
+        }
+        for kind in AlertKind::ALL {
+            let count = current.values().filter(|alert| alert.kind == kind).count();
+            metrics::gauge!("explorer_alerts", "kind" => kind.name()).set(count as f64);
+        }
+        active = current;
+        report_success();
+    }
+}
+
+/// Set the gauges of the fullnode and local tips and of the checkpoints stored per status.
+/// Called by the alert monitor and on every scrape of `/metrics`, so that the processes
+/// serving the API report them too.
+pub async fn record_index_gauges<R: CheckpointRepository + SyncStateRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+) {
+    if let Ok(Some(tip)) = fullnode_checkpoint_tip(fetcher).await {
+        metrics::gauge!("explorer_fullnode_checkpoint_tip").set(tip as f64);
+    }
+    match repository.get_index_range().await {
+        Ok(index_range) => {
+            if let Some(tip) = index_range.last_checkpoint {
+                metrics::gauge!("explorer_local_checkpoint_tip").set(tip as f64);
+            }
+            if let Some(tip) = index_range.last_block {
+                metrics::gauge!("explorer_local_block_tip").set(tip as f64);
+            }
+        }
+        Err(e) => error!("Failed to fetch the index range: {:?}", e),
+    }
+    match repository.get_checkpoint_counts_by_status().await {
+        Ok(counts) => {
+            for checkpoint_state in CheckpointState::ALL {
+                let count = counts
+                    .iter()
+                    .find(|(status, _)| status == checkpoint_state.status())
+                    .map_or(0, |(_, count)| *count);
+                metrics::gauge!("explorer_checkpoints", "status" => checkpoint_state.to_string())
+                    .set(count as f64);
+            }
+        }
+        Err(e) => error!("Failed to count checkpoints by status: {:?}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fake_fullnode::{checkpoint_info, FakeFullnode};
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+
+    /// Checkpoints 0 to 2 stored, the fullnode at checkpoint 5
+    async fn three_behind<R: TestRepository>() -> (FakeFullnode, R) {
+        let fullnode = FakeFullnode::default();
+        let repository = R::open().await;
+        for idx in 0..=5 {
+            fullnode.add_checkpoint(idx, (5 * idx, 5 * idx + 4), "finalized", "aa");
+        }
+        for idx in 0..=2 {
+            let checkpoint = checkpoint_info(idx, (5 * idx, 5 * idx + 4), "finalized", "aa");
+            repository
+                .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+                .await;
+        }
+        (fullnode, repository)
+    }
+
+    async fn raises_a_lag_alert_past_the_threshold<R: TestRepository>() {
+        let (fullnode, repository) = three_behind::<R>().await;
+        let fetcher = fullnode.serve();
+        let thresholds = |lag| AlertThresholds {
+            checkpoint_lag: Some(lag),
+            ..Default::default()
+        };
+
+        let report = evaluate_alerts(&fetcher, &repository, &thresholds(2))
+            .await
+            .unwrap();
+        let [alert] = report.alerts.as_slice() else {
+            panic!("expected one alert, got {:?}", report.alerts);
+        };
+        assert_eq!(
+            (
+                alert.kind,
+                alert.checkpoint_idx,
+                alert.value,
+                alert.threshold
+            ),
+            (AlertKind::Lag, 2, 3, 2)
+        );
+        assert_eq!(alert.since, None);
+
+        let report = evaluate_alerts(&fetcher, &repository, &thresholds(3))
+            .await
+            .unwrap();
+        assert!(report.alerts.is_empty(), "{:?}", report.alerts);
+    }
+
+    async fn reports_an_unreachable_fullnode_instead_of_the_lag<R: TestRepository>() {
+        let (_, repository) = three_behind::<R>().await;
+        let fetcher = StrataFetcher::new("http://127.0.0.1:1/".to_string());
+        let thresholds = AlertThresholds {
+            checkpoint_lag: Some(0),
+            ..Default::default()
+        };
+
+        let report = evaluate_alerts(&fetcher, &repository, &thresholds)
+            .await
+            .unwrap();
+        assert!(report.alerts.is_empty(), "{:?}", report.alerts);
+        assert_eq!(
+            report.fullnode_error.as_deref(),
+            Some("Failed to reach the fullnode")
+        );
+    }
+
+    repository_tests!(
+        raises_a_lag_alert_past_the_threshold,
+        reports_an_unreachable_fullnode_instead_of_the_lag,
+    );
+
+    /// Move the time checkpoint `idx` entered its status `seconds` back
+    #[cfg(feature = "sqlite")]
+    async fn age(repository: &database::repository::sql::SqlRepository, idx: u64, seconds: i64) {
+        use sea_orm::ConnectionTrait;
+
+        repository
+            .connection()
+            .execute_unprepared(&format!(
+                "UPDATE checkpoints SET status_updated_at = status_updated_at - {} \
+                 WHERE idx = {}",
+                seconds,
+                PgU64(idx).to_i64()
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn raises_alerts_for_checkpoints_stalled_in_their_status() {
+        let repository = database::repository::sql::SqlRepository::open().await;
+        let notifier = WebhookNotifier::default();
+        for (idx, status) in [
+            (0, "finalized"),
+            (1, "confirmed"),
+            (2, "pending"),
+            (3, "pending"),
+        ] {
+            let checkpoint = checkpoint_info(idx, (5 * idx, 5 * idx + 4), status, "aa");
+            repository.insert_checkpoint(checkpoint, &notifier).await;
+        }
+        age(&repository, 0, 7200).await;
+        age(&repository, 1, 7200).await;
+        age(&repository, 2, 600).await;
+        let thresholds = AlertThresholds {
+            pending_seconds: Some(300),
+            confirmed_seconds: Some(3600),
+            checkpoint_lag: None,
+        };
+
+        // the fullnode is not asked without a lag threshold
+        let fetcher = StrataFetcher::new("http://127.0.0.1:1/".to_string());
+        let report = evaluate_alerts(&fetcher, &repository, &thresholds)
+            .await
+            .unwrap();
+        assert_eq!(report.fullnode_error, None);
+        let alerts: Vec<_> = report
+            .alerts
+            .iter()
+            .map(|alert| (alert.kind, alert.checkpoint_idx, alert.threshold))
+            .collect();
+        assert_eq!(
+            alerts,
+            [
+                (AlertKind::StalledPending, 2, 300),
+                (AlertKind::StalledConfirmed, 1, 3600)
+            ]
+        );
+        assert!(report.alerts[0].value >= 600, "{:?}", report.alerts[0]);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn fails_when_the_checkpoints_cannot_be_read() {
+        use sea_orm::ConnectionTrait;
+
+        let repository = database::repository::sql::SqlRepository::open().await;
+        repository
+            .connection()
+            .execute_unprepared("DROP TABLE checkpoints")
+            .await
+            .unwrap();
+        let thresholds = AlertThresholds {
+            pending_seconds: Some(300),
+            ..Default::default()
+        };
+
+        let fetcher = FakeFullnode::default().serve();
+        assert!(evaluate_alerts(&fetcher, &repository, &thresholds)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query},
+    http::{request::Parts, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DbErr;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+/// Header carrying the id of a request, taken from the client when it sends one
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    /// Id of the request being served, see [`assign_request_id`]
+    static REQUEST_ID: String;
+}
+
+/// An API error, answered with its HTTP status and
+/// `{"error": {"code": ..., "message": ..., "request_id": ...}}`.
+///
+/// Codes are stable and meant for clients, messages are meant for humans and may change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Result of the API handlers
+pub type ApiResult = Result<Json<serde_json::Value>, ApiError>;
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// 400, the request cannot be parsed
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    /// 404, the requested resource does not exist
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    /// 422, the request is well-formed but its values cannot be served
+    pub fn unprocessable(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, code, message)
+    }
+
+    /// 503, a dependency of the API is not available
+    pub fn unavailable(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, code, message)
+    }
+
+    /// 503 when the database cannot be reached, 500 when a query failed. `context` is
+    /// logged along with the error, which is not disclosed to the client.
+    pub fn database(context: &str, error: DbErr) -> Self {
+        tracing::error!(request_id = ?request_id(), "{}: {:?}", context, error);
+        match error {
+            DbErr::ConnectionAcquire(_) | DbErr::Conn(_) => {
+                Self::unavailable("database_unavailable", "The database is unavailable")
+            }
+            _ => Self::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "database_error",
+                "The database query failed",
+            ),
+        }
+    }
+
+    /// 500, or the database error it wraps. `context` is logged along with the error, which
+    /// is not disclosed to the client.
+    pub fn internal(context: &str, error: anyhow::Error) -> Self {
+        match error.downcast::<DbErr>() {
+            Ok(error) => Self::database(context, error),
+            Err(error) => {
+                tracing::error!(request_id = ?request_id(), "{}: {:?}", context, error);
+                Self::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "internal_error",
+                    "Internal server error",
+                )
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": {
+                "code": self.code,
+                "message": self.message,
+                "request_id": request_id(),
+            }
+        });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Id of the request being served, `None` outside of [`assign_request_id`]
+fn request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Give every request an id, the one in the `X-Request-Id` header when the client sends a
+/// usable one, and return it in the same header. Errors report it in their body.
+pub async fn assign_request_id<B>(request: Request<B>, next: Next<B>) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map_or_else(|| uuid::Uuid::new_v4().to_string(), str::to_string);
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Query string extractor answering malformed parameters with a 400 [`ApiError`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Query::<T>::from_request_parts(parts, state).await {
+            Ok(Query(value)) => Ok(Self(value)),
+            Err(rejection) => Err(ApiError::bad_request(
+                "invalid_query",
+                rejection.body_text(),
+            )),
+        }
+    }
+}
+
+/// Path parameters extractor answering malformed segments with a 400 [`ApiError`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiPath<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(Self(value)),
+            Err(rejection) => Err(ApiError::bad_request("invalid_path", rejection.body_text())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::api_service::{app, AppState};
+    use axum::body::Body;
+    use database::connection::DatabaseWrapper;
+    use database::repository::memory::InMemoryRepository;
+    use fullnode_client::fetcher::StrataFetcher;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use sea_orm::{error::ConnAcquireErr, DatabaseConnection, RuntimeErr};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[test]
+    fn database_errors_are_unavailable_only_when_the_database_cannot_be_reached() {
+        let cases = [
+            (
+                DbErr::Conn(RuntimeErr::Internal("refused".to_string())),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "database_unavailable",
+            ),
+            (
+                DbErr::ConnectionAcquire(ConnAcquireErr::Timeout),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "database_unavailable",
+            ),
+            (
+                DbErr::Custom("broken query".to_string()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "database_error",
+            ),
+            (
+                DbErr::RecordNotFound("checkpoint".to_string()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "database_error",
+            ),
+        ];
+        for (error, status, code) in cases {
+            let description = format!("{:?}", error);
+            let error = ApiError::database("Failed to query", error);
+            assert_eq!(
+                (error.status, error.code),
+                (status, code),
+                "{}",
+                description
+            );
+        }
+    }
+
+    #[test]
+    fn internal_errors_report_the_database_error_they_wrap() {
+        let unreachable = anyhow::Error::from(DbErr::ConnectionAcquire(ConnAcquireErr::Timeout));
+        let error = ApiError::internal("Failed to query", unreachable);
+        assert_eq!(error.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error.code, "database_unavailable");
+
+        let failed = anyhow::Error::from(DbErr::Custom("broken query".to_string()))
+            .context("Failed to count checkpoints");
+        let error = ApiError::internal("Failed to query", failed);
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.code, "database_error");
+
+        let error = ApiError::internal("Failed to query", anyhow::anyhow!("fullnode timed out"));
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.code, "internal_error");
+        assert_eq!(error.message, "Internal server error");
+    }
+
+    /// The API over an empty repository, with a database that is never connected
+    fn test_app() -> axum::Router {
+        let state = AppState {
+            repository: Arc::new(InMemoryRepository::new()),
+            tasks: Default::default(),
+            leader: Default::default(),
+            database: Arc::new(DatabaseWrapper::from_connection(
+                DatabaseConnection::Disconnected,
+            )),
+            fetcher: Arc::new(StrataFetcher::new("http://127.0.0.1:1/".to_string())),
+            evm: None,
+            metrics: PrometheusBuilder::new().build_recorder().handle(),
+            alert_thresholds: Default::default(),
+        };
+        app(state)
+    }
+
+    /// Send a GET to the API, returns the status, the `X-Request-Id` header and the body
+    async fn get(uri: &str, request_id: Option<&str>) -> (StatusCode, String, serde_json::Value) {
+        let mut request = Request::get(uri);
+        if let Some(request_id) = request_id {
+            request = request.header(REQUEST_ID_HEADER, request_id);
+        }
+        let response = test_app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let header = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, header, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn errors_report_the_request_id_sent_by_the_client() {
+        let (status, header, body) = get("/api/checkpoints/7", Some("trace-42")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(header, "trace-42");
+        assert_eq!(
+            body,
+            json!({
+                "error": {
+                    "code": "checkpoint_not_found",
+                    "message": "Checkpoint 7 not found",
+                    "request_id": "trace-42",
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_without_a_usable_id_are_given_one() {
+        let too_long = "a".repeat(129);
+        for request_id in [None, Some(""), Some(too_long.as_str())] {
+            let (status, header, body) = get("/api/checkpoints/abc", request_id).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{:?}", request_id);
+            assert!(uuid::Uuid::parse_str(&header).is_ok(), "{:?}", header);
+            assert_eq!(body["error"]["code"], "invalid_path");
+            assert_eq!(body["error"]["request_id"], header.as_str());
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_database_is_reported_with_the_request_id() {
+        let (status, header, body) = get("/ready", Some("probe-1")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(header, "probe-1");
+        assert_eq!(body["error"]["code"], "not_ready");
+        assert_eq!(body["error"]["request_id"], "probe-1");
+    }
+
+    #[tokio::test]
+    async fn successful_responses_carry_the_request_id() {
+        let (status, header, body) = get("/health", Some("probe-2")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(header, "probe-2");
+        assert_eq!(body, json!({ "result": "ok" }));
+    }
+}
@@ -1,65 +1,676 @@
 // services/api_service.rs
 use super::QueryParams;
 use super::SearchQuery;
+use super::{CheckpointQuery, WindowQuery, WithdrawalQuery};
+use crate::services::alert_service::{evaluate_alerts, record_index_gauges, AlertThresholds};
+use crate::services::api_error::{assign_request_id, ApiError, ApiPath, ApiQuery, ApiResult};
+use crate::services::bridge_service::{checkpoint_bridge_intents, withdrawal_status};
+use crate::services::indexer_service::LeaderStatus;
+use crate::services::lifecycle_service::lifecycle_stats;
+use crate::services::resource_service::{
+    block_resource, block_resource_by_hash, checkpoint_blocks, checkpoint_path,
+    checkpoint_resource, latest_checkpoint_resource,
+};
+use crate::utils::metrics::track_api_request;
+use crate::utils::supervisor::TaskHealthRegistry;
 use axum::{
-    extract::{Query, State},
-    Json,
+    extract::{FromRef, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware,
+    routing::get,
+    Json, Router,
+};
+use database::connection::{DatabaseWrapper, MigrationStatus};
+use database::repository::{
+    BlockFetchJobRepository, BlockRepository, BridgeIntentRepository, CheckpointRepository,
+    EvmBlockRepository, IntegrityRepository, L1BlockRepository, Repository, SyncStateRepository,
+    WebhookDeliveryRepository,
 };
-use database::connection::DatabaseWrapper;
-use database::services::checkpoint_service::CheckpointService;
+use database::services::pagination::PaginatedData;
+use database::services::utils::unix_timestamp;
+use fullnode_client::evm::{with_hex_prefix, EvmClient};
+use fullnode_client::fetcher::StrataFetcher;
 use hex;
+use metrics_exporter_prometheus::PrometheusHandle;
+use model::checkpoint::{CheckpointLifecycle, CheckpointState};
 use model::pgu64::PgU64;
+use model::sync_state::{RpcSyncCursor, SyncCursor};
+use sea_orm::DbErr;
 use serde_json::json;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-pub async fn checkpoints(
-    State(database): State<Arc<DatabaseWrapper>>,
-    Query(params): Query<QueryParams>,
-) -> Json<serde_json::Value> {
-    let current_page = params.p.unwrap_or(1);
-    let page_size = params.ps.unwrap_or(10);
+use std::time::Duration;
+
+/// Path the API routes are served under
+pub const API_PREFIX: &str = "/api";
+
+/// How long the sync status waits for the fullnode before reporting it unreachable
+const FULLNODE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest page size of the paginated listings
+const MAX_PAGE_SIZE: u64 = 100;
+
+/// Window of the lifecycle statistics when none is requested, in seconds
+const DEFAULT_LIFECYCLE_WINDOW: u64 = 24 * 3600;
+
+/// State shared by the API handlers
+pub struct AppState<R> {
+    pub repository: Arc<R>,
+    pub tasks: TaskHealthRegistry,
+    /// Leader election state of the indexer of this process
+    pub leader: LeaderStatus,
+    /// Connections checked by the readiness probe
+    pub database: Arc<DatabaseWrapper>,
+    pub fetcher: Arc<StrataFetcher>,
+    /// Execution client resolving transaction hashes in searches, if configured
+    pub evm: Option<Arc<EvmClient>>,
+    /// Renders the Prometheus metrics
+    pub metrics: PrometheusHandle,
+    /// Thresholds of the alerts reported by `/alerts`
+    pub alert_thresholds: AlertThresholds,
+}
+
+impl<R> Clone for AppState<R> {
+    fn clone(&self) -> Self {
+        Self {
+            repository: self.repository.clone(),
+            tasks: self.tasks.clone(),
+            leader: self.leader.clone(),
+            database: self.database.clone(),
+            fetcher: self.fetcher.clone(),
+            evm: self.evm.clone(),
+            metrics: self.metrics.clone(),
+            alert_thresholds: self.alert_thresholds,
+        }
+    }
+}
+
+impl<R> FromRef<AppState<R>> for Arc<R> {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.repository.clone()
+    }
+}
+
+impl<R> FromRef<AppState<R>> for TaskHealthRegistry {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.tasks.clone()
+    }
+}
+
+impl<R> FromRef<AppState<R>> for LeaderStatus {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.leader.clone()
+    }
+}
+
+/// The probes and the API routes over `state`, every request tracked and given an id
+pub fn app<R: Repository + 'static>(state: AppState<R>) -> Router {
+    Router::new()
+        .merge(service_router())
+        .nest(API_PREFIX, router())
+        .route_layer(middleware::from_fn(track_api_request))
+        .layer(middleware::from_fn(assign_request_id))
+        .with_state(state)
+}
+
+/// The probes, the metrics and the health of the background tasks, served by a process that
+/// does not serve the API
+pub fn service_app<R: Repository + 'static>(state: AppState<R>) -> Router {
+    Router::new()
+        .merge(service_router())
+        .route("/tasks", get(tasks))
+        .route_layer(middleware::from_fn(track_api_request))
+        .layer(middleware::from_fn(assign_request_id))
+        .with_state(state)
+}
+
+/// Liveness and readiness probes, the leader state and the Prometheus metrics, served
+/// outside of `/api`
+pub fn service_router<R: Repository + 'static>() -> Router<AppState<R>> {
+    Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready::<R>))
+        .route("/leader", get(leader))
+        .route("/metrics", get(metrics::<R>))
+}
+
+/// API routes, served from any checkpoint repository under [`API_PREFIX`]
+pub fn router<R: Repository + 'static>() -> Router<AppState<R>> {
+    Router::new()
+        .route("/checkpoints", get(checkpoints::<R>))
+        .route("/checkpoints/latest", get(latest_checkpoint::<R>))
+        .route("/checkpoints/:idx", get(checkpoint_by_idx::<R>))
+        .route("/checkpoints/:idx/blocks", get(checkpoint_blocks_page::<R>))
+        .route("/blocks/:height", get(block_by_height::<R>))
+        .route("/blocks/hash/:hash", get(block_by_hash::<R>))
+        .route("/checkpoint", get(checkpoint::<R>))
+        .route("/search", get(search::<R>))
+        .route("/reorgs", get(reorgs::<R>))
+        .route("/sync-status", get(sync_status::<R>))
+        .route("/integrity", get(integrity::<R>))
+        .route("/alerts", get(alerts::<R>))
+        .route("/checkpoint-lifecycle", get(checkpoint_lifecycle::<R>))
+        .route("/lifecycle-stats", get(lifecycle_statistics::<R>))
+        .route("/checkpoint-bridge", get(checkpoint_bridge::<R>))
+        .route("/withdrawal", get(withdrawal::<R>))
+        .route("/tasks", get(tasks))
+}
+
+pub async fn checkpoints<R: CheckpointRepository>(
+    State(repository): State<Arc<R>>,
+    ApiQuery(params): ApiQuery<QueryParams>,
+) -> ApiResult {
+    let (current_page, page_size) = page_params(&params)?;
     let error_msg = params.error_msg.clone();
     tracing::debug!("error_msg: {:?}", error_msg);
 
-    let checkpoint_db = CheckpointService::new(&database.db);
-    let paginated_data = checkpoint_db
+    let paginated_data = repository
         .get_paginated_checkpoints(current_page, page_size, 1, None) // Set absolute_first_page to 1 for checkpoint tables
-        .await;
-    Json(json!({ "result": paginated_data }))
-}
-
-pub async fn checkpoint(
-    State(database): State<Arc<DatabaseWrapper>>,
-    Query(params): Query<QueryParams>,
-) -> Json<serde_json::Value> {
-    let current_page = params.p.unwrap_or(0); // Default to page 0
-    let page_size = 1; // Set constant page size=1 for detail page
-
-    let checkpoint_db = CheckpointService::new(&database.db);
-    // Get paginated checkpoints
-    let mut paginated_data = checkpoint_db
-        .get_paginated_checkpoints(current_page, page_size, 0, Some("asc"))
-        .await;
-    paginated_data.total_pages -= 1; // Adjust total pages for 0-based indexing
-    Json(json!({ "result": paginated_data }))
-}
-
-pub async fn search(
-    State(database): State<Arc<DatabaseWrapper>>,
-    Query(params): Query<SearchQuery>,
-) -> Json<serde_json::Value> {
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch checkpoints", e))?;
+    Ok(Json(json!({ "result": paginated_data })))
+}
+
+/// Deprecated, the checkpoint at position `p` (from 0) in ascending order as a page of one,
+/// `total_pages` being the last position. A position past the last checkpoint is an empty
+/// page. Kept unchanged for existing clients, `/checkpoints/{idx}` replaces it.
+pub async fn checkpoint<R: CheckpointRepository>(
+    State(repository): State<Arc<R>>,
+    ApiQuery(params): ApiQuery<QueryParams>,
+) -> Result<(HeaderMap, Json<serde_json::Value>), ApiError> {
+    let position = params.p.unwrap_or(0);
+
+    let mut paginated_data = if i64::try_from(position).is_ok() {
+        repository
+            .get_paginated_checkpoints(position, 1, 0, Some("asc"))
+            .await
+            .map_err(|e| ApiError::database("Failed to fetch the checkpoint", e))?
+    } else {
+        // too far for the database to skip, there is nothing there
+        PaginatedData {
+            current_page: position,
+            total_pages: repository.get_total_checkpoint_count().await,
+            absolute_first_page: 0,
+            items: Vec::new(),
+        }
+    };
+    paginated_data.total_pages = paginated_data.total_pages.saturating_sub(1);
+
+    let mut headers = HeaderMap::new();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Some(checkpoint) = paginated_data.items.first() {
+        let successor = format!(
+            "<{}>; rel=\"successor-version\"",
+            checkpoint_path(checkpoint.idx)
+        );
+        headers.insert(header::LINK, HeaderValue::from_str(&successor).unwrap());
+    }
+    Ok((headers, Json(json!({ "result": paginated_data }))))
+}
+
+/// A checkpoint, linked to its neighbours and its blocks
+pub async fn checkpoint_by_idx<R: CheckpointRepository>(
+    State(repository): State<Arc<R>>,
+    ApiPath(idx): ApiPath<u64>,
+) -> ApiResult {
+    match checkpoint_resource(repository.as_ref(), idx)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch the checkpoint", e))?
+    {
+        Some(checkpoint) => Ok(Json(json!({ "result": checkpoint }))),
+        None => Err(checkpoint_not_found(idx)),
+    }
+}
+
+/// The highest stored checkpoint, linked like `/checkpoints/{idx}`
+pub async fn latest_checkpoint<R: CheckpointRepository + SyncStateRepository>(
+    State(repository): State<Arc<R>>,
+) -> ApiResult {
+    match latest_checkpoint_resource(repository.as_ref())
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch the latest checkpoint", e))?
+    {
+        Some(checkpoint) => Ok(Json(json!({ "result": checkpoint }))),
+        None => Err(ApiError::not_found(
+            "checkpoint_not_found",
+            "No checkpoint is indexed yet",
+        )),
+    }
+}
+
+/// A page of the blocks of a checkpoint, linked to the previous and next pages
+pub async fn checkpoint_blocks_page<R: CheckpointRepository + BlockRepository>(
+    State(repository): State<Arc<R>>,
+    ApiPath(idx): ApiPath<u64>,
+    ApiQuery(params): ApiQuery<QueryParams>,
+) -> ApiResult {
+    let (current_page, page_size) = page_params(&params)?;
+    match checkpoint_blocks(repository.as_ref(), idx, current_page, page_size)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch the checkpoint blocks", e))?
+    {
+        Some(blocks) => Ok(Json(json!({ "result": blocks }))),
+        None => Err(checkpoint_not_found(idx)),
+    }
+}
+
+/// A block by height, linked to its neighbours and its checkpoint
+pub async fn block_by_height<R: BlockRepository>(
+    State(repository): State<Arc<R>>,
+    ApiPath(height): ApiPath<u64>,
+) -> ApiResult {
+    match block_resource(repository.as_ref(), height)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch the block", e))?
+    {
+        Some(block) => Ok(Json(json!({ "result": block }))),
+        None => Err(ApiError::not_found(
+            "block_not_found",
+            format!("Block {} not found", height),
+        )),
+    }
+}
+
+/// A block by hash, with or without `0x`, linked like `/blocks/{height}`
+pub async fn block_by_hash<R: BlockRepository>(
+    State(repository): State<Arc<R>>,
+    ApiPath(hash): ApiPath<String>,
+) -> ApiResult {
+    let block_hash = hash.trim().trim_start_matches("0x").to_ascii_lowercase();
+    if block_hash.len() != 64 || hex::decode(&block_hash).is_err() {
+        return Err(ApiError::unprocessable(
+            "invalid_block_hash",
+            "Expected a 32-byte hex hash",
+        ));
+    }
+    match block_resource_by_hash(repository.as_ref(), &block_hash)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch the block", e))?
+    {
+        Some(block) => Ok(Json(json!({ "result": block }))),
+        None => Err(ApiError::not_found(
+            "block_not_found",
+            format!("Block {} not found", hash),
+        )),
+    }
+}
+
+pub async fn reorgs<R: CheckpointRepository>(
+    State(repository): State<Arc<R>>,
+    ApiQuery(params): ApiQuery<QueryParams>,
+) -> ApiResult {
+    let (current_page, page_size) = page_params(&params)?;
+
+    let paginated_data = repository
+        .get_paginated_reorg_events(current_page, page_size)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch reorg events", e))?;
+    Ok(Json(json!({ "result": paginated_data })))
+}
+
+/// Page and page size of a paginated listing, both starting at 1. The page must start at an
+/// offset the database can address.
+fn page_params(params: &QueryParams) -> Result<(u64, u64), ApiError> {
+    match (params.p.unwrap_or(1), params.ps.unwrap_or(10)) {
+        (0, _) => Err(ApiError::unprocessable(
+            "invalid_page",
+            "Pages are numbered from 1",
+        )),
+        (_, page_size) if page_size == 0 || page_size > MAX_PAGE_SIZE => {
+            Err(ApiError::unprocessable(
+                "invalid_page_size",
+                format!("The page size must be between 1 and {}", MAX_PAGE_SIZE),
+            ))
+        }
+        (current_page, page_size)
+            if (current_page - 1)
+                .checked_mul(page_size)
+                .is_none_or(|offset| i64::try_from(offset).is_err()) =>
+        {
+            Err(ApiError::unprocessable(
+                "invalid_page",
+                format!("Page {} is out of range", current_page),
+            ))
+        }
+        (current_page, page_size) => Ok((current_page, page_size)),
+    }
+}
+
+/// How far the index is behind the fullnode, what the indexer has stored so far and when
+/// each background task last completed its work
+pub async fn sync_status<
+    R: CheckpointRepository
+        + SyncStateRepository
+        + BlockFetchJobRepository
+        + WebhookDeliveryRepository,
+>(
+    State(state): State<AppState<R>>,
+) -> ApiResult {
+    let db_error = |e: DbErr| ApiError::database("Failed to fetch the sync status", e);
+    let repository = state.repository.as_ref();
+    let cursors = repository.get_sync_cursors().await.map_err(db_error)?;
+    let index_range = repository.get_index_range().await.map_err(db_error)?;
+    let block_fetch_jobs = repository
+        .get_block_fetch_job_counts()
+        .await
+        .map_err(db_error)?;
+    let webhook_deliveries = repository
+        .get_webhook_delivery_counts()
+        .await
+        .map_err(db_error)?;
+
+    let (fullnode_tip, fullnode_error) = match fullnode_checkpoint_tip(&state.fetcher).await {
+        Ok(tip) => (tip, None),
+        Err(e) => (None, Some(e)),
+    };
+    let checkpoint_lag = fullnode_tip.map(|tip| index_range.checkpoint_lag(tip));
+    let last_checkpoint_end = match index_range.last_checkpoint {
+        Some(last) => repository
+            .get_checkpoint_by_idx(PgU64(last).to_i64())
+            .await
+            .map_err(db_error)?
+            .map(|checkpoint| checkpoint.l2_range.1),
+        None => None,
+    };
+    // before the first block is stored the whole L2 range of the index is missing
+    let first_missing_block = match (index_range.last_block, index_range.first_checkpoint) {
+        (Some(last), _) => Some(last + 1),
+        (None, Some(first)) => repository
+            .get_checkpoint_by_idx(PgU64(first).to_i64())
+            .await
+            .map_err(db_error)?
+            .map(|checkpoint| checkpoint.l2_range.0),
+        (None, None) => None,
+    };
+    let block_lag = match (last_checkpoint_end, first_missing_block) {
+        (Some(end), Some(first_missing)) => Some((end + 1).saturating_sub(first_missing)),
+        _ => None,
+    };
+
+    let reconciled = cursors
+        .iter()
+        .find(|cursor| cursor.name == SyncCursor::CheckpointReconciled.name())
+        .map(|cursor| (PgU64::i64_to_u64(cursor.value), cursor.updated_at));
+    let unfinalized_statuses: Vec<&str> = CheckpointState::ALL
+        .iter()
+        .filter(|state| !state.is_final())
+        .map(CheckpointState::status)
+        .collect();
+    let unfinalized = repository
+        .get_checkpoint_idxs_by_status(&unfinalized_statuses)
+        .await
+        .map_err(db_error)?;
+    let now = unix_timestamp();
+
+    let tasks: BTreeMap<&str, serde_json::Value> = state
+        .tasks
+        .snapshot()
+        .into_iter()
+        .map(|(name, health)| {
+            let task = json!({
+                "state": health.state,
+                "last_success_at": health.last_success_at,
+            });
+            (name, task)
+        })
+        .collect();
+    let cursors: Vec<RpcSyncCursor> = cursors.into_iter().map(Into::into).collect();
+
+    Ok(Json(json!({
+        "result": {
+            "fullnode": {
+                "checkpoint_tip": fullnode_tip,
+                "error": fullnode_error,
+            },
+            "local": {
+                "checkpoint_tip": index_range.last_checkpoint,
+                "block_tip": index_range.last_block,
+            },
+            "lag": {
+                "checkpoints": checkpoint_lag,
+                "blocks": block_lag,
+            },
+            "reconciler": {
+                "last_checkpoint": reconciled.map(|(idx, _)| idx),
+                "last_reconciled_at": reconciled.map(|(_, at)| at),
+                "seconds_since_last_reconciliation":
+                    reconciled.map(|(_, at)| now.saturating_sub(at)),
+                "unfinalized_checkpoints": unfinalized.len(),
+                "oldest_unfinalized_checkpoint":
+                    unfinalized.first().copied().map(PgU64::i64_to_u64),
+            },
+            "tasks": tasks,
+            "cursors": cursors,
+            "index_range": index_range,
+            "block_fetch_jobs": block_fetch_jobs,
+            "webhook_deliveries": webhook_deliveries,
+        }
+    })))
+}
+
+/// Checkpoints stalled in pending or confirmed and the gap to the fullnode tip, evaluated
+/// against the configured thresholds on every request
+pub async fn alerts<R: CheckpointRepository + SyncStateRepository>(
+    State(state): State<AppState<R>>,
+) -> ApiResult {
+    let report = evaluate_alerts(
+        &state.fetcher,
+        state.repository.as_ref(),
+        &state.alert_thresholds,
+    )
+    .await
+    .map_err(|e| ApiError::internal("Failed to evaluate the alerts", e))?;
+    Ok(Json(json!({
+        "result": {
+            "thresholds": state.alert_thresholds,
+            "alerts": report.alerts,
+            "fullnode_error": report.fullnode_error,
+        }
+    })))
+}
+
+/// When a checkpoint was first indexed, confirmed and finalized, and the durations between
+/// these steps
+pub async fn checkpoint_lifecycle<R: CheckpointRepository>(
+    State(repository): State<Arc<R>>,
+    ApiQuery(params): ApiQuery<CheckpointQuery>,
+) -> ApiResult {
+    let idx = PgU64(params.idx).to_i64();
+    let checkpoints = repository
+        .get_checkpoints_in_range(idx, idx)
+        .await
+        .map_err(|e| ApiError::database("Failed to fetch the checkpoint", e))?;
+    match checkpoints.first() {
+        Some(checkpoint) => Ok(Json(
+            json!({ "result": CheckpointLifecycle::from(checkpoint) }),
+        )),
+        None => Err(checkpoint_not_found(params.idx)),
+    }
+}
+
+/// Bridge deposits and withdrawals of the blocks of a checkpoint
+pub async fn checkpoint_bridge<R: CheckpointRepository + BridgeIntentRepository>(
+    State(repository): State<Arc<R>>,
+    ApiQuery(params): ApiQuery<CheckpointQuery>,
+) -> ApiResult {
+    let idx = PgU64(params.idx).to_i64();
+    match checkpoint_bridge_intents(repository.as_ref(), idx)
+        .await
+        .map_err(|e| ApiError::internal("Failed to fetch the bridge intents", e))?
+    {
+        Some(intents) => Ok(Json(json!({ "result": intents }))),
+        None => Err(checkpoint_not_found(params.idx)),
+    }
+}
+
+/// The withdrawals requested by an EL transaction, with the checkpoint containing them and
+/// whether it is finalized
+pub async fn withdrawal<R: CheckpointRepository + BridgeIntentRepository>(
+    State(repository): State<Arc<R>>,
+    ApiQuery(params): ApiQuery<WithdrawalQuery>,
+) -> ApiResult {
+    let withdrawals = withdrawal_status(repository.as_ref(), params.txid.trim())
+        .await
+        .map_err(|e| ApiError::internal("Failed to fetch the withdrawal", e))?;
+    if withdrawals.is_empty() {
+        return Err(ApiError::not_found(
+            "withdrawal_not_found",
+            format!("No withdrawal found for transaction {}", params.txid.trim()),
+        ));
+    }
+    Ok(Json(json!({ "result": withdrawals })))
+}
+
+/// p50, p95 and maximum of the lifecycle durations of the checkpoints confirmed or finalized
+/// within the last `window` seconds
+pub async fn lifecycle_statistics<R: CheckpointRepository>(
+    State(repository): State<Arc<R>>,
+    ApiQuery(params): ApiQuery<WindowQuery>,
+) -> ApiResult {
+    let window = params.window.unwrap_or(DEFAULT_LIFECYCLE_WINDOW);
+    let stats = lifecycle_stats(repository.as_ref(), window, unix_timestamp())
+        .await
+        .map_err(|e| ApiError::internal("Failed to compute the lifecycle statistics", e))?;
+    Ok(Json(json!({ "result": stats })))
+}
+
+fn checkpoint_not_found(idx: u64) -> ApiError {
+    ApiError::not_found(
+        "checkpoint_not_found",
+        format!("Checkpoint {} not found", idx),
+    )
+}
+
+/// Latest checkpoint of the fullnode, or why it could not be asked
+pub(crate) async fn fullnode_checkpoint_tip(
+    fetcher: &StrataFetcher,
+) -> Result<Option<u64>, String> {
+    match tokio::time::timeout(
+        FULLNODE_TIMEOUT,
+        fetcher.get_latest_index("strata_getLatestCheckpointIndex"),
+    )
+    .await
+    {
+        Ok(Ok(tip)) => Ok(tip),
+        Ok(Err(e)) => {
+            tracing::warn!("Failed to fetch the fullnode tip: {:?}", e);
+            Err("Failed to reach the fullnode".to_string())
+        }
+        Err(_) => Err(format!(
+            "The fullnode did not answer within {:?}",
+            FULLNODE_TIMEOUT
+        )),
+    }
+}
+
+/// The process is up and serving requests
+pub async fn health() -> Json<serde_json::Value> {
+    Json(json!({ "result": "ok" }))
+}
+
+/// The database answers and its schema matches this binary, 503 otherwise
+pub async fn ready<R>(State(state): State<AppState<R>>) -> ApiResult {
+    let database = state.database.as_ref();
+    let not_ready = match database.ping().await {
+        Err(e) => {
+            tracing::warn!("Database is unreachable: {:?}", e);
+            Some("Database is unreachable".to_string())
+        }
+        Ok(()) => match database.migration_status().await {
+            Ok(MigrationStatus::UpToDate) => None,
+            Ok(MigrationStatus::Pending(versions)) => {
+                Some(format!("Pending migrations: {}", versions.join(", ")))
+            }
+            Ok(MigrationStatus::Unknown(versions)) => {
+                Some(format!("Unknown migrations: {}", versions.join(", ")))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read the applied migrations: {:?}", e);
+                Some("Failed to read the applied migrations".to_string())
+            }
+        },
+    };
+    match not_ready {
+        None => Ok(Json(json!({ "result": "ready" }))),
+        Some(error) => Err(ApiError::unavailable("not_ready", error)),
+    }
+}
+
+/// Prometheus metrics. The tips and the checkpoint counts are refreshed first, so that they
+/// are reported whether or not this process runs the indexer.
+pub async fn metrics<R: CheckpointRepository + SyncStateRepository>(
+    State(state): State<AppState<R>>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    record_index_gauges(&state.fetcher, state.repository.as_ref()).await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
+
+/// Missing checkpoints and blocks as currently stored, see the integrity checker task
+pub async fn integrity<R: IntegrityRepository>(State(repository): State<Arc<R>>) -> ApiResult {
+    let report = repository
+        .get_integrity_report()
+        .await
+        .map_err(|e| ApiError::database("Failed to check data integrity", e))?;
+    Ok(Json(json!({ "result": report })))
+}
+
+/// Whether this process runs the indexer, stands by or lost the leader lock
+pub async fn leader(State(leader): State<LeaderStatus>) -> Json<serde_json::Value> {
+    Json(json!({ "result": leader.snapshot() }))
+}
+
+/// Health of the background tasks
+pub async fn tasks(State(tasks): State<TaskHealthRegistry>) -> Json<serde_json::Value> {
+    Json(json!({ "result": tasks.snapshot() }))
+}
+
+/// Checkpoint containing an L2 block, by height or hash, the L2 block of an EVM block, by
+/// number or hash, a bridge withdrawal or an EVM transaction, or covering an L1 block, by
+/// height or hash. L2 blocks take precedence over EVM blocks, which take precedence over L1
+/// blocks.
+pub async fn search<R>(
+    State(state): State<AppState<R>>,
+    ApiQuery(params): ApiQuery<SearchQuery>,
+) -> ApiResult
+where
+    R: CheckpointRepository + L1BlockRepository + EvmBlockRepository + BridgeIntentRepository,
+{
+    let db_error = |e: DbErr| ApiError::database("Failed to search", e);
+    let repository = state.repository.as_ref();
     let mut query = params.query.trim();
-    let checkpoint_db = CheckpointService::new(&database.db);
+    let mut valid = false;
 
     // Check if it's a valid block number
     if let Ok(block_number) = query.parse::<u64>() {
         tracing::info!("Search request for block number: {}", block_number);
+        valid = true;
         let block_number = PgU64(block_number).to_i64();
-        if let Ok(Some(checkpoint_idx)) = checkpoint_db
+        if let Some(checkpoint_idx) = repository
             .get_checkpoint_idx_by_block_height(block_number)
             .await
+            .map_err(db_error)?
         {
             let checkpoint_idx = PgU64::from_i64(checkpoint_idx).0;
-            return Json(json!({"result": checkpoint_idx}));
+            return Ok(Json(json!({"result": checkpoint_idx})));
+        }
+        if let Some(evm_block) = repository
+            .get_evm_block_by_number(block_number)
+            .await
+            .map_err(db_error)?
+        {
+            let checkpoint_idx = PgU64::from_i64(evm_block.checkpoint_idx).0;
+            return Ok(Json(json!({"result": checkpoint_idx})));
+        }
+        if let Some(l1_block) = repository
+            .get_l1_block_by_height(block_number)
+            .await
+            .map_err(db_error)?
+        {
+            let checkpoint_idx = PgU64::from_i64(l1_block.checkpoint_idx).0;
+            return Ok(Json(json!({"result": checkpoint_idx})));
         }
     }
 
@@ -68,20 +679,568 @@ pub async fn search(
         query = query.trim_start_matches("0x");
     }
 
-    // Check if the length is 64 characters (32 bytes)
-    if query.len() == 64 {
-        // Check if it's a valid hex string
-        if hex::decode(query).is_ok() {
-            tracing::info!("Search request for block hash: {}", query);
-            if let Ok(Some(checkpoint_idx)) =
-                checkpoint_db.get_checkpoint_idx_by_block_hash(query).await
-            {
-                let checkpoint_idx = PgU64::from_i64(checkpoint_idx).0;
-                return Json(json!({"result": checkpoint_idx}));
-            } else {
-                tracing::info!("No checkpoint found for block hash: {}", query);
+    // Check if the length is 64 characters (32 bytes) and it's a valid hex string
+    if query.len() == 64 && hex::decode(query).is_ok() {
+        tracing::info!("Search request for block hash: {}", query);
+        valid = true;
+        if let Some(checkpoint_idx) = repository
+            .get_checkpoint_idx_by_block_hash(query)
+            .await
+            .map_err(db_error)?
+        {
+            let checkpoint_idx = PgU64::from_i64(checkpoint_idx).0;
+            return Ok(Json(json!({"result": checkpoint_idx})));
+        }
+        if let Some(evm_block) = repository
+            .get_evm_block_by_hash(&with_hex_prefix(&query.to_ascii_lowercase()))
+            .await
+            .map_err(db_error)?
+        {
+            let checkpoint_idx = PgU64::from_i64(evm_block.checkpoint_idx).0;
+            return Ok(Json(json!({"result": checkpoint_idx})));
+        }
+        if let Some(l1_block) = repository
+            .get_l1_block_by_hash(query)
+            .await
+            .map_err(db_error)?
+        {
+            let checkpoint_idx = PgU64::from_i64(l1_block.checkpoint_idx).0;
+            return Ok(Json(json!({"result": checkpoint_idx})));
+        }
+        if let Some(checkpoint_idx) = withdrawal_checkpoint(repository, query).await? {
+            return Ok(Json(json!({"result": checkpoint_idx})));
+        }
+        if let Some(checkpoint_idx) = evm_transaction_checkpoint(&state, query).await? {
+            return Ok(Json(json!({"result": checkpoint_idx})));
+        }
+        tracing::info!("No checkpoint found for block hash: {}", query);
+    }
+
+    if valid {
+        Err(ApiError::not_found(
+            "search_no_match",
+            format!("Nothing indexed matches {}", params.query.trim()),
+        ))
+    } else {
+        Err(ApiError::unprocessable(
+            "invalid_search_query",
+            "Expected a block number or a 32-byte hex hash",
+        ))
+    }
+}
+
+/// Checkpoint containing a withdrawal requested by transaction `txid`
+async fn withdrawal_checkpoint<R: CheckpointRepository + BridgeIntentRepository>(
+    repository: &R,
+    txid: &str,
+) -> Result<Option<u64>, ApiError> {
+    let withdrawals = withdrawal_status(repository, txid)
+        .await
+        .map_err(|e| ApiError::internal("Failed to search withdrawals", e))?;
+    Ok(withdrawals
+        .into_iter()
+        .find_map(|withdrawal| withdrawal.checkpoint_idx))
+}
+
+/// Checkpoint containing the EVM transaction `tx_hash`, located by the execution client.
+/// Failures of the execution client are logged and treated as no match.
+async fn evm_transaction_checkpoint<R: EvmBlockRepository>(
+    state: &AppState<R>,
+    tx_hash: &str,
+) -> Result<Option<u64>, ApiError> {
+    let Some(evm) = state.evm.as_ref() else {
+        return Ok(None);
+    };
+    let block_number = match evm.get_transaction_block_number(tx_hash).await {
+        Ok(Some(block_number)) => block_number,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            tracing::warn!("Failed to look up EVM transaction {}: {}", tx_hash, e);
+            return Ok(None);
+        }
+    };
+    let evm_block = state
+        .repository
+        .get_evm_block_by_number(PgU64(block_number).to_i64())
+        .await
+        .map_err(|e| ApiError::database("Failed to search", e))?;
+    Ok(evm_block.map(|evm_block| PgU64::from_i64(evm_block.checkpoint_idx).0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webhook_service::WebhookNotifier;
+    use crate::utils::fake_fullnode::{
+        block_hash, block_header, checkpoint_info, l1_block_hash, FakeFullnode,
+    };
+    use crate::utils::metrics::test_recorder;
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use database::repository::memory::InMemoryRepository;
+    use sea_orm::DatabaseConnection;
+    use tower::ServiceExt;
+
+    /// State over `repository` and `fullnode`, with a database that is never connected
+    fn state<R>(repository: R, fullnode: &FakeFullnode) -> AppState<R> {
+        AppState {
+            repository: Arc::new(repository),
+            tasks: Default::default(),
+            leader: Default::default(),
+            database: Arc::new(DatabaseWrapper::from_connection(
+                DatabaseConnection::Disconnected,
+            )),
+            fetcher: Arc::new(fullnode.serve()),
+            evm: None,
+            metrics: test_recorder(),
+            alert_thresholds: Default::default(),
+        }
+    }
+
+    async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn scrapes_refresh_the_index_gauges() {
+        let repository = InMemoryRepository::new();
+        let notifier = WebhookNotifier::default();
+        repository
+            .insert_checkpoint(checkpoint_info(0, (0, 4), "finalized", "aa"), &notifier)
+            .await;
+        repository
+            .insert_checkpoint(checkpoint_info(1, (5, 9), "pending", "-"), &notifier)
+            .await;
+        for height in 0..=4 {
+            repository
+                .insert_block(block_header(height), PgU64(0).to_i64())
+                .await
+                .unwrap();
+        }
+        let fullnode = FakeFullnode::default();
+        for idx in 0..=3 {
+            fullnode.add_checkpoint(idx, (5 * idx, 5 * idx + 4), "pending", "-");
+        }
+
+        let (status, body) = get(service_app(state(repository, &fullnode)), "/metrics").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let series: Vec<&str> = body.lines().collect();
+        for expected in [
+            "explorer_fullnode_checkpoint_tip 3",
+            "explorer_local_checkpoint_tip 1",
+            "explorer_local_block_tip 4",
+            "explorer_checkpoints{status=\"finalized\"} 1",
+            "explorer_checkpoints{status=\"pending\"} 1",
+            "explorer_checkpoints{status=\"confirmed\"} 0",
+        ] {
+            assert!(
+                series.contains(&expected),
+                "{} missing from\n{}",
+                expected,
+                body
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn the_service_app_serves_the_probes_and_the_indexer_state_without_the_api() {
+        let fullnode = FakeFullnode::default();
+        let app = service_app(state(InMemoryRepository::new(), &fullnode));
+
+        assert_eq!(get(app.clone(), "/health").await.0, StatusCode::OK);
+        let (status, body) = get(app.clone(), "/leader").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            json!({ "result": { "state": "disabled", "since": null } })
+        );
+        let (status, body) = get(app.clone(), "/tasks").await;
+        assert_eq!(
+            (status, body.as_str()),
+            (StatusCode::OK, r#"{"result":{}}"#)
+        );
+        assert_eq!(get(app, "/api/checkpoints").await.0, StatusCode::NOT_FOUND);
+    }
+
+    /// Checkpoint 0 over the L2 blocks 0 to 4, the L2 block 2 linked to the EVM block 1002
+    async fn repository_with_an_evm_block() -> InMemoryRepository {
+        let repository = InMemoryRepository::new();
+        let checkpoint = checkpoint_info(0, (0, 4), "finalized", "aa");
+        repository
+            .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+            .await;
+        for height in 0..=4 {
+            repository
+                .insert_block(block_header(height), PgU64(0).to_i64())
+                .await
+                .unwrap();
+        }
+        let evm_block = model::evm_block::Model {
+            l2_height: PgU64(2).to_i64(),
+            evm_block_number: PgU64(1002).to_i64(),
+            evm_block_hash: format!("0x{}", "ab".repeat(32)),
+            checkpoint_idx: PgU64(0).to_i64(),
+        };
+        repository.insert_evm_blocks(vec![evm_block]).await.unwrap();
+        repository
+    }
+
+    async fn search<R: Repository + 'static>(state: AppState<R>, query: &str) -> serde_json::Value {
+        let (_, body) = get(app(state), &format!("/api/search?query={}", query)).await;
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn searches_evm_blocks_by_number_and_hash() {
+        let fullnode = FakeFullnode::default();
+        let state = state(repository_with_an_evm_block().await, &fullnode);
+        let hash = "ab".repeat(32);
+
+        for query in [
+            "1002".to_string(),
+            hash.clone(),
+            format!("0x{}", hash),
+            hash.to_uppercase(),
+        ] {
+            let body = search(state.clone(), &query).await;
+            assert_eq!(body, json!({ "result": 0 }), "{}", query);
+        }
+        let body = search(state, "1003").await;
+        assert_eq!(body["error"]["code"], "search_no_match");
+    }
+
+    #[tokio::test]
+    async fn searches_evm_transactions_through_the_execution_client() {
+        let fullnode = FakeFullnode::default();
+        let included = format!("0x{}", "cd".repeat(32));
+        let pending = format!("0x{}", "ef".repeat(32));
+        {
+            let mut chain = fullnode.chain();
+            chain
+                .evm_transactions
+                .insert(included.clone(), json!({ "blockNumber": "0x3ea" }));
+            chain
+                .evm_transactions
+                .insert(pending.clone(), json!({ "blockNumber": null }));
+        }
+        let mut state = state(repository_with_an_evm_block().await, &fullnode);
+        state.evm = Some(Arc::new(fullnode.serve_evm()));
+
+        assert_eq!(
+            search(state.clone(), &included).await,
+            json!({ "result": 0 })
+        );
+        for query in [pending, format!("0x{}", "12".repeat(32))] {
+            let body = search(state.clone(), &query).await;
+            assert_eq!(body["error"]["code"], "search_no_match", "{}", query);
+        }
+
+        // without an execution client the transaction is not found
+        state.evm = None;
+        let body = search(state, &included).await;
+        assert_eq!(body["error"]["code"], "search_no_match");
+    }
+
+    /// Checkpoints 0 to 2 over five L2 blocks each, the blocks of checkpoint 0 stored
+    async fn indexed<R: TestRepository>() -> AppState<R> {
+        let repository = R::open().await;
+        let notifier = WebhookNotifier::default();
+        for idx in 0..=2 {
+            let checkpoint = checkpoint_info(idx, (5 * idx, 5 * idx + 4), "finalized", "aa");
+            repository.insert_checkpoint(checkpoint, &notifier).await;
+        }
+        for height in 0..=4 {
+            repository
+                .insert_block(block_header(height), PgU64(0).to_i64())
+                .await
+                .unwrap();
+        }
+        state(repository, &FakeFullnode::default())
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let (status, body) = get(app, uri).await;
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    async fn the_checkpoint_shim_keeps_the_position_page_shape<R: TestRepository>() {
+        let state = indexed::<R>().await;
+        let second = state
+            .repository
+            .get_paginated_checkpoints(1, 1, 0, Some("asc"))
+            .await
+            .unwrap()
+            .items;
+
+        let request = Request::get("/api/checkpoint?p=1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert_eq!(
+            response.headers()[header::LINK],
+            "</api/checkpoints/1>; rel=\"successor-version\""
+        );
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let expected = json!({ "result": {
+            "current_page": 1,
+            "total_pages": 2,
+            "absolute_first_page": 0,
+            "items": second,
+        }});
+        assert_eq!(body, expected.to_string());
+
+        let (status, body) = get_json(app(state.clone()), "/api/checkpoint").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["result"]["current_page"], 0);
+        assert_eq!(body["result"]["items"][0]["idx"], 0);
+
+        // past the last checkpoint the page is empty, not an error
+        for position in ["3", &u64::MAX.to_string()] {
+            let uri = format!("/api/checkpoint?p={}", position);
+            let (status, body) = get_json(app(state.clone()), &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", position);
+            assert_eq!(body["result"]["total_pages"], 2, "{}", position);
+            assert_eq!(body["result"]["items"], json!([]), "{}", position);
+        }
+    }
+
+    async fn links_checkpoints_to_their_stored_neighbours<R: TestRepository>() {
+        let state = indexed::<R>().await;
+
+        let (status, first) = get_json(app(state.clone()), "/api/checkpoints/0").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first["result"]["idx"], 0);
+        assert_eq!(first["result"]["links"]["prev"], json!(null));
+        assert_eq!(first["result"]["links"]["next"], "/api/checkpoints/1");
+
+        let (_, last) = get_json(app(state.clone()), "/api/checkpoints/2").await;
+        assert_eq!(last["result"]["links"]["prev"], "/api/checkpoints/1");
+        assert_eq!(last["result"]["links"]["next"], json!(null));
+
+        let (status, latest) = get_json(app(state.clone()), "/api/checkpoints/latest").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(latest, last);
+
+        let (status, missing) = get_json(app(state), "/api/checkpoints/3").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(missing["error"]["code"], "checkpoint_not_found");
+        assert_eq!(missing["error"]["message"], "Checkpoint 3 not found");
+    }
+
+    async fn has_no_latest_checkpoint_before_indexing<R: TestRepository>() {
+        let state = state(R::open().await, &FakeFullnode::default());
+
+        let (status, body) = get_json(app(state), "/api/checkpoints/latest").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "checkpoint_not_found");
+    }
+
+    async fn links_blocks_by_height_and_hash<R: TestRepository>() {
+        let state = indexed::<R>().await;
+
+        let (status, first) = get_json(app(state.clone()), "/api/blocks/0").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first["result"]["links"]["prev"], json!(null));
+        assert_eq!(first["result"]["links"]["next"], "/api/blocks/1");
+        assert_eq!(first["result"]["links"]["checkpoint"], "/api/checkpoints/0");
+
+        let hash = block_hash(4);
+        let (status, last) =
+            get_json(app(state.clone()), &format!("/api/blocks/hash/0x{}", hash)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(last["result"]["links"]["self"], "/api/blocks/4");
+        assert_eq!(last["result"]["links"]["prev"], "/api/blocks/3");
+        assert_eq!(last["result"]["links"]["next"], json!(null));
+
+        let (status, missing) = get_json(app(state.clone()), "/api/blocks/5").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(missing["error"]["code"], "block_not_found");
+        assert_eq!(missing["error"]["message"], "Block 5 not found");
+
+        let unknown = "ff".repeat(32);
+        let uri = format!("/api/blocks/hash/{}", unknown);
+        let (status, missing) = get_json(app(state.clone()), &uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(missing["error"]["code"], "block_not_found");
+
+        let (status, invalid) = get_json(app(state), "/api/blocks/hash/xyz").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(invalid["error"]["code"], "invalid_block_hash");
+    }
+
+    async fn searches_l1_blocks_by_height_and_hash<R: TestRepository>() {
+        let state = indexed::<R>().await;
+        // checkpoint `idx` covers the L1 blocks 100 + 2 * idx and 101 + 2 * idx
+        for idx in 0..=2 {
+            let checkpoint = checkpoint_info(idx, (5 * idx, 5 * idx + 4), "finalized", "aa");
+            state
+                .repository
+                .insert_l1_blocks(model::l1_block::Model::from_checkpoint_range(&checkpoint))
+                .await
+                .unwrap();
+        }
+
+        for (query, idx) in [
+            ("100".to_string(), 0),
+            ("103".to_string(), 1),
+            (l1_block_hash(104), 2),
+            (format!("0x{}", l1_block_hash(101)), 0),
+        ] {
+            let body = search(state.clone(), &query).await;
+            assert_eq!(body, json!({ "result": idx }), "{}", query);
+        }
+        for query in ["106".to_string(), l1_block_hash(106)] {
+            let body = search(state.clone(), &query).await;
+            assert_eq!(body["error"]["code"], "search_no_match", "{}", query);
+        }
+    }
+
+    async fn reports_the_sync_cursors<R: TestRepository>() {
+        let state = indexed::<R>().await;
+
+        let (status, body) = get_json(app(state), "/api/sync-status").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let cursors: BTreeMap<&str, &serde_json::Value> = body["result"]["cursors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|cursor| (cursor["name"].as_str().unwrap(), &cursor["value"]))
+            .collect();
+        assert_eq!(
+            cursors,
+            BTreeMap::from([
+                (SyncCursor::CheckpointIngested.name(), &json!(2)),
+                (SyncCursor::BlockIngested.name(), &json!(4)),
+            ])
+        );
+    }
+
+    async fn reports_the_lag_behind_the_fullnode<R: TestRepository>() {
+        let mut state = indexed::<R>().await;
+        let fullnode = FakeFullnode::default();
+        for idx in 0..=5 {
+            fullnode.add_checkpoint(idx, (5 * idx, 5 * idx + 4), "finalized", "aa");
+        }
+        state.fetcher = Arc::new(fullnode.serve());
+
+        let (status, body) = get_json(app(state.clone()), "/api/sync-status").await;
+        assert_eq!(status, StatusCode::OK);
+        let result = &body["result"];
+        assert_eq!(
+            result["fullnode"],
+            json!({ "checkpoint_tip": 5, "error": null })
+        );
+        assert_eq!(
+            result["local"],
+            json!({ "checkpoint_tip": 2, "block_tip": 4 })
+        );
+        // the blocks 5 to 14 of checkpoints 1 and 2 are not stored yet
+        assert_eq!(result["lag"], json!({ "checkpoints": 3, "blocks": 10 }));
+        assert_eq!(result["reconciler"]["unfinalized_checkpoints"], 0);
+
+        // an unreachable fullnode leaves the local state reported
+        state.fetcher = Arc::new(StrataFetcher::new("http://127.0.0.1:1/".to_string()));
+        let (status, body) = get_json(app(state), "/api/sync-status").await;
+        assert_eq!(status, StatusCode::OK);
+        let result = &body["result"];
+        assert_eq!(
+            result["fullnode"],
+            json!({ "checkpoint_tip": null, "error": "Failed to reach the fullnode" })
+        );
+        assert_eq!(result["local"]["checkpoint_tip"], 2);
+        assert_eq!(result["lag"], json!({ "checkpoints": null, "blocks": 10 }));
+    }
+
+    #[tokio::test]
+    async fn is_not_ready_without_a_database() {
+        let app = app(state(InMemoryRepository::new(), &FakeFullnode::default()));
+
+        let (status, body) = get_json(app.clone(), "/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"]["code"], "not_ready");
+        assert_eq!(body["error"]["message"], "Database is unreachable");
+        // liveness does not depend on the database
+        assert_eq!(
+            get(app, "/health").await,
+            (StatusCode::OK, r#"{"result":"ok"}"#.to_string())
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn is_ready_once_the_migrations_are_applied() {
+        let options = database::connection::DatabaseOptions {
+            connect_retries: 0,
+            ..Default::default()
+        };
+        let database = DatabaseWrapper::new("sqlite::memory:", None, &options)
+            .await
+            .unwrap();
+        let mut state = state(database.repository(), &FakeFullnode::default());
+        state.database = Arc::new(database);
+
+        let (status, body) = get_json(app(state.clone()), "/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(
+            body["error"]["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Pending migrations: m"),
+            "{}",
+            body
+        );
+
+        state.database.apply_migrations().await.unwrap();
+        let (status, body) = get_json(app(state), "/ready").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "result": "ready" }));
+    }
+
+    async fn refuses_pages_out_of_range_before_querying<R: TestRepository>() {
+        let state = indexed::<R>().await;
+
+        for uri in ["/api/checkpoints", "/api/reorgs"] {
+            for (query, code) in [
+                ("p=0", "invalid_page"),
+                ("p=18446744073709551615&ps=100", "invalid_page"),
+                ("p=92233720368547760&ps=100", "invalid_page"),
+                ("ps=0", "invalid_page_size"),
+                ("ps=101", "invalid_page_size"),
+            ] {
+                let uri = format!("{}?{}", uri, query);
+                let (status, body) = get_json(app(state.clone()), &uri).await;
+                assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", uri);
+                assert_eq!(body["error"]["code"], code, "{}", uri);
             }
         }
+
+        // the last addressable page is queried and is empty
+        let uri = "/api/checkpoints?p=92233720368547759&ps=100";
+        let (status, body) = get_json(app(state.clone()), uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["result"]["items"], json!([]));
+        let (status, body) = get_json(app(state), "/api/checkpoints?p=1&ps=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["result"]["total_pages"], 2);
+        assert_eq!(body["result"]["items"][0]["idx"], 2);
     }
-    Json(json!({ "error": "Invalid search entry" }))
+
+    repository_tests!(
+        refuses_pages_out_of_range_before_querying,
+        the_checkpoint_shim_keeps_the_position_page_shape,
+        links_checkpoints_to_their_stored_neighbours,
+        has_no_latest_checkpoint_before_indexing,
+        links_blocks_by_height_and_hash,
+        searches_l1_blocks_by_height_and_hash,
+        reports_the_sync_cursors,
+        reports_the_lag_behind_the_fullnode,
+    );
 }
@@ -0,0 +1,421 @@
+use crate::utils::supervisor::report_success;
+use database::repository::{BlockRepository, CheckpointRepository, SyncStateRepository};
+use database::services::utils::unix_timestamp;
+use fullnode_client::fetcher::StrataFetcher;
+use futures::stream::{self, StreamExt};
+use model::audit::{AuditDiff, AuditFailure, AuditReport, AuditSubject};
+use model::block::{self, RpcBlockHeader};
+use model::checkpoint::{self, RpcCheckpointInfo};
+use model::pgu64::PgU64;
+use sea_orm::TryIntoModel;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Checkpoints whose stored data is loaded at once during an audit
+const AUDIT_CHUNK: u64 = 100;
+
+/// This function periodically compares stored checkpoints and blocks with the fullnode.
+/// Every run audits the next `batch_size` checkpoints, wrapping around after the last one,
+/// so the whole index is walked over time. Runs every `audit_interval` seconds until `token`
+/// is cancelled.
+pub async fn start_audit_task<R>(
+    fetcher: Arc<StrataFetcher>,
+    repository: Arc<R>,
+    audit_interval: u64,
+    batch_size: u64,
+    concurrency: usize,
+    token: CancellationToken,
+) where
+    R: CheckpointRepository + BlockRepository + SyncStateRepository + 'static,
+{
+    info!("Starting consistency audit...");
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(audit_interval));
+    let mut next_checkpoint: u64 = 0;
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let from = next_checkpoint;
+        let to = from.saturating_add(batch_size.max(1) - 1);
+        match audit_checkpoints(
+            fetcher.as_ref(),
+            repository.as_ref(),
+            Some(from),
+            Some(to),
+            concurrency,
+            &token,
+        )
+        .await
+        {
+            Ok(Some(report)) => {
+                report_success();
+                log_report(&report);
+                next_checkpoint = report.to_checkpoint.saturating_add(1);
+            }
+            // past the last stored checkpoint, start over
+            Ok(None) if from > 0 => {
+                report_success();
+                next_checkpoint = 0;
+                interval.reset_immediately();
+            }
+            Ok(None) => report_success(),
+            Err(e) => error!("Error auditing checkpoints: {}", e),
+        }
+    }
+}
+
+fn log_report(report: &AuditReport) {
+    if report.is_clean() {
+        info!(
+            "Audit of checkpoints {} to {} found no drift ({} checkpoints, {} blocks)",
+            report.from_checkpoint,
+            report.to_checkpoint,
+            report.checked_checkpoints,
+            report.checked_blocks
+        );
+        return;
+    }
+    warn!(
+        "Audit of checkpoints {} to {} found {} differences and {} failures: {}",
+        report.from_checkpoint,
+        report.to_checkpoint,
+        report.diffs.len(),
+        report.failures.len(),
+        serde_json::to_string(report).unwrap_or_default()
+    );
+}
+
+/// Compare the stored checkpoints from `from` to `to`, and their blocks, field by field with
+/// what the fullnode returns now.
+///
+/// The range is narrowed to the checkpoints and blocks stored, so that data the indexer has
+/// not reached yet is not reported as missing. Returns `None` when nothing stored is left in
+/// the range.
+pub async fn audit_checkpoints<R: CheckpointRepository + BlockRepository + SyncStateRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    from: Option<u64>,
+    to: Option<u64>,
+    concurrency: usize,
+    token: &CancellationToken,
+) -> anyhow::Result<Option<AuditReport>> {
+    let index = repository.get_index_range().await?;
+    let (Some(first), Some(last)) = (index.first_checkpoint, index.last_checkpoint) else {
+        return Ok(None);
+    };
+    let from = from.map_or(first, |from| from.max(first));
+    let to = to.map_or(last, |to| to.min(last));
+    if from > to {
+        return Ok(None);
+    }
+
+    let mut report = AuditReport {
+        from_checkpoint: from,
+        to_checkpoint: to,
+        started_at: unix_timestamp(),
+        ..Default::default()
+    };
+    let mut chunk_start = from;
+    loop {
+        if token.is_cancelled() {
+            anyhow::bail!("Audit interrupted");
+        }
+        let chunk_end = chunk_start.saturating_add(AUDIT_CHUNK - 1).min(to);
+        audit_chunk(
+            fetcher,
+            repository,
+            chunk_start,
+            chunk_end,
+            index.last_block,
+            concurrency,
+            &mut report,
+        )
+        .await?;
+        if chunk_end == to {
+            break;
+        }
+        chunk_start = chunk_end + 1;
+    }
+    report.finished_at = unix_timestamp();
+    Ok(Some(report))
+}
+
+async fn audit_chunk<R: CheckpointRepository + BlockRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    from: u64,
+    to: u64,
+    last_block: Option<u64>,
+    concurrency: usize,
+    report: &mut AuditReport,
+) -> anyhow::Result<()> {
+    let stored: BTreeMap<i64, checkpoint::Model> = repository
+        .get_checkpoints_in_range(PgU64(from).to_i64(), PgU64(to).to_i64())
+        .await?
+        .into_iter()
+        .map(|checkpoint| (checkpoint.idx, checkpoint))
+        .collect();
+
+    // heights to audit along with the checkpoint the fullnode attributes them to
+    let mut heights: Vec<(u64, i64)> = Vec::new();
+    for idx in from..=to {
+        report.checked_checkpoints += 1;
+        let fullnode = match fetcher
+            .fetch_data::<RpcCheckpointInfo>("strata_getCheckpointInfo", idx)
+            .await
+        {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                report.failures.push(AuditFailure {
+                    subject: AuditSubject::Checkpoint,
+                    id: idx,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let fullnode = checkpoint::ActiveModel::from(fullnode).try_into_model()?;
+        compare_checkpoint(
+            idx,
+            stored.get(&PgU64(idx).to_i64()),
+            &fullnode,
+            &mut report.diffs,
+        );
+
+        let start = PgU64::i64_to_u64(fullnode.l2_start);
+        let end = PgU64::i64_to_u64(fullnode.l2_end);
+        let end = last_block.map_or(end, |last_block| end.min(last_block));
+        heights.extend((start..=end).map(|height| (height, fullnode.idx)));
+    }
+    let lowest = heights.iter().map(|(height, _)| *height).min();
+    let highest = heights.iter().map(|(height, _)| *height).max();
+    let (Some(lowest), Some(highest)) = (lowest, highest) else {
+        return Ok(());
+    };
+
+    let stored: BTreeMap<i64, block::Model> = repository
+        .get_blocks_in_range(PgU64(lowest).to_i64(), PgU64(highest).to_i64())
+        .await?
+        .into_iter()
+        .map(|block| (block.height, block))
+        .collect();
+    let mut fetches = stream::iter(heights)
+        .map(|(height, checkpoint_idx)| async move {
+            let headers = fetcher
+                .fetch_data::<Vec<RpcBlockHeader>>("strata_getHeadersAtIdx", height)
+                .await;
+            (height, checkpoint_idx, headers)
+        })
+        .buffered(concurrency.max(1));
+    while let Some((height, checkpoint_idx, headers)) = fetches.next().await {
+        report.checked_blocks += 1;
+        match headers {
+            Ok(headers) => compare_block(
+                height,
+                stored.get(&PgU64(height).to_i64()),
+                headers.first(),
+                checkpoint_idx,
+                &mut report.diffs,
+            ),
+            Err(e) => report.failures.push(AuditFailure {
+                subject: AuditSubject::Block,
+                id: height,
+                error: e.to_string(),
+            }),
+        }
+    }
+    Ok(())
+}
+
+fn compare_checkpoint(
+    idx: u64,
+    stored: Option<&checkpoint::Model>,
+    fullnode: &checkpoint::Model,
+    diffs: &mut Vec<AuditDiff>,
+) {
+    let Some(stored) = stored else {
+        diffs.push(AuditDiff {
+            subject: AuditSubject::Checkpoint,
+            id: idx,
+            field: "idx".to_string(),
+            stored: None,
+            fullnode: Some(idx.to_string()),
+        });
+        return;
+    };
+    let fields = [
+        (
+            "l1_range",
+            format_range(stored.l1_start, stored.l1_end),
+            format_range(fullnode.l1_start, fullnode.l1_end),
+        ),
+        (
+            "l2_range",
+            format_range(stored.l2_start, stored.l2_end),
+            format_range(fullnode.l2_start, fullnode.l2_end),
+        ),
+        (
+            "checkpoint_txid",
+            stored.checkpoint_txid.clone(),
+            fullnode.checkpoint_txid.clone(),
+        ),
+        ("status", stored.status.clone(), fullnode.status.clone()),
+    ];
+    for (field, stored, fullnode) in fields {
+        if stored != fullnode {
+            diffs.push(AuditDiff {
+                subject: AuditSubject::Checkpoint,
+                id: idx,
+                field: field.to_string(),
+                stored: Some(stored),
+                fullnode: Some(fullnode),
+            });
+        }
+    }
+}
+
+fn compare_block(
+    height: u64,
+    stored: Option<&block::Model>,
+    fullnode: Option<&RpcBlockHeader>,
+    checkpoint_idx: i64,
+    diffs: &mut Vec<AuditDiff>,
+) {
+    let diff = |field: &str, stored: Option<String>, fullnode: Option<String>| AuditDiff {
+        subject: AuditSubject::Block,
+        id: height,
+        field: field.to_string(),
+        stored,
+        fullnode,
+    };
+    let stored_hash = stored.map(|block| block.block_hash.clone());
+    let fullnode_hash = fullnode.map(|header| header.block_id.clone());
+    if stored_hash != fullnode_hash {
+        diffs.push(diff("block_hash", stored_hash, fullnode_hash));
+    }
+    if let Some(stored) = stored.filter(|block| block.checkpoint_idx != checkpoint_idx) {
+        diffs.push(diff(
+            "checkpoint_idx",
+            Some(PgU64::i64_to_u64(stored.checkpoint_idx).to_string()),
+            Some(PgU64::i64_to_u64(checkpoint_idx).to_string()),
+        ));
+    }
+}
+
+fn format_range(start: i64, end: i64) -> String {
+    format!("{}-{}", PgU64::i64_to_u64(start), PgU64::i64_to_u64(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webhook_service::WebhookNotifier;
+    use crate::utils::fake_fullnode::{block_header, checkpoint_info, FakeFullnode};
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+    use serde_json::json;
+
+    /// Checkpoints 0 to 2 over five L2 blocks each, served by the fullnode and stored with
+    /// their blocks
+    async fn in_sync<R: TestRepository>() -> (FakeFullnode, R) {
+        let fullnode = FakeFullnode::default();
+        let repository = R::open().await;
+        for idx in 0..=2 {
+            let l2_range = (5 * idx, 5 * idx + 4);
+            fullnode.add_checkpoint(idx, l2_range, "finalized", "aa");
+            let checkpoint = checkpoint_info(idx, l2_range, "finalized", "aa");
+            repository
+                .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+                .await;
+            for height in l2_range.0..=l2_range.1 {
+                repository
+                    .insert_block(block_header(height), PgU64(idx).to_i64())
+                    .await
+                    .unwrap();
+            }
+        }
+        (fullnode, repository)
+    }
+
+    async fn audit<R: TestRepository>(
+        fullnode: &FakeFullnode,
+        repository: &R,
+        from: Option<u64>,
+        to: Option<u64>,
+    ) -> Option<AuditReport> {
+        audit_checkpoints(
+            &fullnode.serve(),
+            repository,
+            from,
+            to,
+            2,
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn reports_the_fields_that_drifted_from_the_fullnode<R: TestRepository>() {
+        let (fullnode, repository) = in_sync::<R>().await;
+        let report = audit(&fullnode, &repository, None, None).await.unwrap();
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!((report.checked_checkpoints, report.checked_blocks), (3, 15));
+
+        fullnode.add_checkpoint(1, (5, 9), "finalized", "bb");
+        fullnode.chain().headers.get_mut(&7).unwrap()[0]["block_id"] = json!("ff".repeat(32));
+
+        let report = audit(&fullnode, &repository, None, None).await.unwrap();
+        assert_eq!(
+            report.diffs,
+            [
+                AuditDiff {
+                    subject: AuditSubject::Checkpoint,
+                    id: 1,
+                    field: "checkpoint_txid".to_string(),
+                    stored: Some("aa".to_string()),
+                    fullnode: Some("bb".to_string()),
+                },
+                AuditDiff {
+                    subject: AuditSubject::Block,
+                    id: 7,
+                    field: "block_hash".to_string(),
+                    stored: Some(block_header(7).block_id),
+                    fullnode: Some("ff".repeat(32)),
+                },
+            ]
+        );
+        assert!(report.failures.is_empty());
+    }
+
+    async fn reports_what_the_fullnode_cannot_answer_as_failures<R: TestRepository>() {
+        let (fullnode, repository) = in_sync::<R>().await;
+        fullnode.chain().failing.insert("1".to_string());
+        fullnode.chain().failing.insert("12".to_string());
+
+        let report = audit(&fullnode, &repository, Some(1), None).await.unwrap();
+        assert_eq!((report.from_checkpoint, report.to_checkpoint), (1, 2));
+        // the blocks of a checkpoint the fullnode does not report are not audited
+        assert_eq!((report.checked_checkpoints, report.checked_blocks), (2, 5));
+        let failures: Vec<_> = report
+            .failures
+            .iter()
+            .map(|failure| (failure.subject, failure.id))
+            .collect();
+        assert_eq!(
+            failures,
+            [(AuditSubject::Checkpoint, 1), (AuditSubject::Block, 12)]
+        );
+        assert!(report.diffs.is_empty());
+
+        // nothing stored past the last checkpoint
+        assert_eq!(audit(&fullnode, &repository, Some(3), None).await, None);
+    }
+
+    repository_tests!(
+        reports_the_fields_that_drifted_from_the_fullnode,
+        reports_what_the_fullnode_cannot_answer_as_failures,
+    );
+}
@@ -1,81 +1,647 @@
-use database::connection::DatabaseWrapper;
-use database::services::{block_service::BlockService, checkpoint_service::CheckpointService};
+use crate::utils::supervisor::report_success;
+use database::repository::{
+    BlockFetchJobRepository, BlockRepository, CheckpointRepository, SyncStateRepository,
+};
+use database::services::utils::unix_timestamp;
 use fullnode_client::fetcher::StrataFetcher;
+use futures::stream::{self, StreamExt};
 use model::block::RpcBlockHeader;
 use model::pgu64::PgU64;
-use std::sync::Arc;
-use tokio::sync::mpsc::Receiver;
-use tracing::{debug, info};
-
-/// Event sent to block fetcher to request fetching of blocks for the checkpoint
-#[derive(Debug, Clone)]
-pub struct CheckpointFetch {
-    pub idx: i64,
+use model::sync_state::SyncCursor;
+use sea_orm::DbErr;
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// How long the block fetcher waits for new jobs before looking at the queue again. Jobs
+/// put back because an earlier checkpoint has to be fetched first are retried this often,
+/// failed jobs are picked up on the first look after their retry is due.
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Queue of the checkpoints whose blocks are to be fetched.
+///
+/// Jobs are persisted through the repository, one per checkpoint, so requesting a checkpoint
+/// that is already pending is a no-op and pending work survives restarts. Enqueueing never
+/// waits for the block fetcher, it is only woken up.
+pub struct BlockFetchQueue<R> {
+    repository: Arc<R>,
+    notify: Arc<Notify>,
 }
-impl CheckpointFetch {
-    pub fn new(idx: i64) -> Self {
-        Self { idx }
+
+impl<R> Clone for BlockFetchQueue<R> {
+    fn clone(&self) -> Self {
+        Self {
+            repository: self.repository.clone(),
+            notify: self.notify.clone(),
+        }
     }
 }
-pub async fn run_block_fetcher(
-    fetcher: Arc<StrataFetcher>,
-    database: Arc<DatabaseWrapper>,
-    mut rx: Receiver<CheckpointFetch>,
-) {
-    info!("Starting block fetcher...");
-    while let Some(CheckpointFetch { idx }) = rx.recv().await {
-        debug!("Received checkpoint: {:?}", PgU64::i64_to_u64(idx));
-        fetch_blocks_in_checkpoint(fetcher.clone(), database.clone(), idx).await;
+
+impl<R: BlockFetchJobRepository> BlockFetchQueue<R> {
+    pub fn new(repository: Arc<R>) -> Self {
+        Self {
+            repository,
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Request the blocks of checkpoint `idx`
+    pub async fn enqueue(&self, idx: i64) -> Result<(), DbErr> {
+        self.repository.enqueue_block_fetch(idx).await?;
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Claim the pending job with the lowest checkpoint, failed jobs once their retry is due
+    async fn claim(&self) -> Option<i64> {
+        match self.repository.claim_block_fetch(unix_timestamp()).await {
+            Ok(Some(idx)) => Some(idx),
+            Ok(None) => {
+                // nothing left to fetch, the block fetcher is caught up
+                report_success();
+                None
+            }
+            Err(e) => {
+                error!("Failed to claim a block fetch job: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Record the outcome of the job of checkpoint `idx`
+    async fn finish(&self, idx: i64, error: Option<String>) {
+        if let Err(e) = self.repository.finish_block_fetch(idx, error).await {
+            error!(
+                "Failed to update the block fetch job of checkpoint {}: {:?}",
+                PgU64::i64_to_u64(idx),
+                e
+            );
+        }
+    }
+
+    /// Put the job of checkpoint `idx` back in the queue
+    async fn requeue(&self, idx: i64) {
+        if let Err(e) = self.repository.requeue_block_fetch(idx).await {
+            error!(
+                "Failed to requeue the block fetch job of checkpoint {}: {:?}",
+                PgU64::i64_to_u64(idx),
+                e
+            );
+        }
+    }
+
+    /// Put the jobs whose heights were dropped back in the queue
+    async fn requeue_running(&self) {
+        match self.repository.requeue_running_block_fetches().await {
+            Ok(0) => (),
+            Ok(requeued) => debug!("Requeued {} block fetch jobs", requeued),
+            Err(e) => error!("Failed to requeue the running block fetch jobs: {:?}", e),
+        }
+    }
+
+    /// Wait until a job is enqueued or the poll interval elapsed, `false` once `token` is
+    /// cancelled
+    async fn wait(&self, token: &CancellationToken) -> bool {
+        tokio::select! {
+            _ = token.cancelled() => false,
+            _ = self.notify.notified() => true,
+            _ = tokio::time::sleep(QUEUE_POLL_INTERVAL) => true,
+        }
     }
 }
 
-async fn fetch_blocks_in_checkpoint(
-    fetcher: Arc<StrataFetcher>,
-    database: Arc<DatabaseWrapper>,
+/// A single L2 height scheduled for fetching
+#[derive(Debug, Clone, Copy)]
+struct BlockFetch {
     checkpoint_idx: i64,
-) {
-    let checkpoint_db = CheckpointService::new(&database.db);
-    let block_db = BlockService::new(&database.db);
-    let checkpoint = checkpoint_db.get_checkpoint_by_idx(checkpoint_idx).await;
-    if let Some(c) = checkpoint {
-        let mut start = c.l2_range.0;
-        let end = c.l2_range.1;
-
-        // we will reach this point only when we are sure that we must fetch from particular
-        // checkpoint. So having the heighest among the blocks must give us the shortcut
-        // to determine the most optimal starting point.
-        let last_block = block_db.get_latest_block_index().await;
-        if let Some(last_block_height) = last_block {
-            let last_block_height_u64 = PgU64::from_i64(last_block_height).0;
-            // start from the next block
-            if last_block_height_u64 >= start {
-                start = last_block_height_u64 + 1;
-            }
-        }
-        if start > end {
-            info!(
-                "No blocks to fetch for checkpoint {}",
-                PgU64::i64_to_u64(checkpoint_idx)
-            );
+    height: u64,
+    /// Last height of the checkpoint, its job is done once it is inserted
+    last: bool,
+}
+
+/// What the scheduler does with a claimed job
+#[derive(Debug, PartialEq, Eq)]
+enum FetchPlan {
+    /// Fetch these heights
+    Fetch(RangeInclusive<u64>),
+    /// Every block of the checkpoint is stored or already in flight
+    Complete,
+    /// A failed fetch rewound the scheduler below the checkpoint, the earlier checkpoint
+    /// has to be fetched first
+    Blocked,
+    /// The job cannot run
+    Failed(String),
+}
+
+/// Scheduling state shared by the scheduler and the writer
+#[derive(Debug, Clone, Copy, Default)]
+struct Schedule {
+    /// Next L2 height to hand out, the writer rewinds it after a failed fetch so that the
+    /// height is scheduled again
+    next: Option<u64>,
+    /// Highest height the writer inserted. The block cursor falling below it means blocks
+    /// were deleted underneath us, e.g. by the admin commands.
+    written: Option<u64>,
+}
+
+type SharedSchedule = Arc<Mutex<Schedule>>;
+
+/// Fetches the blocks of every checkpoint in `queue`.
+///
+/// Jobs are claimed in checkpoint order and turned into a stream of heights that is fetched
+/// with up to `concurrency` requests in flight, across checkpoint boundaries. Blocks are
+/// still written strictly in height order so the continuity checks on insert hold. Once
+/// `token` is cancelled the block being written is finished and the fetches still in flight
+/// are dropped, their jobs are picked up again on the next start.
+pub async fn run_block_fetcher<R>(
+    fetcher: Arc<StrataFetcher>,
+    queue: BlockFetchQueue<R>,
+    concurrency: usize,
+    token: CancellationToken,
+) where
+    R: CheckpointRepository + BlockRepository + SyncStateRepository + BlockFetchJobRepository,
+{
+    info!(
+        "Starting block fetcher with {} concurrent requests...",
+        concurrency
+    );
+    let concurrency = concurrency.max(1);
+    let schedule: SharedSchedule = Arc::default();
+    let (height_tx, height_rx) = mpsc::channel(concurrency);
+
+    // the heights of jobs left running by a previous run were dropped with it
+    queue.requeue_running().await;
+    tokio::join!(
+        schedule_block_fetches(queue.clone(), height_tx, schedule.clone(), token.clone()),
+        fetch_and_insert_blocks(fetcher, queue, height_rx, schedule, concurrency, token),
+    );
+}
+
+/// Turns claimed jobs into the heights that still need fetching
+async fn schedule_block_fetches<R>(
+    queue: BlockFetchQueue<R>,
+    height_tx: Sender<BlockFetch>,
+    schedule: SharedSchedule,
+    token: CancellationToken,
+) where
+    R: CheckpointRepository + SyncStateRepository + BlockFetchJobRepository,
+{
+    let repository = queue.repository.clone();
+    loop {
+        if token.is_cancelled() {
             return;
         }
+        let Some(idx) = queue.claim().await else {
+            if !queue.wait(&token).await {
+                return;
+            }
+            continue;
+        };
+        debug!("Claimed checkpoint: {:?}", PgU64::i64_to_u64(idx));
+        let plan = loop {
+            let snapshot = *schedule.lock().unwrap();
+            let removed = blocks_removed(repository.as_ref(), snapshot.written).await;
+            // start over from the block cursor when blocks we wrote are gone
+            let scheduled = if removed { None } else { snapshot.next };
+            let plan = plan_block_fetches(repository.as_ref(), idx, scheduled).await;
+
+            let mut schedule = schedule.lock().unwrap();
+            // the writer rewound after a failed fetch while we were reading, start over
+            if schedule.next != snapshot.next {
+                continue;
+            }
+            if removed {
+                warn!("Blocks were deleted while indexing, rescheduling from the block cursor");
+                *schedule = Schedule::default();
+            }
+            // claim the heights before sending them so a later rewind takes precedence
+            if let FetchPlan::Fetch(heights) = &plan {
+                schedule.next = Some(heights.end() + 1);
+            }
+            break plan;
+        };
+        let heights = match plan {
+            FetchPlan::Fetch(heights) => heights,
+            FetchPlan::Complete => {
+                queue.finish(idx, None).await;
+                continue;
+            }
+            FetchPlan::Blocked => {
+                queue.requeue(idx).await;
+                if !queue.wait(&token).await {
+                    return;
+                }
+                continue;
+            }
+            FetchPlan::Failed(error) => {
+                warn!(
+                    "Cannot fetch the blocks of checkpoint {}: {}",
+                    PgU64::i64_to_u64(idx),
+                    error
+                );
+                queue.finish(idx, Some(error)).await;
+                continue;
+            }
+        };
+
         info!(
             "Fetching blocks from {} to {} for checkpoint {}",
-            start,
-            end,
+            heights.start(),
+            heights.end(),
+            PgU64::i64_to_u64(idx)
+        );
+        let last = *heights.end();
+        for height in heights {
+            let fetch = BlockFetch {
+                checkpoint_idx: idx,
+                height,
+                last: height == last,
+            };
+            tokio::select! {
+                _ = token.cancelled() => return,
+                sent = height_tx.send(fetch) => {
+                    if sent.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether the block cursor fell below the highest height the writer inserted
+async fn blocks_removed<R: SyncStateRepository>(repository: &R, written: Option<u64>) -> bool {
+    let Some(written) = written else {
+        return false;
+    };
+    match repository.get_sync_cursor(SyncCursor::BlockIngested).await {
+        Ok(last_block) => {
+            last_block.is_none_or(|last_block| PgU64::i64_to_u64(last_block) < written)
+        }
+        Err(e) => {
+            error!("Failed to read the block cursor: {:?}", e);
+            false
+        }
+    }
+}
+
+/// Heights of the checkpoint that are neither stored nor already scheduled
+async fn plan_block_fetches<R: CheckpointRepository + SyncStateRepository>(
+    repository: &R,
+    checkpoint_idx: i64,
+    scheduled: Option<u64>,
+) -> FetchPlan {
+    let checkpoint = match repository.get_checkpoint_by_idx(checkpoint_idx).await {
+        Ok(Some(checkpoint)) => checkpoint,
+        Ok(None) => return FetchPlan::Failed("the checkpoint is not stored".to_string()),
+        Err(e) => return FetchPlan::Failed(format!("failed to read the checkpoint: {}", e)),
+    };
+    let mut start = checkpoint.l2_range.0;
+    let end = checkpoint.l2_range.1;
+
+    // we will reach this point only when we are sure that we must fetch from particular
+    // checkpoint. So the cursor of the last ingested block gives us the shortcut
+    // to determine the most optimal starting point.
+    let last_block = match repository.get_sync_cursor(SyncCursor::BlockIngested).await {
+        Ok(last_block) => last_block,
+        Err(e) => return FetchPlan::Failed(format!("failed to read the block cursor: {}", e)),
+    };
+    let next_block = last_block.map(|height| PgU64::i64_to_u64(height) + 1);
+    if let Some(next_block) = next_block {
+        // start from the next block
+        start = start.max(next_block);
+    }
+    // nothing in flight yet, e.g. on startup: the checkpoint right after the stored
+    // blocks goes first, even if a later one was queued before it
+    if let Some(scheduled) = scheduled.or(next_block) {
+        // a failed fetch rewound the scheduler below this checkpoint, the earlier
+        // checkpoint has to be scheduled again first
+        if start > scheduled {
+            debug!(
+                "Waiting for height {} before fetching checkpoint {}",
+                scheduled,
+                PgU64::i64_to_u64(checkpoint_idx)
+            );
+            return FetchPlan::Blocked;
+        }
+        // skip the heights that are already in flight
+        start = start.max(scheduled);
+    }
+
+    if start > end {
+        debug!(
+            "No blocks to fetch for checkpoint {}",
             PgU64::i64_to_u64(checkpoint_idx)
         );
-        for block_height in start..=end {
-            if let Ok(block_headers) = fetcher
-                .fetch_data::<Vec<RpcBlockHeader>>("strata_getHeadersAtIdx", block_height)
-                .await
-            {
-                for block_header in block_headers {
-                    block_db
-                        .insert_block(block_header.clone(), checkpoint_idx)
-                        .await;
+        return FetchPlan::Complete;
+    }
+    FetchPlan::Fetch(start..=end)
+}
+
+/// Fetches scheduled heights concurrently and inserts them in height order
+async fn fetch_and_insert_blocks<R>(
+    fetcher: Arc<StrataFetcher>,
+    queue: BlockFetchQueue<R>,
+    mut height_rx: Receiver<BlockFetch>,
+    schedule: SharedSchedule,
+    concurrency: usize,
+    token: CancellationToken,
+) where
+    R: BlockRepository + SyncStateRepository + BlockFetchJobRepository,
+{
+    let fetches = stream::poll_fn(|cx| height_rx.poll_recv(cx))
+        .map(|fetch| {
+            let fetcher = fetcher.clone();
+            async move {
+                let headers = fetcher
+                    .fetch_data::<Vec<RpcBlockHeader>>("strata_getHeadersAtIdx", fetch.height)
+                    .await;
+                (fetch, headers)
+            }
+        })
+        .buffered(concurrency);
+    futures::pin_mut!(fetches);
+    let repository = queue.repository.clone();
+
+    // set after a failed fetch: later heights are dropped until the failed one is retried
+    let mut resume_at: Option<u64> = None;
+    loop {
+        let (fetch, headers) = tokio::select! {
+            biased;
+            _ = token.cancelled() => return,
+            next = fetches.next() => match next {
+                Some(next) => next,
+                None => return,
+            },
+        };
+        if let Some(height) = resume_at {
+            if fetch.height != height {
+                continue;
+            }
+            resume_at = None;
+        }
+
+        let inserted = match headers {
+            Ok(block_headers) => {
+                // blocks below were deleted since this height was scheduled, resume from
+                // the first missing one
+                if !repository
+                    .can_insert_block(PgU64(fetch.height).to_i64())
+                    .await
+                {
+                    // the jobs of the heights dropped from here on are scheduled again
+                    queue.requeue_running().await;
+                    let Some(height) = first_missing_height(repository.as_ref()).await else {
+                        // the scheduler starts over once it notices the cursor moved back
+                        continue;
+                    };
+                    warn!(
+                        "Blocks before height {} were deleted, resuming from height {}",
+                        fetch.height, height
+                    );
+                    resume_at = Some(height);
+                    rewind(&schedule, height);
+                    continue;
                 }
+                insert_blocks(repository.as_ref(), block_headers, fetch.checkpoint_idx).await
+            }
+            Err(e) => Err(e),
+        };
+
+        match inserted {
+            Ok(()) => {
+                {
+                    let mut schedule = schedule.lock().unwrap();
+                    schedule.written = Some(
+                        schedule
+                            .written
+                            .map_or(fetch.height, |written| written.max(fetch.height)),
+                    );
+                }
+                if fetch.last {
+                    queue.finish(fetch.checkpoint_idx, None).await;
+                }
+                report_success();
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to fetch or store block at height {}, it will be retried: {:?}",
+                    fetch.height, e
+                );
+                resume_at = Some(fetch.height);
+                rewind(&schedule, fetch.height);
+                // the checkpoint runs again once its retry is due, the later ones whose
+                // heights are dropped go back to the queue
+                queue
+                    .finish(fetch.checkpoint_idx, Some(e.to_string()))
+                    .await;
+                queue.requeue_running().await;
             }
         }
     }
 }
+
+/// Insert the blocks fetched at one height, stopping at the first that cannot be stored
+async fn insert_blocks<R: BlockRepository>(
+    repository: &R,
+    block_headers: Vec<RpcBlockHeader>,
+    checkpoint_idx: i64,
+) -> anyhow::Result<()> {
+    for block_header in block_headers {
+        repository
+            .insert_block(block_header, checkpoint_idx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Make the scheduler hand out `height` again
+fn rewind(schedule: &SharedSchedule, height: u64) {
+    let mut schedule = schedule.lock().unwrap();
+    schedule.next = Some(schedule.next.map_or(height, |next| next.min(height)));
+}
+
+/// Height right after the block cursor, `None` if it is unset or cannot be read
+async fn first_missing_height<R: SyncStateRepository>(repository: &R) -> Option<u64> {
+    match repository.get_sync_cursor(SyncCursor::BlockIngested).await {
+        Ok(last_block) => last_block.map(|height| PgU64::i64_to_u64(height) + 1),
+        Err(e) => {
+            error!("Failed to read the block cursor: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webhook_service::WebhookNotifier;
+    use crate::utils::fake_fullnode::{block_header, checkpoint_info, FakeFullnode};
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+    use database::repository::BlockRepository;
+    use model::block_fetch_job::BlockFetchJobCounts;
+
+    /// Checkpoint 0 covers the L2 heights 0 to 4, checkpoint 1 the heights 5 to 9
+    const CHECKPOINTS: [(u64, (u64, u64)); 2] = [(0, (0, 4)), (1, (5, 9))];
+
+    fn idx(idx: u64) -> i64 {
+        PgU64(idx).to_i64()
+    }
+
+    async fn repository_with_blocks_up_to<R: TestRepository>(last_block: Option<u64>) -> Arc<R> {
+        let repository = Arc::new(R::open().await);
+        for (checkpoint_idx, l2_range) in CHECKPOINTS {
+            let checkpoint = checkpoint_info(checkpoint_idx, l2_range, "finalized", "txid");
+            repository
+                .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+                .await;
+        }
+        for height in (0..).take(last_block.map_or(0, |last_block| last_block as usize + 1)) {
+            repository
+                .insert_block(block_header(height), idx(height / 5))
+                .await
+                .unwrap();
+        }
+        repository
+    }
+
+    async fn stored_heights(repository: &impl BlockRepository) -> Vec<u64> {
+        repository
+            .get_blocks_in_range(i64::MIN, i64::MAX)
+            .await
+            .unwrap()
+            .iter()
+            .map(|block| PgU64::i64_to_u64(block.height))
+            .collect()
+    }
+
+    async fn plans_the_heights_neither_stored_nor_scheduled<R: TestRepository>() {
+        let cases = [
+            // nothing stored, the whole checkpoint
+            (None, 0, None, FetchPlan::Fetch(0..=4)),
+            // resume after the stored blocks
+            (Some(2), 0, None, FetchPlan::Fetch(3..=4)),
+            (Some(4), 0, None, FetchPlan::Complete),
+            // the earlier checkpoint has to be fetched first
+            (Some(2), 1, None, FetchPlan::Blocked),
+            (Some(4), 1, Some(3), FetchPlan::Blocked),
+            // skip the heights in flight
+            (Some(4), 1, Some(7), FetchPlan::Fetch(7..=9)),
+            (Some(4), 1, Some(10), FetchPlan::Complete),
+        ];
+        for (last_block, checkpoint_idx, scheduled, expected) in cases {
+            let repository = repository_with_blocks_up_to::<R>(last_block).await;
+            let plan =
+                plan_block_fetches(repository.as_ref(), idx(checkpoint_idx), scheduled).await;
+            assert_eq!(
+                plan, expected,
+                "checkpoint {} with blocks up to {:?} and {:?} scheduled",
+                checkpoint_idx, last_block, scheduled
+            );
+        }
+    }
+
+    async fn fails_to_plan_a_checkpoint_not_stored<R: TestRepository>() {
+        let repository = repository_with_blocks_up_to::<R>(None).await;
+        let plan = plan_block_fetches(repository.as_ref(), idx(2), None).await;
+        assert!(matches!(plan, FetchPlan::Failed(_)), "{:?}", plan);
+    }
+
+    /// Run the writer over the heights of checkpoint 0 until they are all handled
+    async fn fetch_checkpoint_zero<R: TestRepository>(
+        fullnode: &FakeFullnode,
+        repository: Arc<R>,
+    ) -> Schedule {
+        let queue = BlockFetchQueue::new(repository.clone());
+        queue.enqueue(idx(0)).await.unwrap();
+        assert_eq!(queue.claim().await, Some(idx(0)));
+
+        let (height_tx, height_rx) = mpsc::channel(8);
+        for height in 0..=4 {
+            let fetch = BlockFetch {
+                checkpoint_idx: idx(0),
+                height,
+                last: height == 4,
+            };
+            height_tx.send(fetch).await.unwrap();
+        }
+        drop(height_tx);
+
+        let schedule = SharedSchedule::default();
+        let fetcher = Arc::new(fullnode.serve());
+        let token = CancellationToken::new();
+        fetch_and_insert_blocks(fetcher, queue, height_rx, schedule.clone(), 2, token).await;
+        let schedule = *schedule.lock().unwrap();
+        schedule
+    }
+
+    async fn inserts_the_fetched_blocks_and_finishes_the_job<R: TestRepository>() {
+        let fullnode = FakeFullnode::default();
+        for (checkpoint_idx, l2_range) in CHECKPOINTS {
+            fullnode.add_checkpoint(checkpoint_idx, l2_range, "finalized", "txid");
+        }
+        let repository = repository_with_blocks_up_to::<R>(None).await;
+
+        let schedule = fetch_checkpoint_zero(&fullnode, repository.clone()).await;
+
+        assert_eq!(
+            stored_heights(repository.as_ref()).await,
+            vec![0, 1, 2, 3, 4]
+        );
+        assert_eq!(schedule.written, Some(4));
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!(
+            counts,
+            BlockFetchJobCounts {
+                done: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    async fn rewinds_the_job_on_a_failed_fetch<R: TestRepository>() {
+        let fullnode = FakeFullnode::default();
+        for (checkpoint_idx, l2_range) in CHECKPOINTS {
+            fullnode.add_checkpoint(checkpoint_idx, l2_range, "finalized", "txid");
+        }
+        fullnode.chain().failing.insert("2".to_string());
+        let repository = repository_with_blocks_up_to::<R>(None).await;
+
+        let schedule = fetch_checkpoint_zero(&fullnode, repository.clone()).await;
+
+        // the later heights are dropped until the failed one is scheduled again
+        assert_eq!(stored_heights(repository.as_ref()).await, vec![0, 1]);
+        assert_eq!(schedule.next, Some(2));
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!(counts.failed, 1);
+    }
+
+    async fn rewinds_the_job_on_a_block_that_does_not_follow_the_stored_ones<R: TestRepository>() {
+        let fullnode = FakeFullnode::default();
+        for (checkpoint_idx, l2_range) in CHECKPOINTS {
+            fullnode.add_checkpoint(checkpoint_idx, l2_range, "finalized", "txid");
+        }
+        let skipping = serde_json::to_value(vec![block_header(3)]).unwrap();
+        fullnode.chain().headers.insert(2, skipping);
+        let repository = repository_with_blocks_up_to::<R>(None).await;
+
+        let schedule = fetch_checkpoint_zero(&fullnode, repository.clone()).await;
+
+        assert_eq!(stored_heights(repository.as_ref()).await, vec![0, 1]);
+        assert_eq!(schedule.next, Some(2));
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!(counts.failed, 1);
+    }
+
+    repository_tests!(
+        plans_the_heights_neither_stored_nor_scheduled,
+        fails_to_plan_a_checkpoint_not_stored,
+        inserts_the_fetched_blocks_and_finishes_the_job,
+        rewinds_the_job_on_a_failed_fetch,
+        rewinds_the_job_on_a_block_that_does_not_follow_the_stored_ones,
+    );
+}
@@ -0,0 +1,514 @@
+use crate::utils::supervisor::report_success;
+use database::repository::{
+    BlockRepository, BridgeIntentRepository, CheckpointRepository, SyncStateRepository,
+};
+use database::services::utils::unix_timestamp;
+use fullnode_client::fetcher::StrataFetcher;
+use model::bridge_duty::RpcBridgeDuties;
+use model::bridge_intent::{self, IntentKind, RpcBridgeIntent, RpcExecUpdate};
+use model::checkpoint;
+use model::pgu64::PgU64;
+use model::sync_state::SyncCursor;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Most L2 blocks scanned on every pass
+const BLOCK_BATCH_SIZE: i64 = 200;
+
+/// This function indexes the bridge deposits and withdrawals of the stored L2 blocks, and
+/// the duties of the bridge operators `operators`
+/// It will run in a loop with a delay of `interval` seconds until `token` is cancelled
+pub async fn start_bridge_indexer<R>(
+    fetcher: Arc<StrataFetcher>,
+    repository: Arc<R>,
+    interval: u64,
+    operators: Vec<u32>,
+    token: CancellationToken,
+) where
+    R: BlockRepository + SyncStateRepository + BridgeIntentRepository + 'static,
+{
+    info!("Starting bridge indexer...");
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval));
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let indexed = index_bridge_intents(fetcher.as_ref(), repository.as_ref(), &token);
+        let mut succeeded = match indexed.await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Error indexing bridge intents: {}", e);
+                false
+            }
+        };
+        for &operator_idx in &operators {
+            let indexed = index_bridge_duties(fetcher.as_ref(), repository.as_ref(), operator_idx);
+            if let Err(e) = indexed.await {
+                warn!(
+                    "Error indexing the duties of bridge operator {}: {}",
+                    operator_idx, e
+                );
+                succeeded = false;
+            }
+        }
+        if succeeded {
+            report_success();
+        }
+    }
+}
+
+/// Scan up to `BLOCK_BATCH_SIZE` blocks after the bridge cursor with
+/// `strata_getExecUpdateById` and store their deposits and withdrawals.
+///
+/// Blocks are scanned in height order and the scan stops at the first missing block or
+/// failed fetch, to resume from there on the next pass.
+async fn index_bridge_intents<R: BlockRepository + SyncStateRepository + BridgeIntentRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let from = match repository
+        .get_sync_cursor(SyncCursor::BridgeScanned)
+        .await?
+    {
+        Some(scanned) => scanned + 1,
+        None => match repository.get_index_range().await?.first_block {
+            Some(first_block) => PgU64(first_block).to_i64(),
+            None => return Ok(()),
+        },
+    };
+    let blocks = repository
+        .get_blocks_in_range(from, from.saturating_add(BLOCK_BATCH_SIZE - 1))
+        .await?;
+
+    let mut intents = Vec::new();
+    let mut scanned = None;
+    for (expected, block) in (from..).zip(blocks) {
+        if block.height != expected || token.is_cancelled() {
+            break;
+        }
+        let update = match fetcher
+            .fetch_data_by_id::<RpcExecUpdate>("strata_getExecUpdateById", &block.block_hash)
+            .await
+        {
+            Ok(update) => update,
+            Err(e) => {
+                warn!(
+                    "Failed to fetch the exec update of block {}: {}",
+                    PgU64::i64_to_u64(block.height),
+                    e
+                );
+                break;
+            }
+        };
+        match update {
+            Some(update) => intents.extend(update.into_intents(block.height, &block.block_hash)),
+            None => debug!(
+                "Block {} has no exec update",
+                PgU64::i64_to_u64(block.height)
+            ),
+        }
+        scanned = Some(block.height);
+    }
+
+    if let Some(scanned) = scanned {
+        let inserted = repository
+            .insert_bridge_intents(from, scanned, intents)
+            .await?;
+        debug!(
+            "Stored {} bridge intents up to block {}",
+            inserted,
+            PgU64::i64_to_u64(scanned)
+        );
+    }
+    Ok(())
+}
+
+/// Store the duties listed by `strata_getBridgeDuties` for operator `operator_idx` since the
+/// last listing, and move its cursor to the `stop_index` returned.
+///
+/// The duties say which operator signs a deposit or pays out a withdrawal, which the exec
+/// updates do not. They are not tied to a block, so the intents are still located by the
+/// exec updates and the duties are matched to them by destination.
+async fn index_bridge_duties<R: BridgeIntentRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    operator_idx: u32,
+) -> anyhow::Result<()> {
+    let start_index = repository
+        .get_bridge_duty_cursor(operator_idx as i32)
+        .await?
+        .map_or(0, PgU64::i64_to_u64);
+    let Some(listing) = fetcher
+        .fetch_data_with_params::<RpcBridgeDuties>(
+            "strata_getBridgeDuties",
+            json!([operator_idx, start_index]),
+        )
+        .await?
+    else {
+        debug!("Bridge operator {} has no duties", operator_idx);
+        return Ok(());
+    };
+
+    let seen_at = unix_timestamp();
+    let duties = listing
+        .duties
+        .into_iter()
+        .map(|duty| duty.into_model(operator_idx, seen_at))
+        .collect();
+    let inserted = repository
+        .insert_bridge_duties(
+            operator_idx as i32,
+            PgU64(listing.stop_index).to_i64(),
+            duties,
+        )
+        .await?;
+    debug!(
+        "Stored {} duties of bridge operator {} up to index {}",
+        inserted, operator_idx, listing.stop_index
+    );
+    Ok(())
+}
+
+/// Deposits and withdrawals of one checkpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckpointBridgeIntents {
+    pub checkpoint_idx: u64,
+    pub deposits: Vec<RpcBridgeIntent>,
+    pub withdrawals: Vec<RpcBridgeIntent>,
+}
+
+/// Bridge intents of the blocks of checkpoint `idx`, `None` if it is not stored
+pub async fn checkpoint_bridge_intents<R: CheckpointRepository + BridgeIntentRepository>(
+    repository: &R,
+    idx: i64,
+) -> anyhow::Result<Option<CheckpointBridgeIntents>> {
+    let Some(checkpoint) = repository
+        .get_checkpoints_in_range(idx, idx)
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Ok(None);
+    };
+
+    let mut deposits = Vec::new();
+    let mut withdrawals = Vec::new();
+    for intent in repository
+        .get_bridge_intents_in_range(checkpoint.l2_start, checkpoint.l2_end)
+        .await?
+    {
+        let is_deposit = intent.kind == IntentKind::Deposit.kind();
+        let duties = repository
+            .get_bridge_duties_by_destination(&intent.destination)
+            .await?;
+        let intent = RpcBridgeIntent::new(intent, Some(&checkpoint), &duties);
+        if is_deposit {
+            deposits.push(intent);
+        } else {
+            withdrawals.push(intent);
+        }
+    }
+    Ok(Some(CheckpointBridgeIntents {
+        checkpoint_idx: PgU64::i64_to_u64(checkpoint.idx),
+        deposits,
+        withdrawals,
+    }))
+}
+
+/// Withdrawals requested by transaction `txid` along with the checkpoint finalizing them
+pub async fn withdrawal_status<R: CheckpointRepository + BridgeIntentRepository>(
+    repository: &R,
+    txid: &str,
+) -> anyhow::Result<Vec<RpcBridgeIntent>> {
+    let intents = repository
+        .get_bridge_intents_by_txid(&bridge_intent::normalize_txid(txid))
+        .await?;
+
+    let mut statuses = Vec::with_capacity(intents.len());
+    for intent in intents {
+        let checkpoint = checkpoint_at_height(repository, intent.l2_height).await?;
+        let duties = repository
+            .get_bridge_duties_by_destination(&intent.destination)
+            .await?;
+        statuses.push(RpcBridgeIntent::new(intent, checkpoint.as_ref(), &duties));
+    }
+    Ok(statuses)
+}
+
+/// The stored checkpoint whose L2 range covers `height`
+async fn checkpoint_at_height<R: CheckpointRepository>(
+    repository: &R,
+    height: i64,
+) -> anyhow::Result<Option<checkpoint::Model>> {
+    let Some(idx) = repository.get_checkpoint_idx_by_l2_height(height).await? else {
+        return Ok(None);
+    };
+    Ok(repository
+        .get_checkpoints_in_range(idx, idx)
+        .await?
+        .into_iter()
+        .next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webhook_service::WebhookNotifier;
+    use crate::utils::fake_fullnode::{block_hash, block_header, checkpoint_info, FakeFullnode};
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+    use database::repository::{BridgeIntentRepository, SyncStateRepository};
+    use serde_json::json;
+
+    /// Checkpoint 0 with its blocks 0 to 4, a deposit in block 1 and a withdrawal in block 3
+    async fn setup<R: TestRepository>() -> (FakeFullnode, R) {
+        let repository = R::open().await;
+        let checkpoint = checkpoint_info(0, (0, 4), "finalized", "aa");
+        repository
+            .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+            .await;
+        for height in 0..=4 {
+            repository
+                .insert_block(block_header(height), PgU64(0).to_i64())
+                .await
+                .unwrap();
+        }
+
+        let fullnode = FakeFullnode::default();
+        let mut chain = fullnode.chain();
+        for height in [0, 2, 4] {
+            chain
+                .exec_updates
+                .insert(block_hash(height), json!({ "update_idx": height }));
+        }
+        let deposit = json!({ "intent_idx": 7, "amt": 1000, "dest_addr": "0xabcd" });
+        chain.exec_updates.insert(
+            block_hash(1),
+            json!({ "update_idx": 1, "deposits": [deposit] }),
+        );
+        let withdrawal = json!({ "amt": 900, "destination": "bc1q", "withdrawal_txid": "0x01" });
+        chain.exec_updates.insert(
+            block_hash(3),
+            json!({ "update_idx": 3, "withdrawals": [withdrawal] }),
+        );
+        drop(chain);
+        (fullnode, repository)
+    }
+
+    /// Kinds and heights of the stored intents, and the bridge cursor
+    async fn indexed(
+        repository: &(impl BridgeIntentRepository + SyncStateRepository),
+    ) -> (Vec<(String, u64)>, Option<u64>) {
+        let intents = repository
+            .get_bridge_intents_in_range(i64::MIN, i64::MAX)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|intent| (intent.kind, PgU64::i64_to_u64(intent.l2_height)))
+            .collect();
+        let scanned = repository
+            .get_sync_cursor(SyncCursor::BridgeScanned)
+            .await
+            .unwrap()
+            .map(PgU64::i64_to_u64);
+        (intents, scanned)
+    }
+
+    fn intent(kind: IntentKind, height: u64) -> (String, u64) {
+        (kind.kind().to_string(), height)
+    }
+
+    async fn indexes_the_intents_of_the_stored_blocks<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>().await;
+        let fetcher = fullnode.serve();
+
+        index_bridge_intents(&fetcher, &repository, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        let expected = vec![
+            intent(IntentKind::Deposit, 1),
+            intent(IntentKind::Withdrawal, 3),
+        ];
+        assert_eq!(indexed(&repository).await, (expected, Some(4)));
+    }
+
+    async fn resumes_the_scan_at_the_first_failed_block<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>().await;
+        fullnode.chain().failing.insert(block_hash(3));
+        let fetcher = fullnode.serve();
+
+        index_bridge_intents(&fetcher, &repository, &CancellationToken::new())
+            .await
+            .unwrap();
+        let expected = vec![intent(IntentKind::Deposit, 1)];
+        assert_eq!(indexed(&repository).await, (expected, Some(2)));
+
+        fullnode.chain().failing.clear();
+        index_bridge_intents(&fetcher, &repository, &CancellationToken::new())
+            .await
+            .unwrap();
+        let expected = vec![
+            intent(IntentKind::Deposit, 1),
+            intent(IntentKind::Withdrawal, 3),
+        ];
+        assert_eq!(indexed(&repository).await, (expected, Some(4)));
+    }
+
+    async fn waits_for_blocks_to_scan<R: TestRepository>() {
+        let repository = R::open().await;
+        let fetcher = FakeFullnode::default().serve();
+
+        index_bridge_intents(&fetcher, &repository, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(indexed(&repository).await, (vec![], None));
+    }
+
+    fn sign_deposit(outpoint: &str, el_address: &str, amount: u64) -> serde_json::Value {
+        json!({ "type": "SignDeposit", "payload": {
+            "deposit_request_outpoint": outpoint,
+            "el_address": el_address,
+            "total_amount": amount,
+            "take_back_leaf_hash": "00",
+            "original_taproot_addr": { "address": "bc1p", "network": "regtest" },
+        }})
+    }
+
+    fn fulfill_withdrawal(outpoint: &str, destination: &str) -> serde_json::Value {
+        json!({ "type": "FulfillWithdrawal", "payload": {
+            "deposit_outpoint": outpoint,
+            "user_destination": destination,
+            "assigned_operator_idx": 0,
+            "exec_deadline": 150,
+        }})
+    }
+
+    async fn duty_cursor(
+        repository: &impl BridgeIntentRepository,
+        operator_idx: i32,
+    ) -> Option<u64> {
+        let cursor = repository
+            .get_bridge_duty_cursor(operator_idx)
+            .await
+            .unwrap();
+        cursor.map(PgU64::i64_to_u64)
+    }
+
+    /// Operator and outpoint of the duties paying to or crediting `destination`
+    async fn duties(repository: &impl BridgeIntentRepository, destination: &str) -> Vec<String> {
+        repository
+            .get_bridge_duties_by_destination(destination)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|duty| format!("{}/{}", duty.operator_idx, duty.outpoint))
+            .collect()
+    }
+
+    async fn indexes_the_duties_of_an_operator_from_its_cursor<R: TestRepository>() {
+        let repository = R::open().await;
+        let fullnode = FakeFullnode::default();
+        fullnode.chain().bridge_duties.insert(
+            0,
+            vec![
+                sign_deposit("aa:0", "0xabcd", 1000),
+                fulfill_withdrawal("bb:0", "bc1q"),
+            ],
+        );
+        let fetcher = fullnode.serve();
+
+        index_bridge_duties(&fetcher, &repository, 0).await.unwrap();
+        assert_eq!(duty_cursor(&repository, 0).await, Some(2));
+        assert_eq!(duties(&repository, "0xabcd").await, ["0/aa:0"]);
+        assert_eq!(duties(&repository, "bc1q").await, ["0/bb:0"]);
+
+        // the next listing starts where the last one stopped
+        fullnode
+            .chain()
+            .bridge_duties
+            .get_mut(&0)
+            .unwrap()
+            .push(fulfill_withdrawal("cc:1", "bc1q"));
+        index_bridge_duties(&fetcher, &repository, 0).await.unwrap();
+        assert_eq!(duty_cursor(&repository, 0).await, Some(3));
+        assert_eq!(duties(&repository, "bc1q").await, ["0/bb:0", "0/cc:1"]);
+    }
+
+    async fn keeps_the_duty_cursor_when_the_listing_fails<R: TestRepository>() {
+        let repository = R::open().await;
+        let fullnode = FakeFullnode::default();
+        fullnode
+            .chain()
+            .bridge_duties
+            .insert(1, vec![sign_deposit("aa:0", "0xabcd", 1000)]);
+        fullnode.chain().failing.insert("1".to_string());
+        let fetcher = fullnode.serve();
+
+        assert!(index_bridge_duties(&fetcher, &repository, 1).await.is_err());
+        assert_eq!(duty_cursor(&repository, 1).await, None);
+        assert!(duties(&repository, "0xabcd").await.is_empty());
+
+        // an operator without duties is not an error and keeps no cursor either
+        index_bridge_duties(&fetcher, &repository, 2).await.unwrap();
+        assert_eq!(duty_cursor(&repository, 2).await, None);
+    }
+
+    async fn reports_the_duties_serving_each_intent<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>().await;
+        fullnode.chain().bridge_duties.insert(
+            0,
+            vec![
+                sign_deposit("aa:0", "0xabcd", 1000),
+                // same address, another amount: another deposit
+                sign_deposit("aa:1", "0xabcd", 5000),
+                fulfill_withdrawal("bb:0", "bc1q"),
+            ],
+        );
+        let fetcher = fullnode.serve();
+        index_bridge_intents(&fetcher, &repository, &CancellationToken::new())
+            .await
+            .unwrap();
+        index_bridge_duties(&fetcher, &repository, 0).await.unwrap();
+
+        let withdrawals = withdrawal_status(&repository, "0x01").await.unwrap();
+        let outpoints: Vec<_> = withdrawals[0]
+            .duties
+            .iter()
+            .map(|duty| {
+                (
+                    duty.operator_idx,
+                    duty.outpoint.as_str(),
+                    duty.exec_deadline,
+                )
+            })
+            .collect();
+        assert_eq!(outpoints, [(0, "bb:0", Some(150))]);
+
+        let intents = checkpoint_bridge_intents(&repository, PgU64(0).to_i64())
+            .await
+            .unwrap()
+            .unwrap();
+        let outpoints: Vec<_> = intents.deposits[0]
+            .duties
+            .iter()
+            .map(|duty| duty.outpoint.as_str())
+            .collect();
+        assert_eq!(outpoints, ["aa:0"]);
+    }
+
+    repository_tests!(
+        indexes_the_intents_of_the_stored_blocks,
+        resumes_the_scan_at_the_first_failed_block,
+        waits_for_blocks_to_scan,
+        indexes_the_duties_of_an_operator_from_its_cursor,
+        keeps_the_duty_cursor_when_the_listing_fails,
+        reports_the_duties_serving_each_intent,
+    );
+}
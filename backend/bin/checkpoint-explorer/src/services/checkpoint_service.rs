@@ -1,42 +1,143 @@
-use crate::services::block_service::CheckpointFetch;
-use database::connection::DatabaseWrapper;
-use database::services::{block_service::BlockService, checkpoint_service::CheckpointService};
+use crate::services::block_service::BlockFetchQueue;
+use crate::services::lifecycle_service::L1BlockTimes;
+use crate::services::webhook_service::WebhookNotifier;
+use crate::utils::supervisor::report_success;
+use database::repository::{
+    BlockFetchJobRepository, CheckpointReconciliation, CheckpointRepository, L1BlockRepository,
+    SyncStateRepository,
+};
 use fullnode_client::fetcher::StrataFetcher;
-use model::checkpoint::RpcCheckpointInfo;
+use model::checkpoint::{CheckpointState, RpcCheckpointInfo};
+use model::l1_block;
 use model::pgu64::PgU64;
+use model::sync_state::SyncCursor;
 use std::cmp::min;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::mpsc::Sender;
-use tracing::{error, info, warn};
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Range of checkpoints the indexer fetches
+#[derive(Debug, Clone, Copy)]
+pub struct IndexBounds {
+    /// First checkpoint, the continuity checks treat it as the genesis
+    pub start: u64,
+    /// Last checkpoint, `None` to follow the fullnode tip
+    pub end: Option<u64>,
+}
+
+/// Record `bounds` as the index range of the database.
+///
+/// The start can only be chosen on a fresh database: once checkpoints are stored, moving it
+/// would either leave a hole in front of them or index checkpoints the continuity checks
+/// reject, so this fails and the database has to be wiped first. The end can be changed
+/// at any time.
+pub async fn declare_index_range<R: SyncStateRepository>(
+    repository: &R,
+    bounds: IndexBounds,
+) -> anyhow::Result<()> {
+    if let Some(end) = bounds.end.filter(|end| *end < bounds.start) {
+        anyhow::bail!(
+            "The end checkpoint {} is before the start checkpoint {}",
+            end,
+            bounds.start
+        );
+    }
+    let range = repository.get_index_range().await?;
+    if let Some(first_checkpoint) = range.first_checkpoint {
+        if range.start_checkpoint != bounds.start {
+            anyhow::bail!(
+                "The database is indexed from checkpoint {} (stored checkpoints {} to {}), it cannot be re-indexed from checkpoint {}. Start from checkpoint {} or use a fresh database.",
+                range.start_checkpoint,
+                first_checkpoint,
+                range.last_checkpoint.unwrap_or(first_checkpoint),
+                bounds.start,
+                range.start_checkpoint
+            );
+        }
+    }
+
+    repository
+        .declare_index_range(
+            PgU64(bounds.start).to_i64(),
+            bounds.end.map(|end| PgU64(end).to_i64()),
+        )
+        .await?;
+    match bounds.end {
+        Some(end) => info!("Indexing checkpoints {} to {}", bounds.start, end),
+        None => info!(
+            "Indexing checkpoints from {} up to the fullnode tip",
+            bounds.start
+        ),
+    }
+    Ok(())
+}
 
 /// This function fetches the checkpoints from the fullnode and inserts them into the database
-/// It will run in a loop with a delay of `fetch_interval` seconds
-pub async fn start_checkpoint_fetcher(
+/// It will run in a loop with a delay of `fetch_interval` seconds until `token` is cancelled
+#[allow(clippy::too_many_arguments)]
+pub async fn start_checkpoint_fetcher<
+    R: CheckpointRepository
+        + SyncStateRepository
+        + BlockFetchJobRepository
+        + L1BlockRepository
+        + 'static,
+>(
     fetcher: Arc<StrataFetcher>,
-    database: Arc<DatabaseWrapper>,
-    tx: Sender<CheckpointFetch>,
+    repository: Arc<R>,
+    queue: BlockFetchQueue<R>,
+    notifier: WebhookNotifier,
+    l1_times: L1BlockTimes,
     fetch_interval: u64,
+    bounds: IndexBounds,
+    token: CancellationToken,
 ) {
     info!("Starting checkpoint fetcher...");
     let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(fetch_interval));
 
     loop {
-        interval.tick().await;
-        match fetch_checkpoints(fetcher.clone(), database.clone(), tx.clone()).await {
-            Ok(_) => (),
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        match fetch_checkpoints(
+            fetcher.clone(),
+            repository.clone(),
+            &queue,
+            &notifier,
+            &l1_times,
+            bounds,
+            &token,
+        )
+        .await
+        {
+            Ok(_) => report_success(),
             Err(e) => tracing::error!("Error fetching checkpoints: {}", e),
         }
     }
 }
 
 /// This function fetches the checkpoints from the fullnode and inserts them into the database
-/// It then sends the checkpoint index to the block fetcher task to fetch the corresponding block
-async fn fetch_checkpoints(
+/// It then queues the checkpoint for the block fetcher to fetch the corresponding blocks
+/// and a `checkpoint.created` event for the webhooks
+/// The first and last L1 blocks of the checkpoint are stored, the L1 block indexer fills in
+/// the rest
+/// Checkpoints already confirmed get the time of their L1 block, if it can be looked up
+/// Only checkpoints within `bounds` are fetched
+async fn fetch_checkpoints<R>(
     fetcher: Arc<StrataFetcher>,
-    database: Arc<DatabaseWrapper>,
-    tx: Sender<CheckpointFetch>,
-) -> anyhow::Result<()> {
-    let checkpoint_db = CheckpointService::new(&database.db);
+    repository: Arc<R>,
+    queue: &BlockFetchQueue<R>,
+    notifier: &WebhookNotifier,
+    l1_times: &L1BlockTimes,
+    bounds: IndexBounds,
+    token: &CancellationToken,
+) -> anyhow::Result<()>
+where
+    R: CheckpointRepository + SyncStateRepository + BlockFetchJobRepository + L1BlockRepository,
+{
     info!("Fetching checkpoints from fullnode...");
     let fullnode_last_checkpoint = fetcher
         .get_latest_index("strata_getLatestCheckpointIndex")
@@ -46,11 +147,23 @@ async fn fetch_checkpoints(
         warn!("Failed to fetch latest checkpoint index from fullnode or no checkpoint yet.");
         return Ok(());
     }
-    let fn_chkpt_i64 = PgU64(fullnode_last_checkpoint.unwrap()).to_i64();
-    let starting_checkpoint = get_starting_checkpoint_idx(database.clone()).await?;
+    let mut last_checkpoint = fullnode_last_checkpoint.unwrap();
+    if let Some(end) = bounds.end.filter(|end| *end < last_checkpoint) {
+        debug!(
+            "Fullnode is at checkpoint {}, indexing stops at checkpoint {}",
+            last_checkpoint, end
+        );
+        last_checkpoint = end;
+    }
+    let fn_chkpt_i64 = PgU64(last_checkpoint).to_i64();
+    let starting_checkpoint =
+        get_starting_checkpoint_idx(repository.as_ref(), bounds.start).await?;
     info!("latest checkpoint index in fullnode: {}, local checkpoint to start block indexing from: {}", PgU64::i64_to_u64(fn_chkpt_i64), PgU64::i64_to_u64(starting_checkpoint));
     for idx in (starting_checkpoint)..=fn_chkpt_i64 {
-        if !checkpoint_db.checkpoint_exists(idx).await {
+        if token.is_cancelled() {
+            return Ok(());
+        }
+        if !repository.checkpoint_exists(idx).await {
             info!(
                 "Checkpoint does not exist in db, fetching checkpoint with idx {}",
                 PgU64::i64_to_u64(idx)
@@ -61,180 +174,539 @@ async fn fetch_checkpoints(
                 .await
             {
                 // info!("Inserting checkpoint with idx {}", idx);
-                checkpoint_db.insert_checkpoint(checkpoint.clone()).await;
+                repository
+                    .insert_checkpoint(checkpoint.clone(), notifier)
+                    .await;
+                // the insert is skipped when the previous checkpoint is missing
+                if repository.checkpoint_exists(idx).await {
+                    if let Err(e) = repository
+                        .insert_l1_blocks(l1_block::Model::from_checkpoint_range(&checkpoint))
+                        .await
+                    {
+                        warn!("Failed to store the L1 range of checkpoint {}: {:?}", i, e);
+                    }
+                    l1_times
+                        .record_confirmation(repository.as_ref(), &checkpoint)
+                        .await;
+                }
+            } else {
+                // the integrity checker picks up the hole if it is never filled
+                warn!("Failed to fetch checkpoint with idx {}", i);
             }
         }
-        let range = CheckpointFetch::new(idx);
-        tx.send(range).await?;
+        queue.enqueue(idx).await?;
     }
     Ok(())
 }
 
 /// It is a helper function that returns the starting checkpoint index to start fetching from
-/// It will return the minimum of the last checkpoint in the database and the checkpoint correcpoinding to
-/// last block in the database
-async fn get_starting_checkpoint_idx(db: Arc<DatabaseWrapper>) -> anyhow::Result<i64> {
-    let checkpoint_db = CheckpointService::new(&db.db);
-    let block_db = BlockService::new(&db.db);
-
-    let last_block = block_db.get_latest_block_index().await;
+/// It will return the minimum of the last ingested checkpoint and the checkpoint corresponding to
+/// the last ingested block, as recorded by the sync cursors, and never before `start`
+async fn get_starting_checkpoint_idx<R: CheckpointRepository + SyncStateRepository>(
+    repository: &R,
+    start: u64,
+) -> anyhow::Result<i64> {
+    let first_checkpoint = PgU64(start).to_i64();
 
-    let local_last_checkpoint = checkpoint_db
-        .get_latest_checkpoint_index()
-        .await
-        .unwrap_or(-1);
-    // if we do not have a checkpoint in db start from 0
-    if local_last_checkpoint == -1 {
-        return Ok(i64::MIN);
-    }
-    // we are calling it probable_* to consider some weirdest condition when
-    // we have the block but no any earlier checkpoint (before where block corresponds)
-    let probable_starting_checkpoint: i64 = if let Some(block_height) = last_block {
-        checkpoint_db
-            .get_checkpoint_idx_by_block_height(block_height)
-            .await?
-            .unwrap_or_default()
-    } else {
-        i64::MIN
+    // if we have not ingested a checkpoint yet start from the start of the index
+    let Some(last_checkpoint) = repository
+        .get_sync_cursor(SyncCursor::CheckpointIngested)
+        .await?
+    else {
+        return Ok(first_checkpoint);
+    };
+    // blocks of every checkpoint are still to be fetched
+    let Some(last_block) = repository
+        .get_sync_cursor(SyncCursor::BlockIngested)
+        .await?
+    else {
+        return Ok(first_checkpoint);
     };
 
-    Ok(min(probable_starting_checkpoint, local_last_checkpoint))
+    let last_block_checkpoint = repository
+        .get_checkpoint_idx_by_block_height(last_block)
+        .await?
+        .unwrap_or(last_checkpoint);
+
+    Ok(min(last_block_checkpoint, last_checkpoint).max(first_checkpoint))
+}
+
+/// How often checkpoints are compared against the fullnode, depending on their state
+#[derive(Debug, Clone, Copy)]
+pub struct StatusRevisitIntervals {
+    /// Checkpoints without a status yet and checkpoints pending on L1
+    pub pending: Duration,
+    /// Checkpoints confirmed on L1 and waiting to be finalized
+    pub confirmed: Duration,
+}
+
+impl StatusRevisitIntervals {
+    /// Revisit interval of checkpoints in `state`, `None` for the final state
+    pub fn for_state(&self, state: CheckpointState) -> Option<Duration> {
+        match state {
+            CheckpointState::Unknown | CheckpointState::Pending => Some(self.pending),
+            CheckpointState::Confirmed => Some(self.confirmed),
+            CheckpointState::Finalized => None,
+        }
+    }
 }
 
-/// This function starts the checkpoint status updater task
-pub async fn start_checkpoint_status_updater_task(
+/// This function starts the checkpoint status reconciler.
+///
+/// Every non-final state is revisited at its own interval, a state falling due on a tick
+/// gets all of its checkpoints reconciled with the fullnode. Runs until `token` is cancelled.
+pub async fn start_checkpoint_status_updater_task<R: CheckpointRepository + 'static>(
     fetcher: Arc<StrataFetcher>,
-    database: Arc<DatabaseWrapper>,
-    update_interval: u64,
+    repository: Arc<R>,
+    notifier: WebhookNotifier,
+    l1_times: L1BlockTimes,
+    intervals: StatusRevisitIntervals,
+    token: CancellationToken,
 ) {
-    info!("Starting checkpoint status updater...");
-
-    // Spawn the "pending" checkpoint updater loop
-    let fetcher_clone = fetcher.clone();
-    let database_clone = database.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(update_interval));
+    info!("Starting checkpoint status reconciler...");
+    let tick = CheckpointState::ALL
+        .into_iter()
+        .filter_map(|state| intervals.for_state(state))
+        .min()
+        .unwrap_or(intervals.pending);
+    let mut interval = tokio::time::interval(tick);
+    let mut next_visits: HashMap<CheckpointState, Instant> = HashMap::new();
 
-        loop {
-            interval.tick().await;
+    loop {
+        let now = tokio::select! {
+            _ = token.cancelled() => return,
+            now = interval.tick() => now,
+        };
 
-            if let Err(e) =
-                update_checkpoints_status(fetcher_clone.clone(), database_clone.clone(), "pending")
-                    .await
-            {
-                tracing::error!("Error fetching pending checkpoints: {}", e);
+        let mut due_states = Vec::new();
+        for state in CheckpointState::ALL {
+            let Some(revisit_interval) = intervals.for_state(state) else {
+                continue;
+            };
+            if next_visits.get(&state).is_some_and(|next| *next > now) {
+                continue;
             }
+            next_visits.insert(state, now + revisit_interval);
+            due_states.push(state);
         }
-    });
-
-    // Spawn the "confirmed" checkpoint updater loop
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(update_interval));
 
-        loop {
-            interval.tick().await;
-
-            if let Err(e) =
-                update_checkpoints_status(fetcher.clone(), database.clone(), "confirmed").await
-            {
-                tracing::error!("Error fetching confirmed checkpoints: {}", e);
-            }
+        match update_checkpoints_status(
+            fetcher.as_ref(),
+            repository.as_ref(),
+            &notifier,
+            &l1_times,
+            &due_states,
+            &token,
+        )
+        .await
+        {
+            Ok(()) => report_success(),
+            Err(e) => error!("Error reconciling checkpoint statuses: {}", e),
         }
-    });
+    }
 }
 
-/// This function continuously updates the status of the checkpoints which are yet to be finalized.
+/// This function reconciles the status of the checkpoints in the given states.
 ///
-/// This algorithm works on the assumptions that the checkpoints must get finalized in the order they are created.
-/// i.e. (n-1)th checkpoint gets finalized before (n)th.
+/// An L1 reorg can send a checkpoint back from confirmed to pending or give it a new txid,
+/// so checkpoints are not assumed to finalize in order and every checkpoint in a due state
+/// is compared against the fullnode on each pass.
 ///
 /// ** Algorithm **
-/// 1. Get the earliest checkpoint idx whose status is Either pending or Confirmed
-/// 2. Fetch the checkpoint from fullnode
-/// 3. If the checkpoint status is different from the one in the database,
-///    a. update the status may be
-///    b. increment the idx and go to step 2
-/// 4. Else break the loop
-async fn update_checkpoints_status(
-    fetcher: Arc<StrataFetcher>,
-    database: Arc<DatabaseWrapper>,
-    status: &str,
+/// 1. Get all checkpoint idxs whose status is one of `states`
+/// 2. For each of them, fetch the checkpoint from fullnode
+/// 3. Reconcile the stored checkpoint with the fetched one. Transitions the checkpoint
+///    lifecycle does not allow are rejected, reorgs are recorded as reorg events
+/// 4. Queue webhook events for the status and txid changes and the reorgs
+/// 5. Record the L1 block time of newly confirmed checkpoints
+async fn update_checkpoints_status<R: CheckpointRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    notifier: &WebhookNotifier,
+    l1_times: &L1BlockTimes,
+    states: &[CheckpointState],
+    token: &CancellationToken,
 ) -> anyhow::Result<()> {
-    let checkpoint_db = CheckpointService::new(&database.db);
-
-    let mut idx = -1;
-    if status == "pending" {
-        idx = match checkpoint_db.get_earliest_pending_checkpoint_idx().await {
-            Some(i) => i,
-            None => {
-                info!("No more pending checkpoints locally.");
-                return Ok(());
-            }
-        };
-    } else if status == "confirmed" {
-        idx = match checkpoint_db.get_earliest_confirmed_checkpoint_idx().await {
-            Some(i) => i,
-            None => {
-                info!("No more confirmed checkpoints locally.");
-                return Ok(());
-            }
-        };
+    if states.is_empty() {
+        return Ok(());
+    }
+    let statuses: Vec<&str> = states.iter().map(CheckpointState::status).collect();
+    let idxs = repository.get_checkpoint_idxs_by_status(&statuses).await?;
+    if idxs.is_empty() {
+        info!("No checkpoints to reconcile in states {:?}", states);
+        return Ok(());
     }
 
-    loop {
-        // This is the stopping condition for the loop. If the checkpoint is not found in the database,
-        // break the loop as we have already updated all the checkpoints.
-        let Some(checkpoint_in_db) = checkpoint_db.get_checkpoint_by_idx(idx).await else {
-            info!("Status of all checkpoints in db is already updated.");
+    for idx in idxs {
+        if token.is_cancelled() {
             return Ok(());
+        }
+        reconcile_checkpoint_status(fetcher, repository, notifier, l1_times, idx).await;
+    }
+    Ok(())
+}
+
+/// Fetch the checkpoint `idx` from the fullnode and reconcile the stored one with it,
+/// notifying the webhooks of any change and recording the L1 block time of a new
+/// confirmation, returns whether the checkpoint could be reconciled
+pub(crate) async fn reconcile_checkpoint_status<R: CheckpointRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    notifier: &WebhookNotifier,
+    l1_times: &L1BlockTimes,
+    idx: i64,
+) -> bool {
+    let i = PgU64::from_i64(idx).0;
+
+    let Ok(checkpoint_from_rpc) = fetcher
+        .fetch_data::<RpcCheckpointInfo>("strata_getCheckpointInfo", i)
+        .await
+    else {
+        warn!("Checkpoint not found in fullnode for idx {}", i);
+        return false;
+    };
+
+    let reconciliation = repository
+        .reconcile_checkpoint(idx, checkpoint_from_rpc.clone(), notifier)
+        .await;
+    if let Ok(reconciliation) = &reconciliation {
+        let confirmed_again = match reconciliation {
+            CheckpointReconciliation::Updated { before, after } => {
+                after.confirmed_at != before.confirmed_at
+            }
+            CheckpointReconciliation::Reorged(_) => true,
+            _ => false,
         };
+        if confirmed_again {
+            l1_times
+                .record_confirmation(repository, &checkpoint_from_rpc)
+                .await;
+        }
+    }
+    match reconciliation {
+        Ok(CheckpointReconciliation::Unchanged) => true,
+        Ok(CheckpointReconciliation::Updated { .. }) => {
+            info!("Updated checkpoint status: idx={}", i);
+            true
+        }
+        Ok(CheckpointReconciliation::Reorged(event)) => {
+            info!(
+                "Reconciled reorged checkpoint: idx={}, status={}",
+                i, event.new_status
+            );
+            true
+        }
+        Ok(CheckpointReconciliation::Rejected { from, to }) => {
+            error!(
+                "Fullnode reported checkpoint {} going from {} to {}, keeping it {}",
+                i, from, to, from
+            );
+            true
+        }
+        Err(e) => {
+            error!("Error reconciling checkpoint {}: {:?}", i, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fake_fullnode::{block_header, checkpoint_info, FakeFullnode};
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+    use model::webhook_delivery::WebhookEventType;
 
-        let i = PgU64::from_i64(idx).0;
+    const WEBHOOK_URL: &str = "http://127.0.0.1:9/hook";
+
+    /// Store checkpoint 0 as `stored` and have the fullnode report it as `reported`, then
+    /// reconcile it. Returns whether it could be reconciled, the stored status and txid, the
+    /// queued webhook events and the number of reorg events.
+    async fn reconcile<R: TestRepository>(
+        stored: (&str, &str),
+        reported: Option<(&str, &str)>,
+    ) -> (bool, (String, String), Vec<String>, usize) {
+        let repository = R::open().await;
+        let checkpoint = checkpoint_info(0, (0, 4), stored.0, stored.1);
+        repository
+            .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+            .await;
+        let fullnode = FakeFullnode::default();
+        if let Some((status, txid)) = reported {
+            fullnode.add_checkpoint(0, (0, 4), status, txid);
+        }
+        let notifier = WebhookNotifier::new(vec![WEBHOOK_URL.to_string()]);
 
-        let Ok(checkpoint_from_rpc) = fetcher
-            .fetch_data::<RpcCheckpointInfo>("strata_getCheckpointInfo", i)
+        let reconciled = reconcile_checkpoint_status(
+            &fullnode.serve(),
+            &repository,
+            &notifier,
+            &L1BlockTimes::default(),
+            PgU64(0).to_i64(),
+        )
+        .await;
+
+        let checkpoint = repository
+            .get_checkpoints_in_range(PgU64(0).to_i64(), PgU64(0).to_i64())
             .await
-        else {
-            warn!(
-                "Checkpoint not found in fullnode for idx {}",
-                PgU64::i64_to_u64(idx)
+            .unwrap()
+            .remove(0);
+        let events = repository
+            .get_pending_webhook_deliveries(WEBHOOK_URL, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|delivery| delivery.event_type)
+            .collect();
+        let reorgs = repository
+            .get_paginated_reorg_events(1, 10)
+            .await
+            .unwrap()
+            .items
+            .len();
+        (
+            reconciled,
+            (
+                CheckpointState::from_status(&checkpoint.status).to_string(),
+                checkpoint.checkpoint_txid,
+            ),
+            events,
+            reorgs,
+        )
+    }
+
+    fn names(events: &[WebhookEventType]) -> Vec<String> {
+        events
+            .iter()
+            .map(|event| event.name().to_string())
+            .collect()
+    }
+
+    async fn reconciles_the_stored_checkpoint_with_the_fullnode<R: TestRepository>() {
+        let cases = [
+            // unchanged
+            (
+                ("confirmed", "aa"),
+                ("confirmed", "aa"),
+                ("confirmed", "aa"),
+                vec![],
+                0,
+            ),
+            // moves forward and gets its first txid
+            (
+                ("pending", "-"),
+                ("confirmed", "aa"),
+                ("confirmed", "aa"),
+                vec![
+                    WebhookEventType::StatusChanged,
+                    WebhookEventType::TxidChanged,
+                ],
+                0,
+            ),
+            (
+                ("confirmed", "aa"),
+                ("finalized", "aa"),
+                ("finalized", "aa"),
+                vec![WebhookEventType::StatusChanged],
+                0,
+            ),
+            // the txid is replaced by an L1 reorg
+            (
+                ("confirmed", "aa"),
+                ("confirmed", "bb"),
+                ("confirmed", "bb"),
+                vec![WebhookEventType::Reorged],
+                1,
+            ),
+            // back to pending after an L1 reorg
+            (
+                ("confirmed", "aa"),
+                ("pending", "-"),
+                ("pending", "-"),
+                vec![WebhookEventType::Reorged],
+                1,
+            ),
+            // a finalized checkpoint cannot move, the transition is rejected
+            (
+                ("finalized", "aa"),
+                ("pending", "-"),
+                ("finalized", "aa"),
+                vec![],
+                0,
+            ),
+            (
+                ("finalized", "aa"),
+                ("finalized", "bb"),
+                ("finalized", "aa"),
+                vec![],
+                0,
+            ),
+        ];
+        for (stored, reported, expected, events, reorgs) in cases {
+            let outcome = reconcile::<R>(stored, Some(reported)).await;
+            let expected = (
+                true,
+                (expected.0.to_string(), expected.1.to_string()),
+                names(&events),
+                reorgs,
             );
-            return Ok(());
-        };
+            assert_eq!(outcome, expected, "{:?} reported as {:?}", stored, reported);
+        }
+    }
 
-        let status = match checkpoint_from_rpc.confirmation_status {
-            Some(status) => status.to_string(),
-            None => {
-                warn!("Checkpoint status is None for idx {}", idx);
-                return Ok(()); // Simply return and continue execution instead of erroring
-            }
-        };
+    async fn keeps_a_checkpoint_the_fullnode_does_not_report<R: TestRepository>() {
+        let outcome = reconcile::<R>(("confirmed", "aa"), None).await;
+        assert_eq!(
+            outcome,
+            (
+                false,
+                ("confirmed".to_string(), "aa".to_string()),
+                vec![],
+                0
+            )
+        );
+    }
 
-        // if there is no change in status, return by doing nothing
-        if checkpoint_in_db
-            .confirmation_status
-            .map_or("-".to_string(), |s| s.to_string())
-            == status.to_string()
-        {
-            // if the status is unchanged then do nothing
-            return Ok(());
+    async fn starts_from_the_checkpoint_of_the_last_stored_block<R: TestRepository>() {
+        let repository = R::open().await;
+        let start = |from| get_starting_checkpoint_idx(&repository, from);
+        // nothing ingested yet
+        assert_eq!(start(0).await.unwrap(), PgU64(0).to_i64());
+        assert_eq!(start(3).await.unwrap(), PgU64(3).to_i64());
+
+        for idx in 0..=2 {
+            let checkpoint = checkpoint_info(idx, (5 * idx, 5 * idx + 4), "finalized", "aa");
+            repository
+                .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+                .await;
         }
+        // the blocks of every checkpoint are still to be fetched
+        assert_eq!(start(0).await.unwrap(), PgU64(0).to_i64());
+
+        for height in 0..=6 {
+            repository
+                .insert_block(block_header(height), PgU64(height / 5).to_i64())
+                .await
+                .unwrap();
+        }
+        assert_eq!(start(0).await.unwrap(), PgU64(1).to_i64());
+        // never before the start of the index
+        assert_eq!(start(2).await.unwrap(), PgU64(2).to_i64());
+    }
+
+    /// Indexes of the checkpoints stored from 0 to 9
+    async fn stored_checkpoints<R: TestRepository>(repository: &R) -> Vec<u64> {
+        repository
+            .get_checkpoints_in_range(PgU64(0).to_i64(), PgU64(9).to_i64())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|checkpoint| PgU64::i64_to_u64(checkpoint.idx))
+            .collect()
+    }
+
+    async fn indexes_only_the_declared_range<R: TestRepository>() {
+        let fullnode = FakeFullnode::default();
+        for idx in 0..=5 {
+            fullnode.add_checkpoint(idx, (5 * idx, 5 * idx + 4), "finalized", "aa");
+        }
+        let repository = Arc::new(R::open().await);
+        let bounds = IndexBounds {
+            start: 2,
+            end: Some(3),
+        };
+        declare_index_range(repository.as_ref(), bounds)
+            .await
+            .unwrap();
 
-        info!(
-            "Updating checkpoint status: idx={}, status={}",
-            PgU64::i64_to_u64(idx),
-            status.clone()
+        let queue = BlockFetchQueue::new(repository.clone());
+        fetch_checkpoints(
+            Arc::new(fullnode.serve()),
+            repository.clone(),
+            &queue,
+            &WebhookNotifier::default(),
+            &L1BlockTimes::default(),
+            bounds,
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        // the first checkpoint of the index passes the continuity checks without checkpoint 1
+        assert_eq!(stored_checkpoints(repository.as_ref()).await, [2, 3]);
+        let range = repository.get_index_range().await.unwrap();
+        assert_eq!((range.start_checkpoint, range.end_checkpoint), (2, Some(3)));
+        let jobs = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!(jobs.queued, 2);
+    }
+
+    async fn refuses_to_move_the_start_of_an_indexed_database<R: TestRepository>() {
+        let repository = R::open().await;
+        let error = declare_index_range(
+            &repository,
+            IndexBounds {
+                start: 3,
+                end: Some(2),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("before the start"), "{}", error);
+
+        let bounds = IndexBounds {
+            start: 2,
+            end: None,
+        };
+        declare_index_range(&repository, bounds).await.unwrap();
+        repository
+            .insert_checkpoint(
+                checkpoint_info(2, (10, 14), "finalized", "aa"),
+                &WebhookNotifier::default(),
+            )
+            .await;
+        let error = declare_index_range(&repository, IndexBounds { start: 0, ..bounds })
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("cannot be re-indexed from checkpoint 0"),
+            "{}",
+            error
         );
-        // update the db with the new checkpoint record instead of tweaking the existing one
-        // as there could be change in both status and txid
-        checkpoint_db
-            .update_checkpoint(idx, checkpoint_from_rpc)
+
+        // the end can still move
+        let bounds = IndexBounds {
+            start: 2,
+            end: Some(8),
+        };
+        declare_index_range(&repository, bounds).await.unwrap();
+        assert_eq!(
+            repository.get_index_range().await.unwrap().end_checkpoint,
+            Some(8)
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn fails_to_find_the_start_when_the_cursors_cannot_be_read() {
+        use sea_orm::ConnectionTrait;
+
+        let repository = database::repository::sql::SqlRepository::open().await;
+        repository
+            .connection()
+            .execute_unprepared("DROP TABLE sync_state")
             .await
-            .map_err(|e| {
-                error!("Error updating checkpoint status: {:?}", e);
-                anyhow::anyhow!("Failed to update checkpoint status")
-            })?;
+            .unwrap();
 
-        idx = idx.saturating_add(1);
+        assert!(get_starting_checkpoint_idx(&repository, 0).await.is_err());
     }
+
+    repository_tests!(
+        reconciles_the_stored_checkpoint_with_the_fullnode,
+        keeps_a_checkpoint_the_fullnode_does_not_report,
+        starts_from_the_checkpoint_of_the_last_stored_block,
+        indexes_only_the_declared_range,
+        refuses_to_move_the_start_of_an_indexed_database,
+    );
 }
@@ -0,0 +1,272 @@
+use crate::utils::supervisor::report_success;
+use database::repository::EvmBlockRepository;
+use fullnode_client::evm::{EvmBlock, EvmClient};
+use fullnode_client::fetcher::StrataFetcher;
+use model::block::{self, RpcBlockHeader};
+use model::evm_block;
+use model::pgu64::PgU64;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Most L2 blocks linked on every pass
+const BLOCK_BATCH_SIZE: u64 = 200;
+
+/// This function links the stored L2 blocks to the EVM blocks of the execution client
+/// It will run in a loop with a delay of `interval` seconds until `token` is cancelled
+pub async fn start_evm_block_linker<R: EvmBlockRepository + 'static>(
+    fetcher: Arc<StrataFetcher>,
+    evm: Arc<EvmClient>,
+    repository: Arc<R>,
+    interval: u64,
+    token: CancellationToken,
+) {
+    info!("Starting EVM block linker...");
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval));
+    // blocks below it were tried on an earlier pass, they are tried again once caught up
+    let mut after: Option<i64> = None;
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let linked = link_evm_blocks(
+            fetcher.as_ref(),
+            evm.as_ref(),
+            repository.as_ref(),
+            &mut after,
+            &token,
+        );
+        match linked.await {
+            Ok(()) => report_success(),
+            Err(e) => warn!("Error linking EVM blocks: {}", e),
+        }
+    }
+}
+
+/// Link up to `BLOCK_BATCH_SIZE` L2 blocks above `after` to the EVM block whose hash is their
+/// `exec_segment_hash`, and move `after` past them.
+///
+/// Blocks stored without the hash get it from `strata_getHeadersAtIdx`. A block the execution
+/// client does not know is skipped until the next round.
+async fn link_evm_blocks<R: EvmBlockRepository>(
+    fetcher: &StrataFetcher,
+    evm: &EvmClient,
+    repository: &R,
+    after: &mut Option<i64>,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let blocks = repository
+        .get_blocks_without_evm_block(*after, BLOCK_BATCH_SIZE)
+        .await?;
+    if blocks.is_empty() {
+        // caught up, start over with the blocks skipped so far
+        *after = None;
+        return Ok(());
+    }
+
+    let mut linked = Vec::new();
+    let mut outcome = Ok(());
+    for block in blocks {
+        if token.is_cancelled() {
+            break;
+        }
+        match evm_block_of(fetcher, evm, &block).await {
+            Ok(Some(evm_block)) => linked.push(evm_block::Model {
+                l2_height: block.height,
+                evm_block_number: PgU64(evm_block.number).to_i64(),
+                evm_block_hash: evm_block.hash,
+                checkpoint_idx: block.checkpoint_idx,
+            }),
+            Ok(None) => debug!(
+                "L2 block {} has no EVM block yet",
+                PgU64::i64_to_u64(block.height)
+            ),
+            Err(e) => {
+                // the execution client is likely down, retry from this block
+                outcome = Err(e);
+                break;
+            }
+        }
+        *after = Some(block.height);
+    }
+
+    let inserted = repository.insert_evm_blocks(linked).await?;
+    debug!("Linked {} L2 blocks to EVM blocks", inserted);
+    outcome
+}
+
+async fn evm_block_of(
+    fetcher: &StrataFetcher,
+    evm: &EvmClient,
+    block: &block::Model,
+) -> anyhow::Result<Option<EvmBlock>> {
+    let exec_segment_hash = match &block.exec_segment_hash {
+        Some(exec_segment_hash) => exec_segment_hash.clone(),
+        None => {
+            let height = PgU64::i64_to_u64(block.height);
+            let headers = fetcher
+                .fetch_data::<Vec<RpcBlockHeader>>("strata_getHeadersAtIdx", height)
+                .await?;
+            let Some(header) = headers
+                .into_iter()
+                .find(|header| header.block_id == block.block_hash)
+            else {
+                warn!("The fullnode does not know L2 block {} anymore", height);
+                return Ok(None);
+            };
+            header.exec_segment_hash
+        }
+    };
+    evm.get_block_by_hash(&exec_segment_hash).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webhook_service::WebhookNotifier;
+    use crate::utils::fake_fullnode::{
+        block_header, checkpoint_info, evm_block_hash, FakeFullnode,
+    };
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+
+    /// Checkpoint 0 with the L2 blocks 0 to `last`, and a fullnode whose execution client
+    /// knows the EVM block `1000 + height` of each of them
+    async fn setup<R: TestRepository>(last: u64) -> (FakeFullnode, R) {
+        let repository = R::open().await;
+        let checkpoint = checkpoint_info(0, (0, last), "finalized", "aa");
+        repository
+            .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+            .await;
+        let fullnode = FakeFullnode::default();
+        for height in 0..=last {
+            repository
+                .insert_block(block_header(height), PgU64(0).to_i64())
+                .await
+                .unwrap();
+            fullnode.add_evm_block(height, 1000 + height);
+        }
+        (fullnode, repository)
+    }
+
+    /// L2 heights linked so far, with the number of their EVM block
+    async fn linked<R: TestRepository>(repository: &R, heights: &[u64]) -> Vec<(u64, u64)> {
+        let mut linked = Vec::new();
+        for &height in heights {
+            let number = PgU64(1000 + height).to_i64();
+            if let Some(evm_block) = repository.get_evm_block_by_number(number).await.unwrap() {
+                assert_eq!(evm_block.evm_block_hash, evm_block_hash(height));
+                linked.push((
+                    PgU64::i64_to_u64(evm_block.l2_height),
+                    PgU64::i64_to_u64(evm_block.evm_block_number),
+                ));
+            }
+        }
+        linked
+    }
+
+    async fn links_blocks_to_the_evm_block_of_their_exec_segment_hash<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>(2).await;
+        let (fetcher, evm) = (fullnode.serve(), fullnode.serve_evm());
+        let mut after = None;
+
+        link_evm_blocks(
+            &fetcher,
+            &evm,
+            &repository,
+            &mut after,
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            linked(&repository, &[0, 1, 2]).await,
+            vec![(0, 1000), (1, 1001), (2, 1002)]
+        );
+        assert_eq!(after, Some(PgU64(2).to_i64()));
+        let by_hash = repository
+            .get_evm_block_by_hash(&evm_block_hash(1))
+            .await
+            .unwrap();
+        assert_eq!(by_hash.unwrap().l2_height, PgU64(1).to_i64());
+    }
+
+    async fn links_at_most_a_batch_per_pass<R: TestRepository>() {
+        let last = BLOCK_BATCH_SIZE + 49;
+        let (fullnode, repository) = setup::<R>(last).await;
+        let (fetcher, evm) = (fullnode.serve(), fullnode.serve_evm());
+        let token = CancellationToken::new();
+        let mut after = None;
+        let heights: Vec<u64> = (0..=last).collect();
+
+        link_evm_blocks(&fetcher, &evm, &repository, &mut after, &token)
+            .await
+            .unwrap();
+        assert_eq!(linked(&repository, &heights).await.len(), 200);
+        assert_eq!(after, Some(PgU64(BLOCK_BATCH_SIZE - 1).to_i64()));
+
+        link_evm_blocks(&fetcher, &evm, &repository, &mut after, &token)
+            .await
+            .unwrap();
+        assert_eq!(linked(&repository, &heights).await.len(), 250);
+        assert_eq!(after, Some(PgU64(last).to_i64()));
+
+        // caught up, the next pass starts over
+        link_evm_blocks(&fetcher, &evm, &repository, &mut after, &token)
+            .await
+            .unwrap();
+        assert_eq!(after, None);
+    }
+
+    async fn skips_a_block_the_execution_client_does_not_know<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>(2).await;
+        fullnode.chain().evm_blocks.remove(&evm_block_hash(1));
+        let (fetcher, evm) = (fullnode.serve(), fullnode.serve_evm());
+        let token = CancellationToken::new();
+        let mut after = None;
+
+        link_evm_blocks(&fetcher, &evm, &repository, &mut after, &token)
+            .await
+            .unwrap();
+        assert_eq!(
+            linked(&repository, &[0, 1, 2]).await,
+            vec![(0, 1000), (2, 1002)]
+        );
+        assert_eq!(after, Some(PgU64(2).to_i64()));
+
+        // the skipped block is tried again once the linker has caught up
+        fullnode.add_evm_block(1, 1001);
+        link_evm_blocks(&fetcher, &evm, &repository, &mut after, &token)
+            .await
+            .unwrap();
+        assert_eq!(after, None);
+        link_evm_blocks(&fetcher, &evm, &repository, &mut after, &token)
+            .await
+            .unwrap();
+        assert_eq!(linked(&repository, &[0, 1, 2]).await.len(), 3);
+    }
+
+    async fn stops_at_a_failed_lookup<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>(2).await;
+        fullnode.chain().failing.insert(evm_block_hash(1));
+        let (fetcher, evm) = (fullnode.serve(), fullnode.serve_evm());
+        let token = CancellationToken::new();
+        let mut after = None;
+
+        let linking = link_evm_blocks(&fetcher, &evm, &repository, &mut after, &token).await;
+
+        assert!(linking.is_err());
+        // the blocks before the failure are kept, the next pass resumes at the failed one
+        assert_eq!(linked(&repository, &[0, 1, 2]).await, vec![(0, 1000)]);
+        assert_eq!(after, Some(PgU64(0).to_i64()));
+    }
+
+    repository_tests!(
+        links_blocks_to_the_evm_block_of_their_exec_segment_hash,
+        links_at_most_a_batch_per_pass,
+        skips_a_block_the_execution_client_does_not_know,
+        stops_at_a_failed_lookup,
+    );
+}
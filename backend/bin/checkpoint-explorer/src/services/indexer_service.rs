@@ -0,0 +1,423 @@
+use crate::services::{
+    alert_service::start_alert_monitor,
+    audit_service::start_audit_task,
+    block_service::{run_block_fetcher, BlockFetchQueue},
+    bridge_service::start_bridge_indexer,
+    checkpoint_service::{
+        declare_index_range, start_checkpoint_fetcher, start_checkpoint_status_updater_task,
+    },
+    evm_block_service::start_evm_block_linker,
+    integrity_service::start_integrity_checker_task,
+    l1_block_service::start_l1_block_indexer,
+    webhook_service::{start_webhook_dispatcher, WebhookSettings},
+};
+use crate::utils::config::Config;
+use crate::utils::supervisor::Supervisor;
+use database::leader::LeaderElection;
+use database::repository::Repository;
+use database::services::utils::unix_timestamp;
+use fullnode_client::fetcher::StrataFetcher;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderState {
+    /// This process does not run the indexer
+    Disabled,
+    /// Another replica holds the leader lock
+    Standby,
+    /// This replica holds the leader lock and runs the indexer
+    Leader,
+    /// This replica lost the leader lock and stopped its indexer
+    Lost,
+}
+
+/// Where this replica stands in the leader election, and since when
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LeaderSnapshot {
+    pub state: LeaderState,
+    /// UNIX timestamp (seconds) of the last change of state, unset while disabled
+    pub since: Option<i64>,
+}
+
+/// Shared view of the leader state, updated by [`run_indexer`]
+#[derive(Debug, Clone)]
+pub struct LeaderStatus {
+    snapshot: Arc<RwLock<LeaderSnapshot>>,
+}
+
+impl Default for LeaderStatus {
+    fn default() -> Self {
+        Self {
+            snapshot: Arc::new(RwLock::new(LeaderSnapshot {
+                state: LeaderState::Disabled,
+                since: None,
+            })),
+        }
+    }
+}
+
+impl LeaderStatus {
+    pub fn snapshot(&self) -> LeaderSnapshot {
+        *self.snapshot.read().unwrap()
+    }
+
+    fn set(&self, state: LeaderState) {
+        let mut snapshot = self.snapshot.write().unwrap();
+        if snapshot.state != state {
+            *snapshot = LeaderSnapshot {
+                state,
+                since: Some(unix_timestamp()),
+            };
+        }
+    }
+}
+
+/// This function runs the indexer on the replica holding the leader lock.
+///
+/// It stands by until the lock is acquired, then starts the indexer tasks under `supervisor`
+/// and keeps checking that the lock is still held. Losing it (e.g. the database dropped our
+/// session) stops everything and returns an error, so that the replica is restarted and
+/// competes for the lock again instead of writing alongside the new leader.
+pub async fn run_indexer<R: Repository + 'static>(
+    mut supervisor: Supervisor,
+    lock: impl LeaderElection,
+    leader: LeaderStatus,
+    fetcher: Arc<StrataFetcher>,
+    repository: Arc<R>,
+    config: Config,
+    webhooks: Option<WebhookSettings>,
+) -> anyhow::Result<()> {
+    let token = supervisor.token();
+    let check_interval = Duration::from_secs(config.leader_check_interval);
+
+    loop {
+        match lock.try_acquire().await {
+            Ok(true) => break,
+            Ok(false) => {
+                leader.set(LeaderState::Standby);
+                info!("Another replica is running the indexer, standing by...");
+            }
+            Err(e) => error!("Failed to acquire the indexer lock: {:?}", e),
+        }
+        tokio::select! {
+            _ = token.cancelled() => return Ok(()),
+            _ = sleep(check_interval) => {}
+        }
+    }
+    info!("Acquired the indexer lock, starting the indexer");
+    leader.set(LeaderState::Leader);
+    if let Err(e) = declare_index_range(repository.as_ref(), config.index_bounds()).await {
+        token.cancel();
+        return Err(e);
+    }
+    spawn_indexer_tasks(&mut supervisor, fetcher, repository, &config, webhooks);
+
+    let mut interval = tokio::time::interval(check_interval);
+    let outcome = loop {
+        tokio::select! {
+            _ = token.cancelled() => break Ok(()),
+            _ = interval.tick() => {}
+        }
+        match lock.is_held().await {
+            Ok(true) => (),
+            Ok(false) => {
+                leader.set(LeaderState::Lost);
+                token.cancel();
+                break Err(anyhow::anyhow!(
+                    "Lost the indexer lock, stopping the indexer"
+                ));
+            }
+            Err(e) => warn!("Failed to check the indexer lock: {:?}", e),
+        }
+    };
+
+    // Let the background tasks finish their database writes
+    supervisor
+        .shutdown(Duration::from_secs(config.shutdown_timeout))
+        .await;
+    outcome
+}
+
+fn spawn_indexer_tasks<R: Repository + 'static>(
+    supervisor: &mut Supervisor,
+    fetcher: Arc<StrataFetcher>,
+    repository: Arc<R>,
+    config: &Config,
+    webhooks: Option<WebhookSettings>,
+) {
+    // Persisted queue of block fetch jobs fed by the checkpoint fetcher, it outlives
+    // restarts of both tasks
+    let queue = BlockFetchQueue::new(repository.clone());
+
+    // Start block fetcher task
+    let fetcher_clone = fetcher.clone();
+    let queue_clone = queue.clone();
+    let block_fetch_concurrency = config.block_fetch_concurrency;
+    supervisor.spawn("block_fetcher", move |token| {
+        run_block_fetcher(
+            fetcher_clone.clone(),
+            queue_clone.clone(),
+            block_fetch_concurrency,
+            token,
+        )
+    });
+
+    // Start checkpoint fetcher task
+    let fetcher_clone = fetcher.clone();
+    let repository_clone = repository.clone();
+    let fetch_interval = config.fetch_interval;
+    let index_bounds = config.index_bounds();
+    let notifier = config.webhook_notifier();
+    let notifier_clone = notifier.clone();
+    let l1_times = config.l1_block_times();
+    let l1_times_clone = l1_times.clone();
+    supervisor.spawn("checkpoint_fetcher", move |token| {
+        start_checkpoint_fetcher(
+            fetcher_clone.clone(),
+            repository_clone.clone(),
+            queue.clone(),
+            notifier_clone.clone(),
+            l1_times_clone.clone(),
+            fetch_interval,
+            index_bounds,
+            token,
+        )
+    });
+
+    // Start integrity checker task
+    let fetcher_clone = fetcher.clone();
+    let repository_clone = repository.clone();
+    let notifier_clone = notifier.clone();
+    let integrity_check_interval = config.integrity_check_interval;
+    supervisor.spawn("integrity_checker", move |token| {
+        start_integrity_checker_task(
+            fetcher_clone.clone(),
+            repository_clone.clone(),
+            notifier_clone.clone(),
+            integrity_check_interval,
+            token,
+        )
+    });
+
+    // Start L1 block indexer task
+    let fetcher_clone = fetcher.clone();
+    let repository_clone = repository.clone();
+    let l1_times_clone = l1_times.clone();
+    let l1_block_interval = config.l1_block_interval;
+    supervisor.spawn("l1_block_indexer", move |token| {
+        start_l1_block_indexer(
+            fetcher_clone.clone(),
+            repository_clone.clone(),
+            l1_times_clone.clone(),
+            l1_block_interval,
+            token,
+        )
+    });
+
+    // Start EVM block linker task
+    if let Some(evm) = config.evm_client() {
+        let fetcher_clone = fetcher.clone();
+        let repository_clone = repository.clone();
+        let evm_link_interval = config.evm_link_interval;
+        supervisor.spawn("evm_block_linker", move |token| {
+            start_evm_block_linker(
+                fetcher_clone.clone(),
+                evm.clone(),
+                repository_clone.clone(),
+                evm_link_interval,
+                token,
+            )
+        });
+    }
+
+    // Start bridge indexer task
+    if config.bridge_interval > 0 {
+        let fetcher_clone = fetcher.clone();
+        let repository_clone = repository.clone();
+        let bridge_interval = config.bridge_interval;
+        let bridge_operators = config.bridge_operators.clone();
+        supervisor.spawn("bridge_indexer", move |token| {
+            start_bridge_indexer(
+                fetcher_clone.clone(),
+                repository_clone.clone(),
+                bridge_interval,
+                bridge_operators.clone(),
+                token,
+            )
+        });
+    }
+
+    // Start consistency audit task
+    if config.audit_interval > 0 {
+        let fetcher_clone = fetcher.clone();
+        let repository_clone = repository.clone();
+        let audit_interval = config.audit_interval;
+        let audit_batch_size = config.audit_batch_size;
+        supervisor.spawn("consistency_audit", move |token| {
+            start_audit_task(
+                fetcher_clone.clone(),
+                repository_clone.clone(),
+                audit_interval,
+                audit_batch_size,
+                block_fetch_concurrency,
+                token,
+            )
+        });
+    }
+
+    // Start alert monitor task
+    let fetcher_clone = fetcher.clone();
+    let repository_clone = repository.clone();
+    let notifier_clone = notifier.clone();
+    let alert_thresholds = config.alert_thresholds();
+    let alert_check_interval = config.alert_check_interval;
+    supervisor.spawn("alert_monitor", move |token| {
+        start_alert_monitor(
+            fetcher_clone.clone(),
+            repository_clone.clone(),
+            notifier_clone.clone(),
+            alert_thresholds,
+            alert_check_interval,
+            token,
+        )
+    });
+
+    // Start webhook dispatcher task
+    if let Some(webhooks) = webhooks {
+        let repository_clone = repository.clone();
+        supervisor.spawn("webhook_dispatcher", move |token| {
+            start_webhook_dispatcher(repository_clone.clone(), webhooks.clone(), token)
+        });
+    }
+
+    // Start checkpoint status updater task
+    let status_revisit_intervals = config.status_revisit_intervals();
+    supervisor.spawn("checkpoint_status_updater", move |token| {
+        start_checkpoint_status_updater_task(
+            fetcher.clone(),
+            repository.clone(),
+            notifier.clone(),
+            l1_times.clone(),
+            status_revisit_intervals,
+            token,
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fake_fullnode::FakeFullnode;
+    use crate::utils::supervisor::TaskState;
+    use async_trait::async_trait;
+    use clap::Parser;
+    use database::repository::memory::InMemoryRepository;
+    use sea_orm::DbErr;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Lock granted or not from the start, and held for `checks` checks once granted
+    struct FakeLock {
+        granted: bool,
+        checks: AtomicU32,
+    }
+
+    #[async_trait]
+    impl LeaderElection for FakeLock {
+        async fn try_acquire(&self) -> Result<bool, DbErr> {
+            Ok(self.granted)
+        }
+
+        async fn is_held(&self) -> Result<bool, DbErr> {
+            let checks = self.checks.load(Ordering::SeqCst);
+            self.checks
+                .store(checks.saturating_sub(1), Ordering::SeqCst);
+            Ok(checks > 0)
+        }
+    }
+
+    fn config() -> Config {
+        Config::parse_from([
+            "checkpoint-explorer",
+            "--leader-check-interval",
+            "1",
+            "--shutdown-timeout",
+            "5",
+            "--bridge-interval",
+            "0",
+        ])
+    }
+
+    #[tokio::test]
+    async fn stops_the_indexer_when_the_lock_is_lost() {
+        let supervisor = Supervisor::new();
+        let (token, tasks) = (supervisor.token(), supervisor.health());
+        let leader = LeaderStatus::default();
+        let lock = FakeLock {
+            granted: true,
+            checks: AtomicU32::new(1),
+        };
+
+        let outcome = tokio::time::timeout(
+            Duration::from_secs(10),
+            run_indexer(
+                supervisor,
+                lock,
+                leader.clone(),
+                Arc::new(FakeFullnode::default().serve()),
+                Arc::new(InMemoryRepository::new()),
+                config(),
+                None,
+            ),
+        )
+        .await
+        .expect("the indexer did not stop");
+
+        let error = outcome.unwrap_err();
+        assert!(
+            error.to_string().contains("Lost the indexer lock"),
+            "{}",
+            error
+        );
+        assert!(token.is_cancelled());
+        assert_eq!(leader.snapshot().state, LeaderState::Lost);
+        let tasks = tasks.snapshot();
+        assert!(!tasks.is_empty());
+        for (name, health) in tasks {
+            assert_eq!(health.state, TaskState::Stopped, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn stands_by_while_another_replica_holds_the_lock() {
+        let supervisor = Supervisor::new();
+        let (token, tasks) = (supervisor.token(), supervisor.health());
+        let leader = LeaderStatus::default();
+        let lock = FakeLock {
+            granted: false,
+            checks: AtomicU32::new(0),
+        };
+
+        let indexer = tokio::spawn(run_indexer(
+            supervisor,
+            lock,
+            leader.clone(),
+            Arc::new(FakeFullnode::default().serve()),
+            Arc::new(InMemoryRepository::new()),
+            config(),
+            None,
+        ));
+        while leader.snapshot().state != LeaderState::Standby {
+            tokio::task::yield_now().await;
+        }
+        token.cancel();
+
+        indexer.await.unwrap().unwrap();
+        assert!(leader.snapshot().since.is_some());
+        assert!(tasks.snapshot().is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::services::webhook_service::WebhookNotifier;
+use crate::utils::supervisor::report_success;
+use database::repository::{BlockRepository, CheckpointRepository, IntegrityRepository};
+use fullnode_client::fetcher::StrataFetcher;
+use model::block::RpcBlockHeader;
+use model::checkpoint::RpcCheckpointInfo;
+use model::integrity::{IntegrityReport, MissingRange};
+use model::pgu64::PgU64;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// This function periodically looks for holes in the indexed data and re-fetches them
+/// It will run in a loop with a delay of `check_interval` seconds until `token` is cancelled
+pub async fn start_integrity_checker_task<R>(
+    fetcher: Arc<StrataFetcher>,
+    repository: Arc<R>,
+    notifier: WebhookNotifier,
+    check_interval: u64,
+    token: CancellationToken,
+) where
+    R: CheckpointRepository + BlockRepository + IntegrityRepository + 'static,
+{
+    info!("Starting integrity checker...");
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(check_interval));
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        match check_and_repair(fetcher.as_ref(), repository.as_ref(), &notifier, &token).await {
+            Ok(()) => report_success(),
+            Err(e) => error!("Error checking data integrity: {}", e),
+        }
+    }
+}
+
+/// Detects missing checkpoints, missing blocks and checkpoints whose blocks don't match
+/// their L2 range, then re-fetches exactly the missing ranges.
+///
+/// Ranges are repaired in ascending order and a range is abandoned at its first failure, so
+/// every insert lands right after data that is already stored and passes the continuity
+/// checks. Whatever is left is picked up on the next run.
+async fn check_and_repair<R: CheckpointRepository + BlockRepository + IntegrityRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    notifier: &WebhookNotifier,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let report = repository.get_integrity_report().await?;
+    if report.is_clean() {
+        info!("Integrity check passed, no missing checkpoints or blocks");
+        return Ok(());
+    }
+    log_report("Integrity check found", &report);
+
+    // blocks can only be attached to checkpoints that are stored
+    for range in &report.missing_checkpoints {
+        repair_checkpoints(fetcher, repository, notifier, range, token).await;
+    }
+    for range in &report.missing_blocks {
+        repair_blocks(fetcher, repository, range, token).await;
+    }
+
+    let report = repository.get_integrity_report().await?;
+    if report.is_clean() {
+        info!("Integrity repair completed, no missing checkpoints or blocks left");
+    } else {
+        log_report("Integrity repair left", &report);
+    }
+    Ok(())
+}
+
+fn log_report(prefix: &str, report: &IntegrityReport) {
+    for range in &report.missing_checkpoints {
+        warn!(
+            "{} missing checkpoints {} to {}",
+            prefix, range.start, range.end
+        );
+    }
+    for range in &report.missing_blocks {
+        warn!("{} missing blocks {} to {}", prefix, range.start, range.end);
+    }
+    // missing blocks show up here as well, a checkpoint with all of its blocks stored can
+    // only mismatch if blocks were attributed to the wrong checkpoint
+    for mismatch in &report.block_count_mismatches {
+        warn!(
+            "{} checkpoint {} with {} blocks stored, expected {} for L2 range {:?}",
+            prefix, mismatch.checkpoint_idx, mismatch.actual, mismatch.expected, mismatch.l2_range
+        );
+    }
+}
+
+/// Fetch the checkpoints of `range` in ascending order, returns whether all of them are
+/// stored afterwards
+pub(crate) async fn repair_checkpoints<R: CheckpointRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    notifier: &WebhookNotifier,
+    range: &MissingRange,
+    token: &CancellationToken,
+) -> bool {
+    for idx in range.start..=range.end {
+        if token.is_cancelled() {
+            return false;
+        }
+        let checkpoint = match fetcher
+            .fetch_data::<RpcCheckpointInfo>("strata_getCheckpointInfo", idx)
+            .await
+        {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                warn!("Failed to re-fetch checkpoint {}: {}", idx, e);
+                return false;
+            }
+        };
+        repository.insert_checkpoint(checkpoint, notifier).await;
+        if !repository.checkpoint_exists(PgU64(idx).to_i64()).await {
+            return false;
+        }
+        info!("Repaired missing checkpoint {}", idx);
+    }
+    true
+}
+
+/// Fetch the blocks of `range` in ascending order, returns whether all of them are stored
+/// afterwards
+pub(crate) async fn repair_blocks<R: CheckpointRepository + BlockRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    range: &MissingRange,
+    token: &CancellationToken,
+) -> bool {
+    for height in range.start..=range.end {
+        if token.is_cancelled() {
+            return false;
+        }
+        let checkpoint_idx = match repository
+            .get_checkpoint_idx_by_l2_height(PgU64(height).to_i64())
+            .await
+        {
+            Ok(Some(checkpoint_idx)) => checkpoint_idx,
+            Ok(None) => {
+                warn!(
+                    "No stored checkpoint covers block {}, repairing it later",
+                    height
+                );
+                return false;
+            }
+            Err(e) => {
+                error!(
+                    "Failed to look up the checkpoint of block {}: {:?}",
+                    height, e
+                );
+                return false;
+            }
+        };
+        let block_headers = match fetcher
+            .fetch_data::<Vec<RpcBlockHeader>>("strata_getHeadersAtIdx", height)
+            .await
+        {
+            Ok(block_headers) => block_headers,
+            Err(e) => {
+                warn!("Failed to re-fetch block {}: {}", height, e);
+                return false;
+            }
+        };
+        for block_header in block_headers {
+            if let Err(e) = repository.insert_block(block_header, checkpoint_idx).await {
+                warn!("Failed to store block {}: {:?}", height, e);
+                return false;
+            }
+        }
+        // the next height may only be inserted on top of this one
+        if !repository
+            .can_insert_block(PgU64(height).to_i64().saturating_add(1))
+            .await
+        {
+            warn!("Block {} was not stored, repairing it later", height);
+            return false;
+        }
+        info!(
+            "Repaired missing block {} of checkpoint {}",
+            height,
+            PgU64::i64_to_u64(checkpoint_idx)
+        );
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fake_fullnode::{block_header, checkpoint_info, FakeFullnode};
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+
+    /// Checkpoints 0 to 2 over five L2 blocks each, served by the fullnode and stored with
+    /// their blocks except checkpoint 1, which leaves a hole in the checkpoints and blocks
+    async fn with_a_hole<R: TestRepository>() -> (FakeFullnode, R) {
+        let fullnode = FakeFullnode::default();
+        let repository = R::open().await;
+        for idx in 0..=2 {
+            let l2_range = (5 * idx, 5 * idx + 4);
+            fullnode.add_checkpoint(idx, l2_range, "finalized", "aa");
+            let checkpoint = checkpoint_info(idx, l2_range, "finalized", "aa");
+            repository
+                .insert_checkpoint(checkpoint, &WebhookNotifier::default())
+                .await;
+            for height in l2_range.0..=l2_range.1 {
+                repository
+                    .insert_block(block_header(height), PgU64(idx).to_i64())
+                    .await
+                    .unwrap();
+            }
+        }
+        repository
+            .delete_checkpoints(PgU64(1).to_i64(), Some(PgU64(1).to_i64()), false)
+            .await
+            .unwrap();
+        (fullnode, repository)
+    }
+
+    async fn repairs_missing_checkpoints_and_blocks<R: TestRepository>() {
+        let (fullnode, repository) = with_a_hole::<R>().await;
+        let report = repository.get_integrity_report().await.unwrap();
+        assert_eq!(
+            report.missing_checkpoints,
+            [MissingRange { start: 1, end: 1 }]
+        );
+        assert_eq!(report.missing_blocks, [MissingRange { start: 5, end: 9 }]);
+
+        check_and_repair(
+            &fullnode.serve(),
+            &repository,
+            &WebhookNotifier::default(),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(repository.get_integrity_report().await.unwrap().is_clean());
+        let blocks = repository
+            .get_blocks_in_range(PgU64(5).to_i64(), PgU64(9).to_i64())
+            .await
+            .unwrap();
+        assert_eq!(blocks.len(), 5);
+    }
+
+    async fn stops_a_range_at_its_first_failure_and_resumes_on_the_next_run<R: TestRepository>() {
+        let (fullnode, repository) = with_a_hole::<R>().await;
+        let fetcher = fullnode.serve();
+        let token = CancellationToken::new();
+        fullnode.chain().failing.insert("7".to_string());
+
+        check_and_repair(&fetcher, &repository, &WebhookNotifier::default(), &token)
+            .await
+            .unwrap();
+        let report = repository.get_integrity_report().await.unwrap();
+        assert!(report.missing_checkpoints.is_empty());
+        assert_eq!(report.missing_blocks, [MissingRange { start: 7, end: 9 }]);
+
+        fullnode.chain().failing.clear();
+        check_and_repair(&fetcher, &repository, &WebhookNotifier::default(), &token)
+            .await
+            .unwrap();
+        assert!(repository.get_integrity_report().await.unwrap().is_clean());
+    }
+
+    repository_tests!(
+        repairs_missing_checkpoints_and_blocks,
+        stops_a_range_at_its_first_failure_and_resumes_on_the_next_run,
+    );
+}
@@ -0,0 +1,247 @@
+use crate::services::lifecycle_service::L1BlockTimes;
+use crate::utils::supervisor::report_success;
+use database::repository::L1BlockRepository;
+use fullnode_client::fetcher::StrataFetcher;
+use model::l1_block;
+use model::pgu64::PgU64;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Most checkpoints whose L1 blocks are completed on every pass
+const CHECKPOINT_BATCH_SIZE: u64 = 10;
+
+/// Most L1 block hashes and times looked up on every pass
+const L1_BLOCK_BATCH_SIZE: usize = 200;
+
+/// This function indexes the L1 blocks covered by the stored checkpoints
+/// It will run in a loop with a delay of `interval` seconds until `token` is cancelled
+pub async fn start_l1_block_indexer<R: L1BlockRepository + 'static>(
+    fetcher: Arc<StrataFetcher>,
+    repository: Arc<R>,
+    l1_times: L1BlockTimes,
+    interval: u64,
+    token: CancellationToken,
+) {
+    info!("Starting L1 block indexer...");
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval));
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let indexed = index_l1_blocks(fetcher.as_ref(), repository.as_ref(), &l1_times, &token);
+        match indexed.await {
+            Ok(()) => report_success(),
+            Err(e) => warn!("Error indexing L1 blocks: {}", e),
+        }
+    }
+}
+
+/// Fill the L1 ranges of the checkpoints, then the times of the blocks stored without one.
+///
+/// The first and last blocks of a range are stored along with the checkpoint, the heights in
+/// between are resolved with `strata_getL1blockHash`. Times come from the L1 explorer, if
+/// configured. A height the fullnode cannot resolve is skipped and retried on the next pass.
+async fn index_l1_blocks<R: L1BlockRepository>(
+    fetcher: &StrataFetcher,
+    repository: &R,
+    l1_times: &L1BlockTimes,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let mut budget = L1_BLOCK_BATCH_SIZE;
+
+    for range in repository
+        .get_incomplete_l1_ranges(CHECKPOINT_BATCH_SIZE)
+        .await?
+    {
+        let stored: HashSet<i64> = repository
+            .get_l1_block_heights_in_range(range.start, range.end)
+            .await?
+            .into_iter()
+            .collect();
+        let mut blocks = Vec::new();
+        for height in (range.start..=range.end).filter(|height| !stored.contains(height)) {
+            if budget == 0 || token.is_cancelled() {
+                break;
+            }
+            budget -= 1;
+            let l1_height = PgU64::i64_to_u64(height);
+            let block_hash = match fetcher
+                .fetch_data::<String>("strata_getL1blockHash", l1_height)
+                .await
+            {
+                Ok(block_hash) => block_hash,
+                Err(e) => {
+                    warn!("Failed to fetch the hash of L1 block {}: {}", l1_height, e);
+                    continue;
+                }
+            };
+            blocks.push(l1_block::Model {
+                height,
+                timestamp: l1_times.block_time(&block_hash).await,
+                block_hash,
+                checkpoint_idx: range.checkpoint_idx,
+            });
+        }
+        let inserted = repository.insert_l1_blocks(blocks).await?;
+        debug!(
+            "Inserted {} L1 blocks of checkpoint {}",
+            inserted,
+            PgU64::i64_to_u64(range.checkpoint_idx)
+        );
+        if budget == 0 || token.is_cancelled() {
+            return Ok(());
+        }
+    }
+
+    if !l1_times.is_enabled() {
+        return Ok(());
+    }
+    for block in repository
+        .get_l1_blocks_without_timestamp(budget as u64)
+        .await?
+    {
+        if token.is_cancelled() {
+            return Ok(());
+        }
+        if let Some(timestamp) = l1_times.block_time(&block.block_hash).await {
+            repository
+                .set_l1_block_timestamp(block.height, timestamp)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webhook_service::WebhookNotifier;
+    use crate::utils::fake_fullnode::{checkpoint_info, l1_block_hash, FakeFullnode};
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+
+    /// Header time the L1 explorer reports for the block at `height`
+    fn block_time(height: u64) -> i64 {
+        1_600_000_000 + height as i64
+    }
+
+    /// Checkpoint 0 over the L1 blocks 100 to 104 with its first and last block stored, and
+    /// a fullnode and L1 explorer that know every block of the range
+    async fn setup<R: TestRepository>() -> (FakeFullnode, R) {
+        let repository = R::open().await;
+        let mut checkpoint = checkpoint_info(0, (0, 4), "finalized", "aa");
+        checkpoint.l1_range.1.height = 104;
+        checkpoint.l1_range.1.blkid = l1_block_hash(104);
+        repository
+            .insert_checkpoint(checkpoint.clone(), &WebhookNotifier::default())
+            .await;
+        repository
+            .insert_l1_blocks(l1_block::Model::from_checkpoint_range(&checkpoint))
+            .await
+            .unwrap();
+        let fullnode = FakeFullnode::default();
+        for height in 100..=104 {
+            fullnode.add_l1_block(height, block_time(height));
+        }
+        (fullnode, repository)
+    }
+
+    /// Hashes and times of the L1 blocks stored from height 100 to 104
+    async fn stored<R: TestRepository>(repository: &R) -> Vec<(u64, String, Option<i64>)> {
+        let mut stored = Vec::new();
+        for height in 100..=104 {
+            if let Some(block) = repository
+                .get_l1_block_by_height(PgU64(height).to_i64())
+                .await
+                .unwrap()
+            {
+                assert_eq!(block.checkpoint_idx, PgU64(0).to_i64());
+                stored.push((height, block.block_hash, block.timestamp));
+            }
+        }
+        stored
+    }
+
+    async fn fills_the_l1_range_of_a_checkpoint_with_the_block_times<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>().await;
+        let (fetcher, l1_times) = (fullnode.serve(), fullnode.serve_esplora());
+
+        index_l1_blocks(&fetcher, &repository, &l1_times, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        let expected: Vec<_> = (100..=104)
+            .map(|height| (height, l1_block_hash(height), Some(block_time(height))))
+            .collect();
+        assert_eq!(stored(&repository).await, expected);
+        assert!(repository
+            .get_incomplete_l1_ranges(CHECKPOINT_BATCH_SIZE)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    async fn retries_the_blocks_the_fullnode_or_the_explorer_cannot_resolve<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>().await;
+        let (fetcher, l1_times) = (fullnode.serve(), fullnode.serve_esplora());
+        fullnode.chain().failing.insert("102".to_string());
+        fullnode.chain().l1_block_times.remove(&l1_block_hash(103));
+        let token = CancellationToken::new();
+
+        index_l1_blocks(&fetcher, &repository, &l1_times, &token)
+            .await
+            .unwrap();
+        let heights: Vec<_> = stored(&repository)
+            .await
+            .into_iter()
+            .map(|(height, _, timestamp)| (height, timestamp))
+            .collect();
+        assert_eq!(
+            heights,
+            [
+                (100, Some(block_time(100))),
+                (101, Some(block_time(101))),
+                (103, None),
+                (104, Some(block_time(104))),
+            ]
+        );
+
+        // both come back on the next pass
+        fullnode.chain().failing.clear();
+        fullnode.add_l1_block(103, block_time(103));
+        index_l1_blocks(&fetcher, &repository, &l1_times, &token)
+            .await
+            .unwrap();
+        let expected: Vec<_> = (100..=104)
+            .map(|height| (height, l1_block_hash(height), Some(block_time(height))))
+            .collect();
+        assert_eq!(stored(&repository).await, expected);
+    }
+
+    async fn stores_the_blocks_without_times_when_no_explorer_is_configured<R: TestRepository>() {
+        let (fullnode, repository) = setup::<R>().await;
+
+        index_l1_blocks(
+            &fullnode.serve(),
+            &repository,
+            &L1BlockTimes::default(),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        let expected: Vec<_> = (100..=104)
+            .map(|height| (height, l1_block_hash(height), None))
+            .collect();
+        assert_eq!(stored(&repository).await, expected);
+    }
+
+    repository_tests!(
+        fills_the_l1_range_of_a_checkpoint_with_the_block_times,
+        retries_the_blocks_the_fullnode_or_the_explorer_cannot_resolve,
+        stores_the_blocks_without_times_when_no_explorer_is_configured,
+    );
+}
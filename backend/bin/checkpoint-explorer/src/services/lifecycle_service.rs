@@ -0,0 +1,286 @@
+use database::repository::CheckpointRepository;
+use model::checkpoint::{CheckpointLifecycle, CheckpointState, RpcCheckpointInfo};
+use model::pgu64::PgU64;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// How long to wait for the L1 block explorer to answer
+const L1_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Looks up the time of L1 blocks, e.g. the one confirming a checkpoint, from an Esplora
+/// compatible block explorer. The Strata fullnode does not expose L1 block times.
+///
+/// Without a URL, or when the explorer fails, times stay unknown and confirmation times stay
+/// the ones the indexer observed.
+#[derive(Debug, Clone, Default)]
+pub struct L1BlockTimes {
+    esplora: Option<(reqwest::Client, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraBlock {
+    timestamp: i64,
+}
+
+impl L1BlockTimes {
+    pub fn new(esplora_url: Option<String>) -> Self {
+        let Some(url) = esplora_url.filter(|url| !url.is_empty()) else {
+            return Self::default();
+        };
+        match reqwest::Client::builder().timeout(L1_TIMEOUT).build() {
+            Ok(client) => Self {
+                esplora: Some((client, url.trim_end_matches('/').to_string())),
+            },
+            Err(e) => {
+                warn!("Failed to build the L1 explorer HTTP client: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether an L1 explorer is configured
+    pub fn is_enabled(&self) -> bool {
+        self.esplora.is_some()
+    }
+
+    /// UNIX timestamp (seconds) of the header of L1 block `block_id`, `None` when disabled or
+    /// when the lookup failed
+    pub async fn block_time(&self, block_id: &str) -> Option<i64> {
+        let (client, url) = self.esplora.as_ref()?;
+        match fetch_block(client, url, block_id).await {
+            Ok(block) => Some(block.timestamp),
+            Err(e) => {
+                warn!("Failed to fetch L1 block {}: {}", block_id, e);
+                None
+            }
+        }
+    }
+
+    /// Replace the confirmation time of `checkpoint` with the time of the L1 block including
+    /// its transaction, if it is confirmed and the block can be looked up
+    pub async fn record_confirmation<R: CheckpointRepository>(
+        &self,
+        repository: &R,
+        checkpoint: &RpcCheckpointInfo,
+    ) {
+        let Some(l1_reference) = &checkpoint.l1_reference else {
+            return;
+        };
+        let state = checkpoint
+            .confirmation_status
+            .map_or(CheckpointState::Unknown, Into::into);
+        if state < CheckpointState::Confirmed {
+            return;
+        }
+
+        let Some(timestamp) = self.block_time(&l1_reference.block_id).await else {
+            return;
+        };
+        match repository
+            .set_checkpoint_confirmed_at(
+                PgU64(checkpoint.idx).to_i64(),
+                &l1_reference.txid,
+                timestamp,
+            )
+            .await
+        {
+            Ok(true) => debug!(
+                "Checkpoint {} confirmed at L1 block time {}",
+                checkpoint.idx, timestamp
+            ),
+            Ok(false) => (),
+            Err(e) => warn!(
+                "Failed to record the L1 confirmation time of checkpoint {}: {:?}",
+                checkpoint.idx, e
+            ),
+        }
+    }
+}
+
+async fn fetch_block(
+    client: &reqwest::Client,
+    url: &str,
+    block_id: &str,
+) -> Result<EsploraBlock, reqwest::Error> {
+    client
+        .get(format!("{}/block/{}", url, block_id))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+/// Distribution of one lifecycle duration, in seconds. Percentiles are nearest-rank and
+/// unknown without samples.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DurationStats {
+    pub count: u64,
+    pub p50: Option<u64>,
+    pub p95: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl DurationStats {
+    fn from_samples(mut samples: Vec<u64>) -> Self {
+        samples.sort_unstable();
+        Self {
+            count: samples.len() as u64,
+            p50: percentile(&samples, 50),
+            p95: percentile(&samples, 95),
+            max: samples.last().copied(),
+        }
+    }
+}
+
+fn percentile(sorted: &[u64], percent: usize) -> Option<u64> {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+/// Lifecycle durations of the checkpoints that reached a step within a window ending now.
+///
+/// Time to confirmation counts the checkpoints confirmed in the window, the other two the
+/// checkpoints finalized in it.
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleStats {
+    /// Length of the window in seconds
+    pub window: u64,
+    /// UNIX timestamps (seconds) bounding the window
+    pub from: i64,
+    pub to: i64,
+    pub time_to_confirmation: DurationStats,
+    pub confirmation_to_finality: DurationStats,
+    pub time_to_finality: DurationStats,
+}
+
+pub async fn lifecycle_stats<R: CheckpointRepository>(
+    repository: &R,
+    window: u64,
+    to: i64,
+) -> anyhow::Result<LifecycleStats> {
+    let from = to.saturating_sub(window.min(i64::MAX as u64) as i64);
+    let checkpoints = repository.get_checkpoint_lifecycles_since(from).await?;
+    let in_window = |at: Option<i64>| at.is_some_and(|at| from <= at && at <= to);
+
+    let mut time_to_confirmation = Vec::new();
+    let mut confirmation_to_finality = Vec::new();
+    let mut time_to_finality = Vec::new();
+    for checkpoint in &checkpoints {
+        let lifecycle = CheckpointLifecycle::from(checkpoint);
+        if in_window(checkpoint.confirmed_at) {
+            time_to_confirmation.extend(lifecycle.time_to_confirmation);
+        }
+        if in_window(checkpoint.finalized_at) {
+            confirmation_to_finality.extend(lifecycle.confirmation_to_finality);
+            time_to_finality.extend(lifecycle.time_to_finality);
+        }
+    }
+
+    Ok(LifecycleStats {
+        window,
+        from,
+        to,
+        time_to_confirmation: DurationStats::from_samples(time_to_confirmation),
+        confirmation_to_finality: DurationStats::from_samples(confirmation_to_finality),
+        time_to_finality: DurationStats::from_samples(time_to_finality),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::webhook_service::WebhookNotifier;
+    use crate::utils::fake_fullnode::checkpoint_info;
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+    use model::pgu64::PgU64;
+
+    #[test]
+    fn percentiles_are_nearest_rank() {
+        let ten: Vec<u64> = (1..=10).collect();
+        let hundred: Vec<u64> = (1..=100).collect();
+        let cases: [(&[u64], usize, Option<u64>); 10] = [
+            (&[], 50, None),
+            (&[], 100, None),
+            (&[7], 0, Some(7)),
+            (&[7], 50, Some(7)),
+            (&[7], 100, Some(7)),
+            (&ten, 0, Some(1)),
+            (&ten, 50, Some(5)),
+            (&ten, 95, Some(10)),
+            (&ten, 100, Some(10)),
+            (&hundred, 95, Some(95)),
+        ];
+        for (sorted, percent, expected) in cases {
+            assert_eq!(
+                percentile(sorted, percent),
+                expected,
+                "p{} of {:?}",
+                percent,
+                sorted
+            );
+        }
+    }
+
+    #[test]
+    fn summarizes_the_samples() {
+        assert_eq!(
+            DurationStats::from_samples(vec![]),
+            DurationStats::default()
+        );
+        assert_eq!(
+            DurationStats::from_samples(vec![42]),
+            DurationStats {
+                count: 1,
+                p50: Some(42),
+                p95: Some(42),
+                max: Some(42),
+            }
+        );
+        assert_eq!(
+            DurationStats::from_samples(vec![30, 10, 20]),
+            DurationStats {
+                count: 3,
+                p50: Some(20),
+                p95: Some(30),
+                max: Some(30),
+            }
+        );
+    }
+
+    async fn counts_the_checkpoints_confirmed_within_the_window_bounds<R: TestRepository>() {
+        let repository = R::open().await;
+        let notifier = WebhookNotifier::default();
+        let idx = PgU64(0).to_i64();
+        let pending = checkpoint_info(0, (0, 4), "pending", "-");
+        repository.insert_checkpoint(pending, &notifier).await;
+        let confirmed = checkpoint_info(0, (0, 4), "confirmed", "aa");
+        repository
+            .reconcile_checkpoint(idx, confirmed, &notifier)
+            .await
+            .unwrap();
+        let confirmed_at = repository.get_checkpoints_in_range(idx, idx).await.unwrap()[0]
+            .confirmed_at
+            .unwrap();
+
+        let cases = [
+            // both bounds are part of the window
+            (0, confirmed_at, 1),
+            (10, confirmed_at + 10, 1),
+            (10, confirmed_at - 1, 0),
+            (10, confirmed_at + 11, 0),
+        ];
+        for (window, to, count) in cases {
+            let stats = lifecycle_stats(&repository, window, to).await.unwrap();
+            assert_eq!(
+                stats.time_to_confirmation.count, count,
+                "window of {}s up to {}, confirmed at {}",
+                window, to, confirmed_at
+            );
+            assert_eq!(stats.time_to_finality.count, 0);
+        }
+    }
+
+    repository_tests!(counts_the_checkpoints_confirmed_within_the_window_bounds,);
+}
@@ -1,6 +1,18 @@
+pub mod admin_service;
+pub mod alert_service;
+pub mod api_error;
 pub mod api_service;
+pub mod audit_service;
 pub mod block_service;
+pub mod bridge_service;
 pub mod checkpoint_service;
+pub mod evm_block_service;
+pub mod indexer_service;
+pub mod integrity_service;
+pub mod l1_block_service;
+pub mod lifecycle_service;
+pub mod resource_service;
+pub mod webhook_service;
 
 use serde::{Deserialize, Serialize};
 // Struct for pagination parameters
@@ -15,3 +27,21 @@ pub struct QueryParams {
 pub struct SearchQuery {
     pub query: String,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct CheckpointQuery {
+    pub idx: u64,
+}
+
+/// EL transaction requesting a withdrawal
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalQuery {
+    pub txid: String,
+}
+
+/// Window of the lifecycle statistics, ending now
+#[derive(Debug, Deserialize)]
+pub struct WindowQuery {
+    /// Length of the window in seconds
+    pub window: Option<u64>,
+}
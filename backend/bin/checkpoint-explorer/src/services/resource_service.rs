@@ -0,0 +1,208 @@
+use crate::services::api_service::API_PREFIX;
+use database::repository::{BlockRepository, CheckpointRepository, SyncStateRepository};
+use database::services::pagination::PaginatedData;
+use model::block::RpcBlock;
+use model::checkpoint::RpcCheckpointInfoCheckpointExp;
+use model::pgu64::PgU64;
+use sea_orm::DbErr;
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// Links from a resource to itself, its neighbours and the resources it belongs to. `prev`
+/// and `next` are `null` when there is no such resource stored.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Links {
+    #[serde(rename = "self")]
+    pub this: String,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<String>,
+}
+
+/// A checkpoint, linked to the previous and next checkpoints and to its blocks
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckpointResource {
+    #[serde(flatten)]
+    pub checkpoint: RpcCheckpointInfoCheckpointExp,
+    pub links: Links,
+}
+
+/// One page of the blocks of a checkpoint, linked to the previous and next pages
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckpointBlocks {
+    pub checkpoint_idx: u64,
+    pub l2_range: (u64, u64),
+    #[serde(flatten)]
+    pub blocks: PaginatedData<RpcBlock>,
+    pub links: Links,
+}
+
+/// A block, linked to the previous and next blocks and to its checkpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockResource {
+    #[serde(flatten)]
+    pub block: RpcBlock,
+    pub links: Links,
+}
+
+pub fn checkpoint_path(idx: u64) -> String {
+    format!("{}/checkpoints/{}", API_PREFIX, idx)
+}
+
+pub fn block_path(height: u64) -> String {
+    format!("{}/blocks/{}", API_PREFIX, height)
+}
+
+fn checkpoint_blocks_path(idx: u64, page: u64, page_size: u64) -> String {
+    format!(
+        "{}/checkpoints/{}/blocks?p={}&ps={}",
+        API_PREFIX, idx, page, page_size
+    )
+}
+
+/// Checkpoint `idx`, `None` if it is not stored
+pub async fn checkpoint_resource<R: CheckpointRepository>(
+    repository: &R,
+    idx: u64,
+) -> Result<Option<CheckpointResource>, DbErr> {
+    // the neighbours are fetched along with the checkpoint to link only to stored ones
+    let checkpoints = repository
+        .get_checkpoints_in_range(
+            PgU64(idx.saturating_sub(1)).to_i64(),
+            PgU64(idx.saturating_add(1)).to_i64(),
+        )
+        .await?;
+
+    let mut links = Links {
+        this: checkpoint_path(idx),
+        blocks: Some(format!("{}/blocks", checkpoint_path(idx))),
+        ..Links::default()
+    };
+    let mut found = None;
+    for checkpoint in checkpoints {
+        let checkpoint_idx = PgU64::i64_to_u64(checkpoint.idx);
+        match checkpoint_idx.cmp(&idx) {
+            Ordering::Less => links.prev = Some(checkpoint_path(checkpoint_idx)),
+            Ordering::Equal => found = Some(checkpoint),
+            Ordering::Greater => links.next = Some(checkpoint_path(checkpoint_idx)),
+        }
+    }
+    Ok(found.map(|checkpoint| CheckpointResource {
+        checkpoint: checkpoint.into(),
+        links,
+    }))
+}
+
+/// The highest stored checkpoint, `None` before the first one is stored
+pub async fn latest_checkpoint_resource<R: CheckpointRepository + SyncStateRepository>(
+    repository: &R,
+) -> Result<Option<CheckpointResource>, DbErr> {
+    match repository.get_index_range().await?.last_checkpoint {
+        Some(idx) => checkpoint_resource(repository, idx).await,
+        None => Ok(None),
+    }
+}
+
+/// Page `page` of the blocks of checkpoint `idx`, both starting at 1, `None` if the
+/// checkpoint is not stored. Blocks not fetched yet are missing from their page.
+pub async fn checkpoint_blocks<R: CheckpointRepository + BlockRepository>(
+    repository: &R,
+    idx: u64,
+    page: u64,
+    page_size: u64,
+) -> Result<Option<CheckpointBlocks>, DbErr> {
+    let stored_idx = PgU64(idx).to_i64();
+    let Some(checkpoint) = repository
+        .get_checkpoints_in_range(stored_idx, stored_idx)
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Ok(None);
+    };
+
+    let l2_start = PgU64::i64_to_u64(checkpoint.l2_start);
+    let l2_end = PgU64::i64_to_u64(checkpoint.l2_end);
+    let block_count = l2_end.saturating_sub(l2_start).saturating_add(1);
+    let total_pages = block_count.div_ceil(page_size);
+
+    let from = l2_start.saturating_add((page - 1).saturating_mul(page_size));
+    let items = if from <= l2_end {
+        let to = from.saturating_add(page_size - 1).min(l2_end);
+        repository
+            .get_blocks_in_range(PgU64(from).to_i64(), PgU64(to).to_i64())
+            .await?
+            .into_iter()
+            .map(RpcBlock::from)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let links = Links {
+        this: checkpoint_blocks_path(idx, page, page_size),
+        prev: (page > 1)
+            .then(|| checkpoint_blocks_path(idx, (page - 1).min(total_pages), page_size)),
+        next: (page < total_pages).then(|| checkpoint_blocks_path(idx, page + 1, page_size)),
+        checkpoint: Some(checkpoint_path(idx)),
+        blocks: None,
+    };
+    Ok(Some(CheckpointBlocks {
+        checkpoint_idx: idx,
+        l2_range: (l2_start, l2_end),
+        blocks: PaginatedData {
+            current_page: page,
+            total_pages,
+            absolute_first_page: 1,
+            items,
+        },
+        links,
+    }))
+}
+
+/// Block at `height`, `None` if it is not stored
+pub async fn block_resource<R: BlockRepository>(
+    repository: &R,
+    height: u64,
+) -> Result<Option<BlockResource>, DbErr> {
+    // the neighbours are fetched along with the block to link only to stored ones
+    let blocks = repository
+        .get_blocks_in_range(
+            PgU64(height.saturating_sub(1)).to_i64(),
+            PgU64(height.saturating_add(1)).to_i64(),
+        )
+        .await?;
+
+    let mut links = Links {
+        this: block_path(height),
+        ..Links::default()
+    };
+    let mut found = None;
+    for block in blocks {
+        let block_height = PgU64::i64_to_u64(block.height);
+        match block_height.cmp(&height) {
+            Ordering::Less => links.prev = Some(block_path(block_height)),
+            Ordering::Equal => found = Some(block),
+            Ordering::Greater => links.next = Some(block_path(block_height)),
+        }
+    }
+    Ok(found.map(|block| {
+        let block = RpcBlock::from(block);
+        links.checkpoint = Some(checkpoint_path(block.checkpoint_idx));
+        BlockResource { block, links }
+    }))
+}
+
+/// Block with hash `block_hash`, `None` if it is not stored
+pub async fn block_resource_by_hash<R: BlockRepository>(
+    repository: &R,
+    block_hash: &str,
+) -> Result<Option<BlockResource>, DbErr> {
+    match repository.get_block_by_hash(block_hash).await? {
+        Some(block) => block_resource(repository, PgU64::i64_to_u64(block.height)).await,
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,574 @@
+use crate::services::alert_service::Alert;
+use crate::utils::supervisor::report_success;
+use database::repository::{CheckpointEvents, CheckpointReconciliation, WebhookDeliveryRepository};
+use database::services::utils::unix_timestamp;
+use hmac::{Hmac, Mac};
+use model::checkpoint::{self, CheckpointState};
+use model::pgu64::PgU64;
+use model::reorg_event;
+use model::webhook_delivery::{self, NewWebhookEvent, WebhookEventType};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Number of pending deliveries to a URL sent on every dispatch
+const DISPATCH_BATCH_SIZE: u64 = 100;
+
+/// Where checkpoint events are sent and how failing deliveries are retried
+#[derive(Debug, Clone)]
+pub struct WebhookSettings {
+    pub urls: Vec<String>,
+    /// Key of the HMAC-SHA256 signature of every request
+    pub secret: String,
+    pub dispatch_interval: Duration,
+    pub timeout: Duration,
+    /// Failing deliveries are retried with an exponential backoff capped at this interval
+    pub max_retry_interval: Duration,
+    /// Acknowledged deliveries are deleted after this long, zero keeps them
+    pub retention: Duration,
+}
+
+/// Builds the checkpoint and alert events of the configured webhooks, the dispatcher sends
+/// them. Without URLs nothing is queued.
+///
+/// Checkpoint events are queued by the repository in the transaction of the checkpoint write,
+/// see [`CheckpointEvents`]. Alert producers never fail because of webhooks: an alert event
+/// that cannot be queued is logged and dropped.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookNotifier {
+    urls: Arc<[String]>,
+}
+
+impl WebhookNotifier {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self { urls: urls.into() }
+    }
+
+    /// Queue an `alert.raised` or `alert.resolved` event
+    pub async fn alert_changed<R: WebhookDeliveryRepository>(
+        &self,
+        repository: &R,
+        alert: &Alert,
+        raised: bool,
+    ) {
+        if self.urls.is_empty() {
+            return;
+        }
+        let event_type = match raised {
+            true => WebhookEventType::AlertRaised,
+            false => WebhookEventType::AlertResolved,
+        };
+        let checkpoint_idx = PgU64(alert.checkpoint_idx).to_i64();
+        let event = new_event(event_type, checkpoint_idx, json!({ "alert": alert }));
+        match repository.enqueue_webhook_event(event, &self.urls).await {
+            Ok(()) => debug!(
+                "Queued {} event for checkpoint {}",
+                event_type, alert.checkpoint_idx
+            ),
+            Err(e) => error!(
+                "Failed to queue {} event for checkpoint {}: {:?}",
+                event_type, alert.checkpoint_idx, e
+            ),
+        }
+    }
+}
+
+impl CheckpointEvents for WebhookNotifier {
+    fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// A `checkpoint.created` event for a checkpoint indexed for the first time
+    fn created(&self, checkpoint: &checkpoint::Model) -> Vec<NewWebhookEvent> {
+        let data = json!({ "checkpoint": checkpoint_data(checkpoint) });
+        vec![new_event(
+            WebhookEventType::CheckpointCreated,
+            checkpoint.idx,
+            data,
+        )]
+    }
+
+    /// The events describing the outcome of a reconciliation: a reorg, or a status and/or
+    /// txid change
+    fn reconciled(&self, reconciliation: &CheckpointReconciliation) -> Vec<NewWebhookEvent> {
+        let mut events = Vec::new();
+        match reconciliation {
+            CheckpointReconciliation::Updated { before, after } => {
+                if before.status != after.status {
+                    let data = json!({
+                        "checkpoint": checkpoint_data(after),
+                        "old_status": CheckpointState::from_status(&before.status).to_string(),
+                        "new_status": CheckpointState::from_status(&after.status).to_string(),
+                    });
+                    events.push(new_event(WebhookEventType::StatusChanged, after.idx, data));
+                }
+                if before.checkpoint_txid != after.checkpoint_txid {
+                    let data = json!({
+                        "checkpoint": checkpoint_data(after),
+                        "old_txid": before.checkpoint_txid,
+                        "new_txid": after.checkpoint_txid,
+                    });
+                    events.push(new_event(WebhookEventType::TxidChanged, after.idx, data));
+                }
+            }
+            CheckpointReconciliation::Reorged(event) => events.push(new_event(
+                WebhookEventType::Reorged,
+                event.checkpoint_idx,
+                reorg_data(event),
+            )),
+            CheckpointReconciliation::Unchanged | CheckpointReconciliation::Rejected { .. } => (),
+        }
+        events
+    }
+}
+
+fn new_event(event_type: WebhookEventType, checkpoint_idx: i64, data: Value) -> NewWebhookEvent {
+    let event_id = uuid::Uuid::new_v4().to_string();
+    let payload = json!({
+        "id": event_id,
+        "type": event_type.name(),
+        "created_at": unix_timestamp(),
+        "checkpoint_idx": PgU64::i64_to_u64(checkpoint_idx),
+        "data": data,
+    });
+    NewWebhookEvent {
+        event_id,
+        event_type,
+        checkpoint_idx,
+        payload: payload.to_string(),
+    }
+}
+
+fn checkpoint_data(checkpoint: &checkpoint::Model) -> Value {
+    json!({
+        "idx": PgU64::i64_to_u64(checkpoint.idx),
+        "status": CheckpointState::from_status(&checkpoint.status).to_string(),
+        "txid": checkpoint.checkpoint_txid,
+        "l1_range": [
+            PgU64::i64_to_u64(checkpoint.l1_start),
+            PgU64::i64_to_u64(checkpoint.l1_end),
+        ],
+        "l2_range": [
+            PgU64::i64_to_u64(checkpoint.l2_start),
+            PgU64::i64_to_u64(checkpoint.l2_end),
+        ],
+    })
+}
+
+fn reorg_data(event: &reorg_event::Model) -> Value {
+    json!({
+        "old_status": CheckpointState::from_status(&event.old_status).to_string(),
+        "new_status": CheckpointState::from_status(&event.new_status).to_string(),
+        "old_txid": event.old_txid,
+        "new_txid": event.new_txid,
+        "detected_at": event.detected_at,
+    })
+}
+
+/// This function POSTs the pending webhook deliveries to their URLs
+/// It will run in a loop with a delay of `dispatch_interval` until `token` is cancelled
+pub async fn start_webhook_dispatcher<R: WebhookDeliveryRepository + 'static>(
+    repository: Arc<R>,
+    settings: WebhookSettings,
+    token: CancellationToken,
+) {
+    info!(
+        "Starting webhook dispatcher for {} URLs...",
+        settings.urls.len()
+    );
+    let client = match reqwest::Client::builder().timeout(settings.timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to build the webhook HTTP client: {}", e);
+            return;
+        }
+    };
+    let mut interval = tokio::time::interval(settings.dispatch_interval);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let dispatches = settings
+            .urls
+            .iter()
+            .map(|url| dispatch(&client, repository.as_ref(), &settings, url, &token));
+        let outcomes = futures::future::join_all(dispatches).await;
+        let dispatched = outcomes.into_iter().collect::<anyhow::Result<Vec<()>>>();
+        match dispatched.and(prune(repository.as_ref(), &settings).await) {
+            Ok(_) => report_success(),
+            Err(e) => error!("Error dispatching webhooks: {}", e),
+        }
+    }
+}
+
+/// Delete the deliveries acknowledged longer than the retention ago
+async fn prune<R: WebhookDeliveryRepository>(
+    repository: &R,
+    settings: &WebhookSettings,
+) -> anyhow::Result<()> {
+    if settings.retention.is_zero() {
+        return Ok(());
+    }
+    let retention = settings.retention.as_secs().min(i64::MAX as u64) as i64;
+    let pruned = repository
+        .prune_webhook_deliveries(unix_timestamp().saturating_sub(retention))
+        .await?;
+    if pruned > 0 {
+        debug!("Pruned {} acknowledged webhook deliveries", pruned);
+    }
+    Ok(())
+}
+
+/// Send the due deliveries to `url` in the order they were queued.
+///
+/// A failed delivery is retried later and holds back the ones queued after it, so that a
+/// receiver never sees e.g. a checkpoint finalized before it was confirmed.
+async fn dispatch<R: WebhookDeliveryRepository>(
+    client: &reqwest::Client,
+    repository: &R,
+    settings: &WebhookSettings,
+    url: &str,
+    token: &CancellationToken,
+) -> anyhow::Result<()> {
+    let deliveries = repository
+        .get_pending_webhook_deliveries(url, DISPATCH_BATCH_SIZE)
+        .await?;
+    for delivery in deliveries {
+        let now = unix_timestamp();
+        if token.is_cancelled() || delivery.next_attempt_at > now {
+            return Ok(());
+        }
+        match send(client, &settings.secret, &delivery).await {
+            Ok(()) => {
+                repository.mark_webhook_delivered(delivery.id).await?;
+                metrics::counter!("explorer_webhook_deliveries_total", "outcome" => "delivered")
+                    .increment(1);
+                debug!(
+                    "Delivered {} event {} to {}",
+                    delivery.event_type, delivery.event_id, url
+                );
+            }
+            Err(e) => {
+                let attempts = delivery.attempts.saturating_add(1);
+                let next_attempt_at = now
+                    + retry_delay(settings, attempts)
+                        .as_secs()
+                        .min(i64::MAX as u64) as i64;
+                repository
+                    .mark_webhook_failed(delivery.id, e.clone(), next_attempt_at)
+                    .await?;
+                metrics::counter!("explorer_webhook_deliveries_total", "outcome" => "failed")
+                    .increment(1);
+                warn!(
+                    "Failed to deliver {} event {} to {} (attempt {}): {}",
+                    delivery.event_type, delivery.event_id, url, attempts, e
+                );
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// POST a delivery, any 2xx answer acknowledges it.
+///
+/// The signature is the hex HMAC-SHA256 of `<timestamp>.<body>` keyed with the secret, so
+/// that receivers can both authenticate the request and reject replays.
+async fn send(
+    client: &reqwest::Client,
+    secret: &str,
+    delivery: &webhook_delivery::Model,
+) -> Result<(), String> {
+    let timestamp = unix_timestamp().to_string();
+    let signature = signature(secret, &timestamp, &delivery.payload)?;
+
+    let response = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Explorer-Event", &delivery.event_type)
+        .header("X-Explorer-Event-Id", &delivery.event_id)
+        .header("X-Explorer-Delivery", delivery.id.to_string())
+        .header("X-Explorer-Timestamp", &timestamp)
+        .header("X-Explorer-Signature", format!("sha256={}", signature))
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("receiver answered {}", response.status()))
+    }
+}
+
+/// Hex HMAC-SHA256 of `<timestamp>.<body>` keyed with `secret`
+fn signature(secret: &str, timestamp: &str, body: &str) -> Result<String, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|e| e.to_string())?;
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Wait before the next attempt of a delivery that failed `attempts` times: the dispatch
+/// interval doubled on every failure, up to the configured maximum
+fn retry_delay(settings: &WebhookSettings, attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    settings
+        .dispatch_interval
+        .saturating_mul(2u32.pow(exponent))
+        .min(settings.max_retry_interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_repository::{repository_tests, TestRepository};
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    fn settings(urls: Vec<String>, dispatch_interval: Duration) -> WebhookSettings {
+        WebhookSettings {
+            urls,
+            secret: "whsec_test".to_string(),
+            dispatch_interval,
+            timeout: Duration::from_secs(5),
+            max_retry_interval: Duration::from_secs(300),
+            retention: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn signs_the_timestamp_and_the_body_with_the_secret() {
+        let body = r#"{"id":"evt_1","type":"checkpoint.created"}"#;
+
+        assert_eq!(
+            signature("whsec_test", "1700000000", body).unwrap(),
+            "4a6e36e792276e06746642ed437ede1bf3723ac600a3ebac3ae1de62bde1902d"
+        );
+        assert_ne!(
+            signature("whsec_test", "1700000001", body).unwrap(),
+            signature("whsec_test", "1700000000", body).unwrap()
+        );
+    }
+
+    #[test]
+    fn doubles_the_retry_delay_up_to_the_maximum() {
+        let settings = settings(Vec::new(), Duration::from_secs(10));
+
+        let delays: Vec<u64> = (0..=7)
+            .map(|attempts| retry_delay(&settings, attempts).as_secs())
+            .collect();
+        assert_eq!(delays, [10, 10, 20, 40, 80, 160, 300, 300]);
+        assert_eq!(retry_delay(&settings, i32::MAX).as_secs(), 300);
+    }
+
+    /// A request as the receiver saw it
+    #[derive(Debug, Clone)]
+    struct Received {
+        path: String,
+        event_id: String,
+        timestamp: String,
+        signature: String,
+        body: String,
+    }
+
+    #[derive(Default)]
+    struct Receiver {
+        received: Vec<Received>,
+        /// Requests still to answer with a 503, by path
+        failures: HashMap<String, usize>,
+    }
+
+    async fn receive(
+        State(receiver): State<Arc<Mutex<Receiver>>>,
+        Path(path): Path<String>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        let header = |name: &str| headers[name].to_str().unwrap().to_string();
+        let mut receiver = receiver.lock().unwrap();
+        receiver.received.push(Received {
+            path: path.clone(),
+            event_id: header("x-explorer-event-id"),
+            timestamp: header("x-explorer-timestamp"),
+            signature: header("x-explorer-signature"),
+            body,
+        });
+        match receiver.failures.get_mut(&path) {
+            Some(failures) if *failures > 0 => {
+                *failures -= 1;
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            _ => StatusCode::OK,
+        }
+    }
+
+    /// Serve `receiver` in the background, returns its base URL
+    fn serve(receiver: Arc<Mutex<Receiver>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route("/:path", post(receive))
+            .with_state(receiver);
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+        url
+    }
+
+    fn event(event_id: &str) -> NewWebhookEvent {
+        NewWebhookEvent {
+            event_id: event_id.to_string(),
+            event_type: WebhookEventType::CheckpointCreated,
+            checkpoint_idx: PgU64(0).to_i64(),
+            payload: json!({ "id": event_id }).to_string(),
+        }
+    }
+
+    async fn pending(repository: &impl WebhookDeliveryRepository, url: &str) -> Vec<String> {
+        repository
+            .get_pending_webhook_deliveries(url, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|delivery| delivery.event_id)
+            .collect()
+    }
+
+    async fn a_failed_delivery_holds_back_the_later_ones_to_its_url<R: TestRepository>() {
+        let repository = R::open().await;
+        let receiver = Arc::new(Mutex::new(Receiver::default()));
+        receiver.lock().unwrap().failures.insert("a".to_string(), 1);
+        let base = serve(receiver.clone());
+        let (a, b) = (format!("{}/a", base), format!("{}/b", base));
+        // without a dispatch interval a failed delivery is due again at once
+        let settings = settings(vec![a.clone(), b.clone()], Duration::ZERO);
+        for event_id in ["e1", "e2"] {
+            repository
+                .enqueue_webhook_event(event(event_id), &settings.urls)
+                .await
+                .unwrap();
+        }
+        let client = reqwest::Client::new();
+        let token = CancellationToken::new();
+
+        for url in [&a, &b] {
+            dispatch(&client, &repository, &settings, url, &token)
+                .await
+                .unwrap();
+        }
+        let sent = |receiver: &Mutex<Receiver>| -> Vec<(String, String)> {
+            let receiver = receiver.lock().unwrap();
+            let received = receiver.received.iter();
+            received
+                .map(|request| (request.path.clone(), request.event_id.clone()))
+                .collect()
+        };
+        let mut expected = vec![
+            ("a".to_string(), "e1".to_string()),
+            ("b".to_string(), "e1".to_string()),
+            ("b".to_string(), "e2".to_string()),
+        ];
+        assert_eq!(sent(&receiver), expected);
+        assert_eq!(pending(&repository, &a).await, ["e1", "e2"]);
+        assert!(pending(&repository, &b).await.is_empty());
+        let failed = &repository
+            .get_pending_webhook_deliveries(&a, 1)
+            .await
+            .unwrap()[0];
+        assert_eq!(failed.attempts, 1);
+        assert!(failed.last_error.as_deref().unwrap().contains("503"));
+
+        dispatch(&client, &repository, &settings, &a, &token)
+            .await
+            .unwrap();
+        expected.push(("a".to_string(), "e1".to_string()));
+        expected.push(("a".to_string(), "e2".to_string()));
+        assert_eq!(sent(&receiver), expected);
+        assert!(pending(&repository, &a).await.is_empty());
+
+        for request in &receiver.lock().unwrap().received {
+            let expected = signature(&settings.secret, &request.timestamp, &request.body);
+            assert_eq!(request.signature, format!("sha256={}", expected.unwrap()));
+        }
+    }
+
+    async fn waits_for_the_retry_delay_before_attempting_again<R: TestRepository>() {
+        let repository = R::open().await;
+        let receiver = Arc::new(Mutex::new(Receiver::default()));
+        receiver.lock().unwrap().failures.insert("a".to_string(), 1);
+        let url = format!("{}/a", serve(receiver.clone()));
+        let settings = settings(vec![url.clone()], Duration::from_secs(60));
+        repository
+            .enqueue_webhook_event(event("e1"), &settings.urls)
+            .await
+            .unwrap();
+        let client = reqwest::Client::new();
+        let token = CancellationToken::new();
+
+        for _ in 0..2 {
+            dispatch(&client, &repository, &settings, &url, &token)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(receiver.lock().unwrap().received.len(), 1);
+        let failed = &repository
+            .get_pending_webhook_deliveries(&url, 1)
+            .await
+            .unwrap()[0];
+        assert!(failed.next_attempt_at >= unix_timestamp() + 59);
+    }
+
+    async fn prunes_the_acknowledged_deliveries_after_the_retention<R: TestRepository>() {
+        let repository = R::open().await;
+        let url = format!("{}/a", serve(Default::default()));
+        let mut settings = settings(vec![url.clone()], Duration::ZERO);
+        repository
+            .enqueue_webhook_event(event("e1"), &settings.urls)
+            .await
+            .unwrap();
+        dispatch(
+            &reqwest::Client::new(),
+            &repository,
+            &settings,
+            &url,
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        // a zero retention keeps them, a delivery acknowledged just now is kept too
+        prune(&repository, &settings).await.unwrap();
+        settings.retention = Duration::from_secs(3600);
+        prune(&repository, &settings).await.unwrap();
+        let counts = repository.get_webhook_delivery_counts().await.unwrap();
+        assert_eq!((counts.pending, counts.delivered), (0, 1));
+
+        // the cutoff `prune` computes an hour later
+        let cutoff = unix_timestamp() + 1;
+        repository.prune_webhook_deliveries(cutoff).await.unwrap();
+        let counts = repository.get_webhook_delivery_counts().await.unwrap();
+        assert_eq!((counts.pending, counts.delivered), (0, 0));
+    }
+
+    repository_tests!(
+        a_failed_delivery_holds_back_the_later_ones_to_its_url,
+        waits_for_the_retry_delay_before_attempting_again,
+        prunes_the_acknowledged_deliveries_after_the_retention,
+    );
+}
@@ -1,6 +1,16 @@
-use clap::Parser;
+use crate::services::alert_service::AlertThresholds;
+use crate::services::checkpoint_service::{IndexBounds, StatusRevisitIntervals};
+use crate::services::lifecycle_service::L1BlockTimes;
+use crate::services::webhook_service::{WebhookNotifier, WebhookSettings};
+use clap::{Parser, Subcommand, ValueEnum};
+use database::connection::{DatabaseOptions, LevelFilter};
+use fullnode_client::evm::EvmClient;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(
     name = "Checkpoint explorer",
     author = "Your Name",
@@ -8,6 +18,20 @@ use clap::Parser;
     about = "A Checkpoint explorer Application"
 )]
 pub struct Config {
+    /// Maintenance command to run instead of the explorer
+    #[command(subcommand)]
+    pub command: Option<AdminCommand>,
+
+    /// Which parts of the explorer this process runs
+    #[arg(
+        long,
+        env = "APP_MODE",
+        value_enum,
+        default_value_t = RunMode::All,
+        help = "Run the indexer, the API or both"
+    )]
+    pub mode: RunMode,
+
     /// The URL of the Strata Fullnode
     #[arg(
         long,
@@ -26,6 +50,102 @@ pub struct Config {
     )]
     pub database_url: String,
 
+    /// The URL of an optional read-only replica used by the API
+    #[arg(
+        long,
+        env = "APP_DATABASE_READ_URL",
+        help = "Read-only replica database URL used to serve API requests"
+    )]
+    pub database_read_url: Option<String>,
+
+    /// Maximum number of connections in each database pool
+    #[arg(
+        long,
+        env = "APP_DB_MAX_CONNECTIONS",
+        default_value_t = 10,
+        help = "Maximum number of pooled database connections"
+    )]
+    pub db_max_connections: u32,
+
+    /// Minimum number of connections kept open in each database pool
+    #[arg(
+        long,
+        env = "APP_DB_MIN_CONNECTIONS",
+        default_value_t = 1,
+        help = "Minimum number of pooled database connections"
+    )]
+    pub db_min_connections: u32,
+
+    /// How long to wait for a pooled connection, in seconds
+    #[arg(
+        long,
+        env = "APP_DB_ACQUIRE_TIMEOUT",
+        default_value_t = 30,
+        help = "Connection acquire timeout in seconds"
+    )]
+    pub db_acquire_timeout: u64,
+
+    /// How long an unused connection stays in the pool, in seconds
+    #[arg(
+        long,
+        env = "APP_DB_IDLE_TIMEOUT",
+        default_value_t = 600,
+        help = "Idle connection timeout in seconds"
+    )]
+    pub db_idle_timeout: u64,
+
+    /// Server side statement timeout in milliseconds (PostgreSQL only)
+    #[arg(
+        long,
+        env = "APP_DB_STATEMENT_TIMEOUT_MS",
+        help = "Statement timeout in milliseconds, unset keeps the server default"
+    )]
+    pub db_statement_timeout_ms: Option<u64>,
+
+    /// Log level for executed SQL statements
+    #[arg(
+        long,
+        env = "APP_DB_SQL_LOG_LEVEL",
+        default_value_t = LevelFilter::Info,
+        help = "SQL statement log level (off, error, warn, info, debug, trace)"
+    )]
+    pub db_sql_log_level: LevelFilter,
+
+    /// Number of attempts to reconnect to the database at startup
+    #[arg(
+        long,
+        env = "APP_DB_CONNECT_RETRIES",
+        default_value_t = 10,
+        help = "Database connection retries at startup"
+    )]
+    pub db_connect_retries: u32,
+
+    /// Apply pending migrations at startup instead of refusing to start
+    #[arg(
+        long,
+        env = "APP_APPLY_MIGRATIONS",
+        default_value_t = false,
+        help = "Apply pending database migrations at startup"
+    )]
+    pub apply_migrations: bool,
+
+    /// First checkpoint to index, earlier checkpoints and their blocks are never fetched
+    #[arg(
+        long,
+        env = "APP_START_CHECKPOINT",
+        default_value_t = 0,
+        help = "Index of the first checkpoint to index"
+    )]
+    pub start_checkpoint: u64,
+
+    /// Last checkpoint to index, the indexer follows the fullnode tip when unset
+    #[arg(
+        long,
+        env = "APP_END_CHECKPOINT",
+        help = "Index of the last checkpoint to index, unset follows the fullnode tip"
+    )]
+    pub end_checkpoint: Option<u64>,
+
     /// The fetch interval in seconds
     #[arg(
         long,
@@ -35,15 +155,240 @@ pub struct Config {
     )]
     pub fetch_interval: u64,
 
-    /// The status update interval in seconds
+    /// The status update interval of unknown and pending checkpoints in seconds
     #[arg(
         long,
         env = "APP_STATUS_UPDATE_INTERVAL",
         default_value_t = 30,
-        help = "Status update interval in seconds"
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Status update interval of unknown and pending checkpoints in seconds"
     )]
     pub status_update_interval: u64,
 
+    /// The status update interval of confirmed checkpoints in seconds
+    #[arg(
+        long,
+        env = "APP_CONFIRMED_STATUS_UPDATE_INTERVAL",
+        default_value_t = 120,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Status update interval of confirmed checkpoints in seconds"
+    )]
+    pub confirmed_status_update_interval: u64,
+
+    /// Maximum number of block requests in flight to the fullnode
+    #[arg(
+        long,
+        env = "APP_BLOCK_FETCH_CONCURRENCY",
+        default_value_t = 10,
+        help = "Number of blocks fetched concurrently"
+    )]
+    pub block_fetch_concurrency: usize,
+
+    /// The integrity check interval in seconds
+    #[arg(
+        long,
+        env = "APP_INTEGRITY_CHECK_INTERVAL",
+        default_value_t = 300,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Interval in seconds between checks for missing checkpoints and blocks"
+    )]
+    pub integrity_check_interval: u64,
+
+    /// The L1 block indexing interval in seconds
+    #[arg(
+        long,
+        env = "APP_L1_BLOCK_INTERVAL",
+        default_value_t = 30,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Interval in seconds between passes of the L1 block indexer"
+    )]
+    pub l1_block_interval: u64,
+
+    /// The EVM block linking interval in seconds
+    #[arg(
+        long,
+        env = "APP_EVM_LINK_INTERVAL",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Interval in seconds between passes of the EVM block linker"
+    )]
+    pub evm_link_interval: u64,
+
+    /// The bridge indexing interval in seconds, 0 disables bridge indexing
+    #[arg(
+        long,
+        env = "APP_BRIDGE_INTERVAL",
+        default_value_t = 10,
+        help = "Interval in seconds between passes of the bridge indexer, 0 disables it"
+    )]
+    pub bridge_interval: u64,
+
+    /// Bridge operators whose duties are indexed along with the bridge intents
+    #[arg(
+        long,
+        env = "APP_BRIDGE_OPERATORS",
+        value_delimiter = ',',
+        help = "Comma separated indexes of the bridge operators whose duties are indexed"
+    )]
+    pub bridge_operators: Vec<u32>,
+
+    /// The consistency audit interval in seconds, 0 disables the periodic audit
+    #[arg(
+        long,
+        env = "APP_AUDIT_INTERVAL",
+        default_value_t = 3600,
+        help = "Interval in seconds between consistency audits against the fullnode, 0 disables them"
+    )]
+    pub audit_interval: u64,
+
+    /// Number of checkpoints compared with the fullnode on every periodic audit
+    #[arg(
+        long,
+        env = "APP_AUDIT_BATCH_SIZE",
+        default_value_t = 100,
+        help = "Number of checkpoints audited on every periodic audit"
+    )]
+    pub audit_batch_size: u64,
+
+    /// Longest time a checkpoint may stay pending, in seconds, 0 disables the alert
+    #[arg(
+        long,
+        env = "APP_ALERT_PENDING_THRESHOLD",
+        default_value_t = 3600,
+        help = "Seconds a checkpoint may stay pending before raising an alert, 0 disables it"
+    )]
+    pub alert_pending_threshold: u64,
+
+    /// Longest time a checkpoint may stay confirmed, in seconds, 0 disables the alert
+    #[arg(
+        long,
+        env = "APP_ALERT_CONFIRMED_THRESHOLD",
+        default_value_t = 7200,
+        help = "Seconds a checkpoint may stay confirmed before raising an alert, 0 disables it"
+    )]
+    pub alert_confirmed_threshold: u64,
+
+    /// Largest acceptable gap between the fullnode tip and the latest checkpoint, 0 disables
+    /// the alert
+    #[arg(
+        long,
+        env = "APP_ALERT_CHECKPOINT_LAG",
+        default_value_t = 10,
+        help = "Checkpoints the index may be behind the fullnode before raising an alert, 0 disables it"
+    )]
+    pub alert_checkpoint_lag: u64,
+
+    /// The alert evaluation interval in seconds
+    #[arg(
+        long,
+        env = "APP_ALERT_CHECK_INTERVAL",
+        default_value_t = 60,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Interval in seconds between alert evaluations"
+    )]
+    pub alert_check_interval: u64,
+
+    /// URLs the checkpoint events are POSTed to, webhooks are disabled when empty
+    #[arg(
+        long,
+        env = "APP_WEBHOOK_URLS",
+        value_delimiter = ',',
+        help = "Comma separated URLs receiving the checkpoint events"
+    )]
+    pub webhook_urls: Vec<String>,
+
+    /// Secret signing the webhook bodies, required with `webhook_urls`
+    #[arg(
+        long,
+        env = "APP_WEBHOOK_SECRET",
+        hide_env_values = true,
+        help = "Secret used to sign the webhook requests (HMAC-SHA256)"
+    )]
+    pub webhook_secret: Option<String>,
+
+    /// How often pending webhook deliveries are sent, in seconds
+    #[arg(
+        long,
+        env = "APP_WEBHOOK_DISPATCH_INTERVAL",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Interval in seconds between webhook dispatches"
+    )]
+    pub webhook_dispatch_interval: u64,
+
+    /// How long to wait for a webhook receiver to answer, in seconds
+    #[arg(
+        long,
+        env = "APP_WEBHOOK_TIMEOUT",
+        default_value_t = 10,
+        help = "Webhook request timeout in seconds"
+    )]
+    pub webhook_timeout: u64,
+
+    /// Longest wait between two attempts of a failing webhook delivery, in seconds
+    #[arg(
+        long,
+        env = "APP_WEBHOOK_MAX_RETRY_INTERVAL",
+        default_value_t = 3600,
+        help = "Maximum interval in seconds between attempts of a failing webhook delivery"
+    )]
+    pub webhook_max_retry_interval: u64,
+
+    /// How long acknowledged webhook deliveries are kept, in seconds, 0 keeps them forever
+    #[arg(
+        long,
+        env = "APP_WEBHOOK_RETENTION",
+        default_value_t = 7 * 24 * 3600,
+        help = "Seconds acknowledged webhook deliveries are kept before being deleted, 0 keeps them"
+    )]
+    pub webhook_retention: u64,
+
+    /// Esplora compatible API of the L1 block explorer, e.g. `https://mempool.space/api`
+    #[arg(
+        long,
+        env = "APP_L1_ESPLORA_URL",
+        help = "Esplora API URL used to look up the time of the L1 blocks confirming checkpoints"
+    )]
+    pub l1_esplora_url: Option<String>,
+
+    /// `eth_` JSON-RPC endpoint of the execution client, e.g. `http://localhost:8545`
+    #[arg(
+        long,
+        env = "APP_EVM_RPC_URL",
+        help = "Execution client JSON-RPC URL used to link L2 blocks to EVM blocks"
+    )]
+    pub evm_rpc_url: Option<String>,
+
+    /// How often a standby replica retries to become the indexer, and the leader checks it
+    /// still is, in seconds
+    #[arg(
+        long,
+        env = "APP_LEADER_CHECK_INTERVAL",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Indexer leader lock check interval in seconds"
+    )]
+    pub leader_check_interval: u64,
+
+    /// Address of the probes and metrics of a process running only the indexer, a process
+    /// serving the API serves them next to it
+    #[arg(
+        long,
+        env = "APP_METRICS_ADDR",
+        default_value = "0.0.0.0:9100",
+        help = "Address serving /health, /ready and /metrics in indexer mode"
+    )]
+    pub metrics_addr: SocketAddr,
+
+    /// How long to wait for background tasks to finish on shutdown, in seconds
+    #[arg(
+        long,
+        env = "APP_SHUTDOWN_TIMEOUT",
+        default_value_t = 30,
+        help = "Time in seconds given to background tasks to stop on shutdown"
+    )]
+    pub shutdown_timeout: u64,
+
     #[arg(
         long,
         env = "STRATA_URL",
@@ -52,3 +397,162 @@ pub struct Config {
     )]
     pub strata_url: String,
 }
+
+/// Parts of the explorer run by a process. Any number of `api` replicas can share a database,
+/// `indexer` and `all` replicas elect a single one of them to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RunMode {
+    /// Only fetch from the fullnode and write to the database
+    Indexer,
+    /// Only serve the API
+    Api,
+    /// Both the indexer and the API
+    All,
+}
+
+/// Maintenance commands, they only touch the data they repair and can run alongside a live
+/// indexer
+#[derive(Subcommand, Debug, Clone)]
+pub enum AdminCommand {
+    /// Delete a range of checkpoints with their blocks and fetch them again
+    Reindex {
+        /// First checkpoint to re-index
+        from: u64,
+        /// Last checkpoint to re-index
+        to: u64,
+        /// Also delete the reorg events recorded for these checkpoints, kept by default
+        #[arg(long)]
+        with_reorg_events: bool,
+    },
+    /// Delete the blocks of a checkpoint and fetch them again
+    RefetchBlocks {
+        /// Checkpoint whose blocks are re-fetched
+        idx: u64,
+    },
+    /// Delete every checkpoint after an index with its blocks, the indexer fetches them again
+    Purge {
+        /// Last checkpoint to keep
+        #[arg(long)]
+        after: u64,
+        /// Also delete the reorg events recorded for the deleted checkpoints, kept by default
+        #[arg(long)]
+        with_reorg_events: bool,
+    },
+    /// Compare stored checkpoints and blocks with the fullnode and print the differences as JSON
+    Audit {
+        /// First checkpoint to audit, defaults to the first one stored
+        #[arg(long)]
+        from: Option<u64>,
+        /// Last checkpoint to audit, defaults to the last one stored
+        #[arg(long)]
+        to: Option<u64>,
+        /// Write the report to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Number of blocks fetched concurrently
+        #[arg(long, default_value_t = 10)]
+        concurrency: usize,
+    },
+    /// Reconcile the status of the stored checkpoints with the fullnode right away,
+    /// finalized ones included
+    RefreshStatus {
+        /// First checkpoint to refresh, defaults to the first one stored
+        #[arg(long)]
+        from: Option<u64>,
+        /// Last checkpoint to refresh, defaults to the last one stored
+        #[arg(long)]
+        to: Option<u64>,
+    },
+}
+
+impl RunMode {
+    pub fn runs_indexer(&self) -> bool {
+        matches!(self, RunMode::Indexer | RunMode::All)
+    }
+
+    pub fn runs_api(&self) -> bool {
+        matches!(self, RunMode::Api | RunMode::All)
+    }
+}
+
+impl Config {
+    /// Connection pool settings derived from the `db_*` options
+    pub fn database_options(&self) -> DatabaseOptions {
+        DatabaseOptions {
+            max_connections: self.db_max_connections,
+            min_connections: self.db_min_connections,
+            acquire_timeout: Duration::from_secs(self.db_acquire_timeout),
+            idle_timeout: Duration::from_secs(self.db_idle_timeout),
+            statement_timeout: self.db_statement_timeout_ms.map(Duration::from_millis),
+            sql_log_level: self.db_sql_log_level,
+            connect_retries: self.db_connect_retries,
+        }
+    }
+
+    /// Range of checkpoints to index derived from the `*_checkpoint` options
+    pub fn index_bounds(&self) -> IndexBounds {
+        IndexBounds {
+            start: self.start_checkpoint,
+            end: self.end_checkpoint,
+        }
+    }
+
+    /// Status revisit intervals derived from the `*status_update_interval` options
+    pub fn status_revisit_intervals(&self) -> StatusRevisitIntervals {
+        StatusRevisitIntervals {
+            pending: Duration::from_secs(self.status_update_interval),
+            confirmed: Duration::from_secs(self.confirmed_status_update_interval),
+        }
+    }
+
+    /// Alert thresholds derived from the `alert_*` options, 0 disabling a check
+    pub fn alert_thresholds(&self) -> AlertThresholds {
+        let enabled = |threshold: u64| (threshold > 0).then_some(threshold);
+        AlertThresholds {
+            pending_seconds: enabled(self.alert_pending_threshold),
+            confirmed_seconds: enabled(self.alert_confirmed_threshold),
+            checkpoint_lag: enabled(self.alert_checkpoint_lag),
+        }
+    }
+
+    /// Webhook settings derived from the `webhook_*` options, `None` when no URL is
+    /// configured. Fails when URLs are configured without a signing secret.
+    pub fn webhook_settings(&self) -> anyhow::Result<Option<WebhookSettings>> {
+        if self.webhook_urls.is_empty() {
+            return Ok(None);
+        }
+        let Some(secret) = self
+            .webhook_secret
+            .clone()
+            .filter(|secret| !secret.is_empty())
+        else {
+            anyhow::bail!("Webhook URLs are configured without APP_WEBHOOK_SECRET to sign them");
+        };
+        Ok(Some(WebhookSettings {
+            urls: self.webhook_urls.clone(),
+            secret,
+            dispatch_interval: Duration::from_secs(self.webhook_dispatch_interval),
+            timeout: Duration::from_secs(self.webhook_timeout),
+            max_retry_interval: Duration::from_secs(self.webhook_max_retry_interval),
+            retention: Duration::from_secs(self.webhook_retention),
+        }))
+    }
+
+    /// L1 block time lookup, disabled without an Esplora URL
+    pub fn l1_block_times(&self) -> L1BlockTimes {
+        L1BlockTimes::new(self.l1_esplora_url.clone())
+    }
+
+    /// Client of the execution client, `None` unless configured
+    pub fn evm_client(&self) -> Option<Arc<EvmClient>> {
+        self.evm_rpc_url
+            .clone()
+            .filter(|url| !url.is_empty())
+            .map(|url| Arc::new(EvmClient::new(url)))
+    }
+
+    /// Notifier queueing checkpoint events for the configured webhooks
+    pub fn webhook_notifier(&self) -> WebhookNotifier {
+        WebhookNotifier::new(self.webhook_urls.clone())
+    }
+}
@@ -0,0 +1,238 @@
+use crate::services::lifecycle_service::L1BlockTimes;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use fullnode_client::evm::EvmClient;
+use fullnode_client::fetcher::StrataFetcher;
+use model::block::RpcBlockHeader;
+use model::checkpoint::RpcCheckpointInfo;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// What the fake fullnode answers, editable while it is serving
+#[derive(Debug, Default)]
+pub struct FakeChain {
+    /// `strata_getCheckpointInfo` answers keyed by checkpoint index, the last one is the
+    /// `strata_getLatestCheckpointIndex` answer
+    pub checkpoints: BTreeMap<u64, Value>,
+    /// `strata_getHeadersAtIdx` answers keyed by L2 height
+    pub headers: BTreeMap<u64, Value>,
+    /// `strata_getExecUpdateById` answers keyed by block hash
+    pub exec_updates: HashMap<String, Value>,
+    /// `eth_getBlockByHash` answers keyed by 0x-prefixed block hash
+    pub evm_blocks: HashMap<String, Value>,
+    /// `eth_getTransactionByHash` answers keyed by 0x-prefixed transaction hash
+    pub evm_transactions: HashMap<String, Value>,
+    /// Duties of each bridge operator, `strata_getBridgeDuties` lists them from
+    /// `start_index` taken as a position in the list
+    pub bridge_duties: BTreeMap<u64, Vec<Value>>,
+    /// `strata_getL1blockHash` answers keyed by L1 height
+    pub l1_block_hashes: BTreeMap<u64, String>,
+    /// Header times the L1 explorer reports at `GET /block/{hash}`, keyed by block hash
+    pub l1_block_times: HashMap<String, i64>,
+    /// Parameters answered with a JSON-RPC error, whatever the method
+    pub failing: HashSet<String>,
+}
+
+/// JSON-RPC server on a local port answering like a Strata fullnode, like the execution
+/// client for the `eth_` methods and like an Esplora L1 explorer, from a [`FakeChain`]
+#[derive(Clone, Default)]
+pub struct FakeFullnode {
+    pub chain: Arc<Mutex<FakeChain>>,
+}
+
+impl FakeFullnode {
+    /// Serve the chain in the background, returns a fetcher pointed at it
+    pub fn serve(&self) -> StrataFetcher {
+        StrataFetcher::new(self.spawn_server())
+    }
+
+    /// Serve the chain in the background, returns an execution client pointed at it
+    pub fn serve_evm(&self) -> EvmClient {
+        EvmClient::new(self.spawn_server())
+    }
+
+    /// Serve the chain in the background, returns the L1 block times looked up from it
+    pub fn serve_esplora(&self) -> L1BlockTimes {
+        L1BlockTimes::new(Some(self.spawn_server()))
+    }
+
+    /// Add the L1 block at `height`, see [`l1_block_hash`], with the header time the L1
+    /// explorer reports for it
+    pub fn add_l1_block(&self, height: u64, timestamp: i64) {
+        let mut chain = self.chain();
+        chain.l1_block_hashes.insert(height, l1_block_hash(height));
+        chain
+            .l1_block_times
+            .insert(l1_block_hash(height), timestamp);
+    }
+
+    /// Add the EVM block `number` whose hash is the `exec_segment_hash` of the L2 block at
+    /// `height`, see [`evm_block_hash`]
+    pub fn add_evm_block(&self, height: u64, number: u64) {
+        let hash = evm_block_hash(height);
+        let block = json!({ "number": format!("{:#x}", number), "hash": hash });
+        self.chain().evm_blocks.insert(hash, block);
+    }
+
+    fn spawn_server(&self) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route("/", post(rpc))
+            .route("/block/:hash", get(esplora_block))
+            .with_state(self.chain.clone());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+        url
+    }
+
+    pub fn chain(&self) -> std::sync::MutexGuard<'_, FakeChain> {
+        self.chain.lock().unwrap()
+    }
+
+    /// Add checkpoint `idx` covering the L2 heights `l2_range` with the given status and
+    /// txid, along with the headers of its blocks
+    pub fn add_checkpoint(&self, idx: u64, l2_range: (u64, u64), status: &str, txid: &str) {
+        let mut chain = self.chain();
+        chain
+            .checkpoints
+            .insert(idx, checkpoint_json(idx, l2_range, status, txid));
+        for height in l2_range.0..=l2_range.1 {
+            chain.headers.insert(height, json!([header_json(height)]));
+        }
+    }
+}
+
+/// The checkpoint `idx` as the fullnode reports it, without an L1 reference when pending
+pub fn checkpoint_info(
+    idx: u64,
+    l2_range: (u64, u64),
+    status: &str,
+    txid: &str,
+) -> RpcCheckpointInfo {
+    serde_json::from_value(checkpoint_json(idx, l2_range, status, txid)).unwrap()
+}
+
+/// Header of the block at `height`, see [`block_hash`]
+pub fn block_header(height: u64) -> RpcBlockHeader {
+    serde_json::from_value(header_json(height)).unwrap()
+}
+
+/// Hash of the block at `height`
+pub fn block_hash(height: u64) -> String {
+    format!("{:064x}", height.wrapping_add(1))
+}
+
+/// Hash of the L1 block at `height`
+pub fn l1_block_hash(height: u64) -> String {
+    format!("{:064x}", height)
+}
+
+/// 0x-prefixed hash of the EVM block of the L2 block at `height`, its `exec_segment_hash`
+pub fn evm_block_hash(height: u64) -> String {
+    format!("0x{:064x}", height)
+}
+
+fn checkpoint_json(idx: u64, l2_range: (u64, u64), status: &str, txid: &str) -> Value {
+    let l1_height = 100 + idx * 2;
+    let l1_reference = (status != "pending").then(|| {
+        json!({
+            "block_height": l1_height + 1,
+            "block_id": l1_block_hash(l1_height + 1),
+            "txid": txid,
+            "wtxid": txid,
+        })
+    });
+    json!({
+        "idx": idx,
+        "l1_range": [
+            { "height": l1_height, "blkid": l1_block_hash(l1_height) },
+            { "height": l1_height + 1, "blkid": l1_block_hash(l1_height + 1) },
+        ],
+        "l2_range": [
+            { "slot": l2_range.0, "blkid": block_hash(l2_range.0) },
+            { "slot": l2_range.1, "blkid": block_hash(l2_range.1) },
+        ],
+        "l1_reference": l1_reference,
+        "confirmation_status": status,
+    })
+}
+
+fn header_json(height: u64) -> Value {
+    json!({
+        "block_idx": height,
+        "timestamp": 1_700_000_000_000u64 + height,
+        "block_id": block_hash(height),
+        "prev_block": block_hash(height.wrapping_sub(1)),
+        "l1_segment_hash": format!("{:064x}", 0),
+        "exec_segment_hash": format!("{:064x}", height),
+        "state_root": format!("{:064x}", 0),
+    })
+}
+
+async fn rpc(
+    State(chain): State<Arc<Mutex<FakeChain>>>,
+    Json(request): Json<Value>,
+) -> Json<Value> {
+    let param = match &request["params"][0] {
+        Value::String(param) => param.clone(),
+        param => param.to_string(),
+    };
+    let chain = chain.lock().unwrap();
+    if chain.failing.contains(&param) {
+        return Json(json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "error": { "code": -32000, "message": "unavailable" },
+        }));
+    }
+    let height = param.parse::<u64>().ok();
+    let result = match request["method"].as_str() {
+        Some("strata_getLatestCheckpointIndex") => {
+            chain.checkpoints.keys().last().map(|idx| json!(idx))
+        }
+        Some("strata_getCheckpointInfo") => {
+            height.and_then(|idx| chain.checkpoints.get(&idx).cloned())
+        }
+        Some("strata_getHeadersAtIdx") => {
+            height.and_then(|height| chain.headers.get(&height).cloned())
+        }
+        Some("strata_getExecUpdateById") => chain.exec_updates.get(&param).cloned(),
+        Some("strata_getBridgeDuties") => height
+            .and_then(|operator_idx| chain.bridge_duties.get(&operator_idx))
+            .map(|duties| {
+                let start = request["params"][1].as_u64().unwrap_or(0) as usize;
+                json!({
+                    "duties": duties.get(start..).unwrap_or_default(),
+                    "start_index": start,
+                    "stop_index": duties.len(),
+                })
+            }),
+        Some("strata_getL1blockHash") => height
+            .and_then(|height| chain.l1_block_hashes.get(&height))
+            .map(|hash| json!(hash)),
+        Some("eth_getBlockByHash") => chain.evm_blocks.get(&param).cloned(),
+        Some("eth_getTransactionByHash") => chain.evm_transactions.get(&param).cloned(),
+        _ => None,
+    };
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn esplora_block(
+    State(chain): State<Arc<Mutex<FakeChain>>>,
+    Path(hash): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let chain = chain.lock().unwrap();
+    match chain.l1_block_times.get(&hash) {
+        Some(timestamp) => Ok(Json(json!({ "id": hash, "timestamp": timestamp }))),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
@@ -0,0 +1,135 @@
+use axum::{extract::MatchedPath, http::Request, middleware::Next, response::Response};
+use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Buckets (seconds) of every latency histogram, from fast queries to slow fullnode calls
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// How often the recorder drains histogram samples when nothing scrapes it
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Install the Prometheus recorder for the whole process, the returned handle renders the
+/// `/metrics` page. Metrics recorded before this are lost.
+pub fn install(token: CancellationToken) -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets(&LATENCY_BUCKETS)?
+        .install_recorder()?;
+    describe();
+
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => return,
+                _ = interval.tick() => upkeep.run_upkeep(),
+            }
+        }
+    });
+    Ok(handle)
+}
+
+/// Recorder of the tests, installed for the whole test process on first use since metrics
+/// are recorded globally
+#[cfg(test)]
+pub fn test_recorder() -> PrometheusHandle {
+    static HANDLE: std::sync::OnceLock<PrometheusHandle> = std::sync::OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            let handle = PrometheusBuilder::new().install_recorder().unwrap();
+            describe();
+            handle
+        })
+        .clone()
+}
+
+fn describe() {
+    describe_counter!(
+        "explorer_fullnode_rpc_requests_total",
+        "JSON-RPC calls made to the fullnode, per method"
+    );
+    describe_counter!(
+        "explorer_fullnode_rpc_errors_total",
+        "JSON-RPC calls to the fullnode that failed, per method"
+    );
+    describe_histogram!(
+        "explorer_fullnode_rpc_duration_seconds",
+        Unit::Seconds,
+        "Latency of the JSON-RPC calls to the fullnode, per method"
+    );
+    describe_counter!(
+        "explorer_checkpoints_ingested_total",
+        "Checkpoints inserted by the indexer"
+    );
+    describe_counter!(
+        "explorer_blocks_ingested_total",
+        "Blocks inserted by the indexer"
+    );
+    describe_histogram!(
+        "explorer_db_query_duration_seconds",
+        Unit::Seconds,
+        "Latency of the database statements, per connection and kind of statement"
+    );
+    describe_counter!(
+        "explorer_db_query_errors_total",
+        "Database statements that failed, per connection and kind of statement"
+    );
+    describe_counter!(
+        "explorer_webhook_deliveries_total",
+        "Webhook delivery attempts, per outcome (delivered or failed)"
+    );
+    describe_gauge!(
+        "explorer_alerts",
+        "Alerts currently raised by the indexer, per kind"
+    );
+    describe_counter!(
+        "explorer_alerts_raised_total",
+        "Alerts raised by the indexer, per kind"
+    );
+    describe_counter!(
+        "explorer_api_requests_total",
+        "API requests served, per route, method and status"
+    );
+    describe_histogram!(
+        "explorer_api_request_duration_seconds",
+        Unit::Seconds,
+        "Latency of the API requests, per route, method and status"
+    );
+    describe_gauge!(
+        "explorer_fullnode_checkpoint_tip",
+        "Latest checkpoint of the fullnode"
+    );
+    describe_gauge!("explorer_local_checkpoint_tip", "Latest checkpoint stored");
+    describe_gauge!("explorer_local_block_tip", "Latest block height stored");
+    describe_gauge!(
+        "explorer_checkpoints",
+        "Checkpoints stored, per confirmation status"
+    );
+}
+
+/// Middleware recording the latency of every request to a known route. The route is the
+/// matched path, e.g. `/api/checkpoint`, so that query strings do not create new series.
+pub async fn track_api_request<B>(request: Request<B>, next: Next<B>) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = request.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("route", route),
+        ("method", method),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!("explorer_api_requests_total", &labels).increment(1);
+    metrics::histogram!("explorer_api_request_duration_seconds", &labels)
+        .record(started.elapsed().as_secs_f64());
+    response
+}
@@ -1 +1,7 @@
 pub mod config;
+#[cfg(test)]
+pub mod fake_fullnode;
+pub mod metrics;
+pub mod supervisor;
+#[cfg(test)]
+pub mod test_repository;
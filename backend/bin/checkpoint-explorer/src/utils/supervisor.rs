@@ -0,0 +1,405 @@
+use database::services::utils::unix_timestamp;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::{JoinError, JoinHandle, JoinSet};
+use tokio::time::{sleep, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Delay before the first restart of a failed task
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+/// Upper bound for the delay between two restarts
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+/// A task that ran at least this long before failing restarts with the initial backoff again
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    Running,
+    /// Failed and waiting for its backoff to elapse before restarting
+    Restarting,
+    /// Stopped on shutdown
+    Stopped,
+}
+
+/// Health of a supervised task
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHealth {
+    pub state: TaskState,
+    /// Number of times the task failed and was restarted
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    /// UNIX timestamp (seconds) of the last failure
+    pub last_failure_at: Option<i64>,
+    /// UNIX timestamp (seconds) at which the current run started
+    pub started_at: i64,
+    /// UNIX timestamp (seconds) of the last time the task reported completing its work,
+    /// see [`report_success`]
+    pub last_success_at: Option<i64>,
+}
+
+/// Shared view of the health of every supervised task, keyed by task name
+#[derive(Debug, Clone, Default)]
+pub struct TaskHealthRegistry {
+    tasks: Arc<RwLock<BTreeMap<&'static str, TaskHealth>>>,
+}
+
+impl TaskHealthRegistry {
+    pub fn snapshot(&self) -> BTreeMap<&'static str, TaskHealth> {
+        self.tasks.read().unwrap().clone()
+    }
+
+    fn started(&self, name: &'static str) {
+        let mut tasks = self.tasks.write().unwrap();
+        let health = tasks.entry(name).or_insert(TaskHealth {
+            state: TaskState::Running,
+            restarts: 0,
+            last_error: None,
+            last_failure_at: None,
+            started_at: 0,
+            last_success_at: None,
+        });
+        health.state = TaskState::Running;
+        health.started_at = unix_timestamp();
+    }
+
+    fn failed(&self, name: &'static str, error: String) {
+        if let Some(health) = self.tasks.write().unwrap().get_mut(name) {
+            health.state = TaskState::Restarting;
+            health.restarts += 1;
+            health.last_error = Some(error);
+            health.last_failure_at = Some(unix_timestamp());
+        }
+    }
+
+    fn succeeded(&self, name: &'static str) {
+        if let Some(health) = self.tasks.write().unwrap().get_mut(name) {
+            health.last_success_at = Some(unix_timestamp());
+        }
+    }
+
+    fn stopped(&self, name: &'static str) {
+        if let Some(health) = self.tasks.write().unwrap().get_mut(name) {
+            health.state = TaskState::Stopped;
+        }
+    }
+
+    fn all_stopped(&self) {
+        for health in self.tasks.write().unwrap().values_mut() {
+            health.state = TaskState::Stopped;
+        }
+    }
+}
+
+/// The supervised task a future runs as, so that it can report its progress
+#[derive(Clone)]
+struct SupervisedTask {
+    name: &'static str,
+    health: TaskHealthRegistry,
+}
+
+tokio::task_local! {
+    static CURRENT_TASK: SupervisedTask;
+}
+
+/// Record that the calling supervised task completed a unit of work, e.g. a fetch pass.
+/// Does nothing outside of a task spawned by [`Supervisor::spawn`], so the same code can run
+/// from the admin commands.
+pub(crate) fn report_success() {
+    let _ = CURRENT_TASK.try_with(|task| task.health.succeeded(task.name));
+}
+
+/// Runs the background tasks of the explorer.
+///
+/// A task that panics or returns before shutdown is restarted with exponential backoff.
+/// All tasks share one cancellation token: on shutdown they are expected to finish the
+/// database write in progress and return, and [`Supervisor::shutdown`] waits for them.
+pub struct Supervisor {
+    token: CancellationToken,
+    health: TaskHealthRegistry,
+    tasks: JoinSet<()>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+            health: TaskHealthRegistry::default(),
+            tasks: JoinSet::new(),
+        }
+    }
+
+    /// Token cancelled when the explorer shuts down
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn health(&self) -> TaskHealthRegistry {
+        self.health.clone()
+    }
+
+    /// Supervise a task. `task` is called again with the shared token on every restart.
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.token.clone();
+        let health = self.health.clone();
+        health.started(name);
+
+        self.tasks.spawn(async move {
+            let mut backoff = INITIAL_RESTART_BACKOFF;
+            loop {
+                health.started(name);
+                let started = Instant::now();
+                // run the task on its own so a panic surfaces here instead of unwinding us
+                let supervised = SupervisedTask {
+                    name,
+                    health: health.clone(),
+                };
+                let mut run = AbortOnDrop(tokio::spawn(
+                    CURRENT_TASK.scope(supervised, task(token.clone())),
+                ));
+                let outcome = (&mut run.0).await;
+
+                if token.is_cancelled() {
+                    if let Err(e) = outcome {
+                        error!("Task {} failed during shutdown: {}", name, describe(e));
+                    }
+                    info!("Task {} stopped", name);
+                    health.stopped(name);
+                    return;
+                }
+
+                let error = match outcome {
+                    Ok(()) => "exited unexpectedly".to_string(),
+                    Err(e) => describe(e),
+                };
+                if started.elapsed() >= HEALTHY_RUN {
+                    backoff = INITIAL_RESTART_BACKOFF;
+                }
+                error!(
+                    "Task {} failed: {}, restarting in {:?}",
+                    name, error, backoff
+                );
+                health.failed(name, error);
+
+                tokio::select! {
+                    _ = token.cancelled() => {
+                        health.stopped(name);
+                        return;
+                    }
+                    _ = sleep(backoff) => {}
+                }
+                backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+            }
+        });
+    }
+
+    /// Cancel every task and wait up to `timeout` for them to finish their current work,
+    /// the tasks still running after that are aborted
+    pub async fn shutdown(mut self, timeout: Duration) {
+        info!("Shutting down background tasks...");
+        self.token.cancel();
+        let drained = tokio::time::timeout(timeout, async {
+            while self.tasks.join_next().await.is_some() {}
+        })
+        .await;
+        match drained {
+            Ok(()) => info!("All background tasks stopped"),
+            Err(_) => {
+                warn!(
+                    "Background tasks did not stop within {:?}, aborting them",
+                    timeout
+                );
+                self.tasks.shutdown().await;
+                self.health.all_stopped();
+            }
+        }
+    }
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resolves once the process is asked to terminate (Ctrl+C or SIGTERM)
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+/// Aborts the task when dropped, so that aborting the supervising task aborts the run it
+/// is waiting for instead of detaching it
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn describe(error: JoinError) -> String {
+    if !error.is_panic() {
+        return error.to_string();
+    }
+    let panic = error.into_panic();
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        format!("panicked: {}", message)
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        format!("panicked: {}", message)
+    } else {
+        "panicked".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    /// Seconds between the successive starts of a task
+    fn gaps(starts: &[Instant]) -> Vec<u64> {
+        starts
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).as_secs())
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restarts_a_panicking_task_with_a_doubling_backoff() {
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        let mut supervisor = Supervisor::new();
+        let health = supervisor.health();
+        let task_starts = starts.clone();
+        supervisor.spawn("flaky", move |_| {
+            let starts = task_starts.clone();
+            async move {
+                starts.lock().unwrap().push(Instant::now());
+                panic!("boom");
+            }
+        });
+
+        sleep(Duration::from_secs(8)).await;
+
+        assert_eq!(gaps(&starts.lock().unwrap()), [1, 2, 4]);
+        let flaky = health.snapshot()["flaky"].clone();
+        assert_eq!(flaky.state, TaskState::Restarting);
+        assert_eq!(flaky.restarts, 4);
+        assert_eq!(flaky.last_error.as_deref(), Some("panicked: boom"));
+        supervisor.shutdown(Duration::from_secs(1)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resets_the_backoff_after_a_healthy_run() {
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        let mut supervisor = Supervisor::new();
+        let task_starts = starts.clone();
+        supervisor.spawn("flaky", move |token| {
+            let starts = task_starts.clone();
+            async move {
+                let run = {
+                    let mut starts = starts.lock().unwrap();
+                    starts.push(Instant::now());
+                    starts.len()
+                };
+                match run {
+                    1..=3 => panic!("boom"),
+                    // fails again after running long enough to count as healthy
+                    4 => sleep(HEALTHY_RUN).await,
+                    _ => token.cancelled().await,
+                }
+            }
+        });
+
+        sleep(Duration::from_secs(80)).await;
+
+        assert_eq!(gaps(&starts.lock().unwrap()), [1, 2, 4, 61]);
+        supervisor.shutdown(Duration::from_secs(1)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drains_the_tasks_on_shutdown_and_marks_them_stopped() {
+        let finished = Arc::new(AtomicU32::new(0));
+        let mut supervisor = Supervisor::new();
+        let health = supervisor.health();
+        let task_finished = finished.clone();
+        supervisor.spawn("indexer", move |token| {
+            let finished = task_finished.clone();
+            async move {
+                token.cancelled().await;
+                // the write in progress when the shutdown starts
+                sleep(Duration::from_millis(500)).await;
+                finished.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        supervisor.spawn("flaky", |_| async { panic!("boom") });
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(health.snapshot()["flaky"].state, TaskState::Restarting);
+
+        supervisor.shutdown(Duration::from_secs(5)).await;
+
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+        for (name, task) in health.snapshot() {
+            assert_eq!(task.state, TaskState::Stopped, "{}", name);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn aborts_the_tasks_still_running_after_the_timeout() {
+        let ticks = Arc::new(AtomicU32::new(0));
+        let mut supervisor = Supervisor::new();
+        let health = supervisor.health();
+        let task_ticks = ticks.clone();
+        supervisor.spawn("stuck", move |_| {
+            let ticks = task_ticks.clone();
+            // ignores the token
+            async move {
+                loop {
+                    sleep(Duration::from_secs(1)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+
+        supervisor.shutdown(Duration::from_millis(2500)).await;
+        let at_shutdown = ticks.load(Ordering::SeqCst);
+        sleep(Duration::from_secs(10)).await;
+
+        assert_eq!(at_shutdown, 2);
+        assert_eq!(ticks.load(Ordering::SeqCst), at_shutdown);
+        assert_eq!(health.snapshot()["stuck"].state, TaskState::Stopped);
+    }
+}
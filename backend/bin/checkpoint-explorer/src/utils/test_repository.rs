@@ -0,0 +1,59 @@
+//! Repositories the service tests run against: the in-memory one and, with the `sqlite`
+//! feature, SQLite in memory
+use database::repository::memory::InMemoryRepository;
+use database::repository::Repository;
+
+/// Repository opened empty for each test
+pub trait TestRepository: Repository + Sized + 'static {
+    async fn open() -> Self;
+}
+
+impl TestRepository for InMemoryRepository {
+    async fn open() -> Self {
+        InMemoryRepository::new()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl TestRepository for database::repository::sql::SqlRepository {
+    async fn open() -> Self {
+        use database::connection::{DatabaseOptions, DatabaseWrapper, LevelFilter};
+
+        let options = DatabaseOptions {
+            sql_log_level: LevelFilter::Off,
+            connect_retries: 0,
+            ..Default::default()
+        };
+        let database = DatabaseWrapper::new("sqlite::memory:", None, &options)
+            .await
+            .unwrap();
+        database.apply_migrations().await.unwrap();
+        database.repository()
+    }
+}
+
+/// Run each `async fn<R: TestRepository>()` of the calling module against every
+/// [`TestRepository`]
+macro_rules! repository_tests {
+    ($($name:ident),* $(,)?) => {
+        mod in_memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name::<database::repository::memory::InMemoryRepository>().await
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name::<database::repository::sql::SqlRepository>().await
+                }
+            )*
+        }
+    };
+}
+pub(crate) use repository_tests;
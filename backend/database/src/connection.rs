@@ -1,28 +1,409 @@
-use sea_orm::{ConnectionTrait, Database, DatabaseConnection, Statement};
+use crate::repository::sql::SqlRepository;
+use migration::{Migrator, MigratorTrait, SchemaManager};
+use sea_orm::{
+    metric, ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend, DbErr,
+    Statement,
+};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+pub use log::LevelFilter;
+
+/// Upper bound for the delay between two connection attempts
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Idle timeout and lifetime used for in-memory SQLite pools, long enough to never expire
+const PINNED_CONNECTION_LIFETIME: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// Connection pool settings applied to every database connection
+#[derive(Debug, Clone)]
+pub struct DatabaseOptions {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    /// Server side statement timeout (PostgreSQL only), `None` keeps the server default
+    pub statement_timeout: Option<Duration>,
+    /// Level at which executed SQL statements are logged, `Off` disables statement logging
+    pub sql_log_level: LevelFilter,
+    /// How many times to retry the initial connection before giving up
+    pub connect_retries: u32,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 1,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(600),
+            statement_timeout: None,
+            sql_log_level: LevelFilter::Info,
+            connect_retries: 10,
+        }
+    }
+}
+
+impl DatabaseOptions {
+    fn connect_options(&self, database_url: &str) -> ConnectOptions {
+        let mut url = database_url.to_string();
+        if let Some(timeout) = self.statement_timeout {
+            if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                url = format!(
+                    "{url}{separator}options[statement_timeout]={}",
+                    timeout.as_millis()
+                );
+            }
+        }
+
+        let in_memory = is_in_memory_sqlite(&url);
+        let file_sqlite = url.starts_with("sqlite:") && !in_memory;
+        let mut options = ConnectOptions::new(url);
+        options
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+            .sqlx_logging(self.sql_log_level != LevelFilter::Off)
+            .sqlx_logging_level(self.sql_log_level);
+
+        // An in-memory SQLite database belongs to the connection that opened it and only
+        // lives as long as that connection, so the pool holds exactly one and never closes it.
+        if in_memory {
+            options
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(PINNED_CONNECTION_LIFETIME)
+                .max_lifetime(PINNED_CONNECTION_LIFETIME);
+        }
+        // SQLite takes one writer at a time and fails a transaction that cannot upgrade its
+        // lock right away, whatever the busy timeout. A single connection makes the writes of
+        // the explorer wait for each other in the pool instead.
+        if file_sqlite {
+            options.max_connections(1).min_connections(1);
+        }
+        options
+    }
+
+    /// Options for a pool of exactly one connection that is never recycled, for state bound
+    /// to a database session such as advisory locks
+    pub(crate) fn pinned_connect_options(&self, database_url: &str) -> ConnectOptions {
+        let mut options = self.connect_options(database_url);
+        options
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(PINNED_CONNECTION_LIFETIME)
+            .max_lifetime(PINNED_CONNECTION_LIFETIME);
+        options
+    }
+}
+
+/// Result of comparing the migrations applied to the database with the ones embedded in
+/// `migration::Migrator`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationStatus {
+    /// Every known migration is applied and nothing else is
+    UpToDate,
+    /// Known migrations that have not been applied yet
+    Pending(Vec<String>),
+    /// Applied migrations this binary does not know about, i.e. the database was migrated
+    /// by a newer version
+    Unknown(Vec<String>),
+}
+
 pub struct DatabaseWrapper {
+    /// Primary database, all writes go here
     pub db: DatabaseConnection,
+    /// Optional read-only replica used for serving API requests
+    read_db: Option<DatabaseConnection>,
 }
 
 impl DatabaseWrapper {
-    /// Create a new database wrapper with the given database URL
-    pub async fn new(database_url: &str) -> Self {
-        let db = Database::connect(database_url)
-            .await
-            .expect("Failed to connect to PostgreSQL {database_url}");
-        Self { db }
+    /// Create a new database wrapper with the given database URL and an optional read replica URL
+    pub async fn new(
+        database_url: &str,
+        read_database_url: Option<&str>,
+        options: &DatabaseOptions,
+    ) -> Result<Self, DbErr> {
+        let mut db = connect_with_retry(
+            "primary",
+            options.connect_options(database_url),
+            options.connect_retries,
+        )
+        .await?;
+        enable_sqlite_wal(&db, database_url).await?;
+        db.set_metric_callback(|info| record_query("primary", info));
+        let read_db = match read_database_url {
+            Some(url) => {
+                let mut read_db = connect_with_retry(
+                    "read replica",
+                    options.connect_options(url),
+                    options.connect_retries,
+                )
+                .await?;
+                enable_sqlite_wal(&read_db, url).await?;
+                read_db.set_metric_callback(|info| record_query("replica", info));
+                Some(read_db)
+            }
+            None => None,
+        };
+        Ok(Self { db, read_db })
+    }
+
+    /// Wrap an already open connection, without a read replica
+    pub fn from_connection(db: DatabaseConnection) -> Self {
+        Self { db, read_db: None }
+    }
+
+    /// Connection to use for read-only queries: the replica if one is configured,
+    /// the primary otherwise
+    pub fn read_db(&self) -> &DatabaseConnection {
+        self.read_db.as_ref().unwrap_or(&self.db)
+    }
+
+    /// Repository over the primary database, used by the indexer
+    pub fn repository(&self) -> SqlRepository {
+        SqlRepository::new(self.db.clone())
     }
-    pub async fn migrations_applied(&self) -> bool {
+
+    /// Repository over the read connection, used by the API
+    pub fn read_repository(&self) -> SqlRepository {
+        SqlRepository::new(self.read_db().clone())
+    }
+
+    /// Check that the primary and the read replica, if any, answer
+    pub async fn ping(&self) -> Result<(), DbErr> {
+        self.db.ping().await?;
+        if let Some(read_db) = &self.read_db {
+            read_db.ping().await?;
+        }
+        Ok(())
+    }
+
+    /// Compare the applied migrations with the ones embedded in this binary
+    pub async fn migration_status(&self) -> Result<MigrationStatus, DbErr> {
+        let known: Vec<String> = Migrator::migrations()
+            .iter()
+            .map(|migration| migration.name().to_string())
+            .collect();
+        let applied = self.applied_migrations().await?;
+
+        let unknown: Vec<String> = applied
+            .iter()
+            .filter(|version| !known.contains(version))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            return Ok(MigrationStatus::Unknown(unknown));
+        }
+
+        let pending: Vec<String> = known
+            .into_iter()
+            .filter(|name| !applied.contains(name))
+            .collect();
+        if !pending.is_empty() {
+            return Ok(MigrationStatus::Pending(pending));
+        }
+        Ok(MigrationStatus::UpToDate)
+    }
+
+    /// Apply all pending migrations embedded in this binary
+    pub async fn apply_migrations(&self) -> Result<(), DbErr> {
+        Migrator::up(&self.db, None).await
+    }
+
+    async fn applied_migrations(&self) -> Result<HashSet<String>, DbErr> {
+        let table = Migrator::migration_table_name().to_string();
+        if !SchemaManager::new(&self.db).has_table(&table).await? {
+            return Ok(HashSet::new());
+        }
+
         let stmt = Statement::from_string(
-            sea_orm::DatabaseBackend::Postgres,
-            "SELECT COUNT(*) as count FROM seaql_migrations",
+            self.db.get_database_backend(),
+            format!("SELECT version FROM {table}"),
         );
+        self.db
+            .query_all(stmt)
+            .await?
+            .into_iter()
+            .map(|row| row.try_get::<String>("", "version"))
+            .collect()
+    }
+}
+
+/// Record the latency of every statement, labelled by connection and kind of statement so
+/// that the number of series stays small
+fn record_query(connection: &'static str, info: &metric::Info<'_>) {
+    let operation = match info.statement.sql.split_whitespace().next() {
+        Some(keyword) if keyword.eq_ignore_ascii_case("select") => "select",
+        Some(keyword) if keyword.eq_ignore_ascii_case("insert") => "insert",
+        Some(keyword) if keyword.eq_ignore_ascii_case("update") => "update",
+        Some(keyword) if keyword.eq_ignore_ascii_case("delete") => "delete",
+        _ => "other",
+    };
+    metrics::histogram!(
+        "explorer_db_query_duration_seconds",
+        "connection" => connection,
+        "operation" => operation
+    )
+    .record(info.elapsed.as_secs_f64());
+    if info.failed {
+        metrics::counter!(
+            "explorer_db_query_errors_total",
+            "connection" => connection,
+            "operation" => operation
+        )
+        .increment(1);
+    }
+}
+
+/// Switch a file-backed SQLite database to write-ahead logging, so that other processes such
+/// as the maintenance commands read while the explorer writes. The mode is stored in the
+/// file, and writers of other processes wait for the lock up to the 5 s busy timeout sqlx
+/// sets on every connection.
+async fn enable_sqlite_wal(db: &DatabaseConnection, url: &str) -> Result<(), DbErr> {
+    if db.get_database_backend() != DbBackend::Sqlite || is_in_memory_sqlite(url) {
+        return Ok(());
+    }
+    db.execute_unprepared("PRAGMA journal_mode=WAL").await?;
+    Ok(())
+}
+
+pub(crate) fn is_in_memory_sqlite(url: &str) -> bool {
+    url.starts_with("sqlite:") && (url.contains(":memory:") || url.contains("mode=memory"))
+}
 
-        match self.db.query_one(stmt).await {
-            Ok(Some(row)) => {
-                let count: i64 = row.try_get("", "count").unwrap_or(0);
-                count > 0
+/// Connect to the database, retrying with exponential backoff instead of failing on the
+/// first error so the explorer can start before the database is reachable
+pub(crate) async fn connect_with_retry(
+    name: &str,
+    connect_options: ConnectOptions,
+    retries: u32,
+) -> Result<DatabaseConnection, DbErr> {
+    let mut backoff = Duration::from_secs(1);
+    let mut attempt = 0;
+    loop {
+        match Database::connect(connect_options.clone()).await {
+            Ok(db) => {
+                info!("Connected to {} database", name);
+                return Ok(db);
+            }
+            Err(err) if attempt < retries => {
+                attempt += 1;
+                warn!(
+                    "Failed to connect to {} database (attempt {}/{}): {:?}, retrying in {:?}",
+                    name, attempt, retries, err, backoff
+                );
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
             }
-            _ => false,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::repository::sql::SqlRepository;
+    use crate::repository::{BlockRepository, CheckpointRepository, IntegrityRepository};
+    use crate::testing::{block, checkpoint, Events, TestRepository};
+    use model::pgu64::PgU64;
+
+    #[tokio::test]
+    async fn applies_every_migration_to_sqlite() {
+        let options = DatabaseOptions {
+            connect_retries: 0,
+            ..Default::default()
+        };
+        let database = DatabaseWrapper::new("sqlite::memory:", None, &options)
+            .await
+            .unwrap();
+        let pending = match database.migration_status().await.unwrap() {
+            MigrationStatus::Pending(pending) => pending,
+            status => panic!("expected pending migrations, got {:?}", status),
+        };
+        assert_eq!(pending.len(), Migrator::migrations().len());
+
+        database.apply_migrations().await.unwrap();
+        assert_eq!(
+            database.migration_status().await.unwrap(),
+            MigrationStatus::UpToDate
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_migrations_missing_from_or_unknown_to_the_binary() {
+        let options = DatabaseOptions {
+            connect_retries: 0,
+            ..Default::default()
+        };
+        let database = DatabaseWrapper::new("sqlite::memory:", None, &options)
+            .await
+            .unwrap();
+        database.apply_migrations().await.unwrap();
+        let last = Migrator::migrations().last().unwrap().name().to_string();
+
+        database
+            .db
+            .execute_unprepared(&format!(
+                "DELETE FROM seaql_migrations WHERE version = '{last}'"
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            database.migration_status().await.unwrap(),
+            MigrationStatus::Pending(vec![last.clone()])
+        );
+
+        // a newer binary migrated the database, whatever else is missing
+        database
+            .db
+            .execute_unprepared(
+                "INSERT INTO seaql_migrations (version, applied_at) \
+                 VALUES ('m29991231_000000_from_a_newer_binary', 0)",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            database.migration_status().await.unwrap(),
+            MigrationStatus::Unknown(vec!["m29991231_000000_from_a_newer_binary".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn stores_checkpoints_and_blocks_in_sqlite() {
+        let repository = SqlRepository::open().await;
+        for (idx, l2_range) in [(0, (0, 2)), (1, (3, 4))] {
+            repository
+                .insert_checkpoint(
+                    checkpoint(idx, l2_range, "finalized", "aa"),
+                    &Events::default(),
+                )
+                .await;
+        }
+        for height in 0..=4 {
+            let checkpoint_idx = if height <= 2 { 0 } else { 1 };
+            repository
+                .insert_block(block(height), PgU64(checkpoint_idx).to_i64())
+                .await
+                .unwrap();
         }
+
+        let stored = repository.get_checkpoint_by_idx(PgU64(1).to_i64()).await;
+        assert_eq!(stored.unwrap().unwrap().l2_range, (3, 4));
+        let blocks = repository
+            .get_blocks_in_range(PgU64(0).to_i64(), PgU64(4).to_i64())
+            .await
+            .unwrap();
+        assert_eq!(blocks.len(), 5);
+        assert_eq!(
+            repository.get_latest_block_index().await,
+            Some(PgU64(4).to_i64())
+        );
+        assert!(repository.get_integrity_report().await.unwrap().is_clean());
     }
 }
@@ -0,0 +1,221 @@
+//! Leader election between explorer replicas sharing one database.
+//!
+//! Only the replica holding the lock runs the indexer. On PostgreSQL it is a session-level
+//! advisory lock, released by the server as soon as that replica's session ends. A
+//! file-backed SQLite database can be opened by several processes, so they take an exclusive
+//! lock on a `<database>-leader` file next to it instead, released by the OS when the holder
+//! exits. An in-memory SQLite database is private to its process and its lock is granted
+//! right away.
+use crate::connection::{connect_with_retry, is_in_memory_sqlite, DatabaseOptions};
+use async_trait::async_trait;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Advisory lock key reserved for the indexer ("ckpt_idx" in ASCII)
+pub const INDEXER_LOCK_KEY: i64 = 0x636b_7074_5f69_6478;
+
+/// Lock electing the single replica that runs the indexer
+#[async_trait]
+pub trait LeaderElection: Send + Sync {
+    /// Try to take the lock without waiting, `true` if this replica holds it afterwards
+    async fn try_acquire(&self) -> Result<bool, DbErr>;
+
+    /// Whether this replica still holds the lock
+    async fn is_held(&self) -> Result<bool, DbErr>;
+}
+
+pub struct LeaderLock {
+    holder: Holder,
+    key: i64,
+}
+
+enum Holder {
+    /// Dedicated single-connection pool holding the advisory lock
+    Postgres(DatabaseConnection),
+    /// Lock file of a SQLite database, open while the lock is held
+    File {
+        path: PathBuf,
+        file: Mutex<Option<File>>,
+    },
+    /// Nothing else can reach the database
+    Private,
+}
+
+impl LeaderLock {
+    /// Open the session the lock will be held on
+    pub async fn connect(
+        database_url: &str,
+        options: &DatabaseOptions,
+        key: i64,
+    ) -> Result<Self, DbErr> {
+        let holder = if database_url.starts_with("postgres") {
+            let db = connect_with_retry(
+                "leader lock",
+                options.pinned_connect_options(database_url),
+                options.connect_retries,
+            )
+            .await?;
+            Holder::Postgres(db)
+        } else if let Some(database) = sqlite_file(database_url) {
+            let mut path = database.into_os_string();
+            path.push("-leader");
+            Holder::File {
+                path: path.into(),
+                file: Mutex::new(None),
+            }
+        } else {
+            info!("Leader election is disabled for in-memory databases");
+            Holder::Private
+        };
+        Ok(Self { holder, key })
+    }
+
+    /// Try to take the lock without waiting, `true` if this session holds it afterwards
+    pub async fn try_acquire(&self) -> Result<bool, DbErr> {
+        match &self.holder {
+            Holder::Postgres(db) => {
+                self.query_bool(db, "SELECT pg_try_advisory_lock($1) AS held")
+                    .await
+            }
+            Holder::File { path, file } => {
+                let mut file = file.lock().unwrap();
+                if file.is_some() {
+                    return Ok(true);
+                }
+                let lock_file = OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(path)
+                    .map_err(|e| lock_file_error(path, e))?;
+                match lock_file.try_lock() {
+                    Ok(()) => {
+                        *file = Some(lock_file);
+                        Ok(true)
+                    }
+                    Err(TryLockError::WouldBlock) => Ok(false),
+                    Err(TryLockError::Error(e)) => Err(lock_file_error(path, e)),
+                }
+            }
+            Holder::Private => Ok(true),
+        }
+    }
+
+    /// Whether this session still holds the lock. It is lost when the connection drops,
+    /// for example when the database restarts.
+    pub async fn is_held(&self) -> Result<bool, DbErr> {
+        let db = match &self.holder {
+            Holder::Postgres(db) => db,
+            Holder::File { file, .. } => return Ok(file.lock().unwrap().is_some()),
+            Holder::Private => return Ok(true),
+        };
+        // a bigint key is stored as its high and low 32 bits with objsubid 1
+        self.query_bool(
+            db,
+            "SELECT EXISTS (SELECT 1 FROM pg_locks WHERE locktype = 'advisory' \
+             AND pid = pg_backend_pid() AND granted AND objsubid = 1 \
+             AND ((classid::bigint << 32) | objid::bigint) = $1) AS held",
+        )
+        .await
+    }
+
+    async fn query_bool(&self, db: &DatabaseConnection, sql: &str) -> Result<bool, DbErr> {
+        let stmt = Statement::from_sql_and_values(DbBackend::Postgres, sql, [self.key.into()]);
+        match db.query_one(stmt).await? {
+            Some(row) => row.try_get("", "held"),
+            None => Ok(false),
+        }
+    }
+}
+
+#[async_trait]
+impl LeaderElection for LeaderLock {
+    async fn try_acquire(&self) -> Result<bool, DbErr> {
+        LeaderLock::try_acquire(self).await
+    }
+
+    async fn is_held(&self) -> Result<bool, DbErr> {
+        LeaderLock::is_held(self).await
+    }
+}
+
+/// Path of a file-backed SQLite database, `None` for other databases
+fn sqlite_file(database_url: &str) -> Option<PathBuf> {
+    if is_in_memory_sqlite(database_url) {
+        return None;
+    }
+    let path = database_url.strip_prefix("sqlite:")?;
+    let path = path.strip_prefix("//").unwrap_or(path);
+    let path = path.split('?').next().unwrap_or(path);
+    Some(PathBuf::from(path))
+}
+
+fn lock_file_error(path: &std::path::Path, error: std::io::Error) -> DbErr {
+    DbErr::Custom(format!(
+        "Failed to lock the leader file {}: {}",
+        path.display(),
+        error
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_file_of_a_sqlite_database() {
+        let cases = [
+            ("sqlite://checkpoints.db?mode=rwc", Some("checkpoints.db")),
+            ("sqlite:checkpoints.db", Some("checkpoints.db")),
+            (
+                "sqlite:///var/lib/explorer.db",
+                Some("/var/lib/explorer.db"),
+            ),
+            ("sqlite::memory:", None),
+            ("sqlite://shared?mode=memory&cache=shared", None),
+            ("postgres://localhost/explorer", None),
+        ];
+        for (url, path) in cases {
+            assert_eq!(sqlite_file(url), path.map(PathBuf::from), "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn one_process_at_a_time_holds_the_lock_of_a_sqlite_file() {
+        let database = std::env::temp_dir().join(format!("leader-{}.db", std::process::id()));
+        let url = format!("sqlite://{}?mode=rwc", database.display());
+        let options = DatabaseOptions::default();
+        let first = LeaderLock::connect(&url, &options, INDEXER_LOCK_KEY)
+            .await
+            .unwrap();
+        let second = LeaderLock::connect(&url, &options, INDEXER_LOCK_KEY)
+            .await
+            .unwrap();
+
+        assert!(first.try_acquire().await.unwrap());
+        assert!(first.try_acquire().await.unwrap());
+        assert!(first.is_held().await.unwrap());
+        assert!(!second.try_acquire().await.unwrap());
+        assert!(!second.is_held().await.unwrap());
+
+        // the lock is released with its holder, e.g. when the process exits
+        drop(first);
+        assert!(second.try_acquire().await.unwrap());
+        assert!(second.is_held().await.unwrap());
+
+        drop(second);
+        let _ = std::fs::remove_file(format!("{}-leader", database.display()));
+    }
+
+    #[tokio::test]
+    async fn in_memory_databases_are_not_shared() {
+        let lock = LeaderLock::connect("sqlite::memory:", &DatabaseOptions::default(), 1)
+            .await
+            .unwrap();
+        assert!(lock.try_acquire().await.unwrap());
+        assert!(lock.is_held().await.unwrap());
+    }
+}
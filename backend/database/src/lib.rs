@@ -1,3 +1,7 @@
 pub mod connection;
+pub mod leader;
+pub mod repository;
 // pub mod _db;
 pub mod services;
+#[cfg(test)]
+mod testing;
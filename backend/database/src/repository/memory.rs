@@ -0,0 +1,1178 @@
+use super::{
+    BlockFetchJobRepository, BlockRepository, BridgeIntentRepository, CheckpointEvents,
+    CheckpointReconciliation, CheckpointRepository, EvmBlockRepository, IntegrityRepository,
+    L1BlockRepository, MaintenanceRepository, PurgeSummary, SyncStateRepository,
+    WebhookDeliveryRepository,
+};
+use crate::services::{
+    block_service::block_discontinuity,
+    checkpoint_service::{classify_change, CheckpointChange},
+    pagination::{page_offset, PaginatedData},
+    utils::{resolve_order, unix_timestamp},
+};
+use async_trait::async_trait;
+use model::block::{self, ActiveModel as BlockActiveModel, RpcBlockHeader};
+use model::block_fetch_job::{self, retry_delay, BlockFetchJobCounts, JobState};
+use model::bridge_duty;
+use model::bridge_intent;
+use model::checkpoint::{self, RpcCheckpointInfo, RpcCheckpointInfoCheckpointExp};
+use model::evm_block;
+use model::integrity::{BlockCountMismatch, IntegrityReport, MissingRange};
+use model::l1_block::{self, IncompleteL1Range};
+use model::pgu64::PgU64;
+use model::reorg_event::{self, RpcReorgEvent};
+use model::sync_state::{self, IndexRange, SyncCursor};
+use model::webhook_delivery::{self, DeliveryState, NewWebhookEvent, WebhookDeliveryCounts};
+use sea_orm::{DbErr, Order, Set, TryIntoModel};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::sync::{Mutex, MutexGuard};
+use tracing::{error, info, warn};
+
+#[derive(Default)]
+struct State {
+    checkpoints: BTreeMap<i64, checkpoint::Model>,
+    /// Blocks keyed by height
+    blocks: BTreeMap<i64, block::Model>,
+    /// L1 blocks keyed by height
+    l1_blocks: BTreeMap<i64, l1_block::Model>,
+    /// EVM blocks keyed by L2 height
+    evm_blocks: BTreeMap<i64, evm_block::Model>,
+    /// Bridge intents keyed by L2 height, kind and position
+    bridge_intents: BTreeMap<(i64, String, i32), bridge_intent::Model>,
+    /// Bridge duties keyed by operator, kind and outpoint
+    bridge_duties: BTreeMap<(i32, String, String), bridge_duty::Model>,
+    /// `start_index` of the next duty listing of each operator
+    bridge_duty_cursors: BTreeMap<i32, i64>,
+    reorg_events: Vec<reorg_event::Model>,
+    sync_state: BTreeMap<&'static str, sync_state::Model>,
+    /// Block fetch jobs keyed by checkpoint
+    block_fetch_jobs: BTreeMap<i64, block_fetch_job::Model>,
+    /// Webhook deliveries keyed by id
+    webhook_deliveries: BTreeMap<i64, webhook_delivery::Model>,
+}
+
+impl State {
+    /// Queue one delivery of each of `events` per URL, all due now
+    fn enqueue_deliveries(&mut self, events: Vec<NewWebhookEvent>, urls: &[String]) {
+        let now = unix_timestamp();
+        for event in events {
+            for url in urls {
+                let id = self
+                    .webhook_deliveries
+                    .last_key_value()
+                    .map_or(1, |(id, _)| id + 1);
+                self.webhook_deliveries.insert(
+                    id,
+                    webhook_delivery::Model {
+                        id,
+                        event_id: event.event_id.clone(),
+                        event_type: event.event_type.name().to_string(),
+                        checkpoint_idx: event.checkpoint_idx,
+                        url: url.clone(),
+                        payload: event.payload.clone(),
+                        state: DeliveryState::Pending.state().to_string(),
+                        attempts: 0,
+                        last_error: None,
+                        next_attempt_at: now,
+                        created_at: now,
+                        delivered_at: None,
+                    },
+                );
+            }
+        }
+    }
+
+    fn set_cursor(&mut self, cursor: SyncCursor, value: i64) {
+        self.sync_state.insert(
+            cursor.name(),
+            sync_state::Model {
+                name: cursor.name().to_string(),
+                value,
+                updated_at: unix_timestamp(),
+            },
+        );
+    }
+
+    fn advance_cursor(&mut self, cursor: SyncCursor, value: i64) {
+        let current = self.sync_state.get(cursor.name()).map(|state| state.value);
+        if current.is_none_or(|current| current < value) {
+            self.set_cursor(cursor, value);
+        }
+    }
+
+    /// First checkpoint of the index, checkpoint 0 unless another start was declared
+    fn index_start_checkpoint(&self) -> i64 {
+        self.sync_state
+            .get(SyncCursor::IndexStart.name())
+            .map_or(i64::MIN, |state| state.value)
+    }
+
+    /// First block height of the index, block 0 while its first checkpoint is not stored
+    fn index_start_height(&self) -> i64 {
+        self.checkpoints
+            .get(&self.index_start_checkpoint())
+            .map_or(i64::MIN, |checkpoint| checkpoint.l2_start)
+    }
+
+    /// Move the ingestion cursors back to the highest checkpoint and block left
+    fn rewind_cursors(&mut self) {
+        let last_checkpoint = self.checkpoints.keys().next_back().copied();
+        let last_block = self.blocks.keys().next_back().copied();
+        for (cursor, value) in [
+            (SyncCursor::CheckpointIngested, last_checkpoint),
+            (SyncCursor::CheckpointReconciled, last_checkpoint),
+            (SyncCursor::BlockIngested, last_block),
+            (SyncCursor::BridgeScanned, last_block),
+        ] {
+            let Some(current) = self.sync_state.get(cursor.name()).map(|state| state.value) else {
+                continue;
+            };
+            match value {
+                None => {
+                    self.sync_state.remove(cursor.name());
+                }
+                Some(value) if current > value => self.set_cursor(cursor, value),
+                Some(_) => (),
+            }
+        }
+    }
+
+    /// Delete the bridge intents of the blocks from height `from` to `to` included
+    fn delete_bridge_intents(&mut self, from: i64, to: i64) {
+        self.bridge_intents
+            .retain(|_, intent| intent.l2_height < from || intent.l2_height > to);
+    }
+
+    /// Delete the bridge intents of the blocks of `checkpoint` and move the bridge cursor
+    /// before them, see [`Self::rewind_cursors`] which only moves it further back
+    fn purge_bridge_intents(&mut self, checkpoint: &checkpoint::Model) {
+        self.delete_bridge_intents(checkpoint.l2_start, checkpoint.l2_end);
+        let Some(current) = self
+            .sync_state
+            .get(SyncCursor::BridgeScanned.name())
+            .map(|state| state.value)
+        else {
+            return;
+        };
+        match checkpoint.l2_start.checked_sub(1) {
+            None => {
+                self.sync_state.remove(SyncCursor::BridgeScanned.name());
+            }
+            Some(value) if current > value => self.set_cursor(SyncCursor::BridgeScanned, value),
+            Some(_) => (),
+        }
+    }
+
+    fn set_job_state(&mut self, idx: i64, job_state: JobState) {
+        if let Some(job) = self.block_fetch_jobs.get_mut(&idx) {
+            job.state = job_state.state().to_string();
+            job.updated_at = unix_timestamp();
+        }
+    }
+
+    fn can_insert_block(&self, height: i64) -> bool {
+        self.blocks.is_empty()
+            || height == self.index_start_height()
+            || self.blocks.contains_key(&(height - 1))
+    }
+}
+
+/// Thread-safe repository keeping everything in memory, with the same continuity rules as
+/// [`super::sql::SqlRepository`]
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: Mutex<State>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // a panic while holding the lock cannot leave the maps half-updated
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Ranges missing from the ascending `keys`, from `first` (the start of the index) up to the
+/// highest key
+fn missing_ranges(keys: impl Iterator<Item = i64>, first: i64) -> Vec<MissingRange> {
+    let mut ranges = Vec::new();
+    let mut expected = first;
+    for key in keys {
+        if key > expected {
+            ranges.push(MissingRange {
+                start: PgU64::i64_to_u64(expected),
+                end: PgU64::i64_to_u64(key - 1),
+            });
+        }
+        expected = key.saturating_add(1);
+    }
+    ranges
+}
+
+fn paginate<T: Clone>(
+    items: &[T],
+    current_page: u64,
+    page_size: u64,
+    absolute_first_page: u64,
+) -> Result<PaginatedData<T>, DbErr> {
+    let total_pages = (items.len() as f64 / page_size as f64).ceil() as u64;
+    let offset = page_offset(current_page, page_size, absolute_first_page)?;
+    let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+    Ok(PaginatedData {
+        current_page,
+        total_pages,
+        absolute_first_page,
+        items: items
+            .iter()
+            .skip(offset)
+            .take(page_size as usize)
+            .cloned()
+            .collect(),
+    })
+}
+
+#[async_trait]
+impl CheckpointRepository for InMemoryRepository {
+    async fn checkpoint_exists(&self, idx: i64) -> bool {
+        self.state().checkpoints.contains_key(&idx)
+    }
+
+    async fn insert_checkpoint(
+        &self,
+        checkpoint: RpcCheckpointInfo,
+        events: &dyn CheckpointEvents,
+    ) {
+        let idx = PgU64(checkpoint.idx).to_i64();
+        let mut state = self.state();
+
+        let index_start = state.index_start_checkpoint();
+        if idx < index_start {
+            error!(
+                "Cannot insert checkpoint with idx {}: the index starts at checkpoint {}",
+                checkpoint.idx,
+                PgU64::i64_to_u64(index_start)
+            );
+            return;
+        }
+        if idx > index_start && !state.checkpoints.contains_key(&(idx - 1)) {
+            error!(
+                "Cannot insert checkpoint with idx {}: previous checkpoint with idx {} does not exist",
+                checkpoint.idx,
+                checkpoint.idx - 1
+            );
+            return;
+        }
+        if state.checkpoints.contains_key(&idx) {
+            error!("Checkpoint with idx {} already exists", checkpoint.idx);
+            return;
+        }
+
+        let mut active_model: checkpoint::ActiveModel = checkpoint.into();
+        let now = unix_timestamp();
+        active_model.status_updated_at = Set(Some(now));
+        active_model.first_seen_at = Set(Some(now));
+        match active_model.try_into_model() {
+            Ok(model) => {
+                if !events.urls().is_empty() {
+                    state.enqueue_deliveries(events.created(&model), events.urls());
+                }
+                state.checkpoints.insert(idx, model);
+                state.advance_cursor(SyncCursor::CheckpointIngested, idx);
+                info!(
+                    "Checkpoint with idx {} inserted successfully",
+                    PgU64::i64_to_u64(idx)
+                );
+            }
+            Err(err) => error!(
+                "Error inserting checkpoint with idx {}: {:?}",
+                PgU64::i64_to_u64(idx),
+                err
+            ),
+        }
+    }
+
+    async fn get_checkpoint_by_idx(
+        &self,
+        idx: i64,
+    ) -> Result<Option<RpcCheckpointInfoCheckpointExp>, DbErr> {
+        Ok(self.state().checkpoints.get(&idx).cloned().map(Into::into))
+    }
+
+    async fn get_checkpoint_idx_by_block_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<i64>, DbErr> {
+        Ok(self
+            .state()
+            .blocks
+            .values()
+            .find(|block| block.block_hash == block_hash)
+            .map(|block| block.checkpoint_idx))
+    }
+
+    async fn get_checkpoint_idx_by_block_height(
+        &self,
+        block_height: i64,
+    ) -> Result<Option<i64>, DbErr> {
+        Ok(self
+            .state()
+            .blocks
+            .get(&block_height)
+            .map(|block| block.checkpoint_idx))
+    }
+
+    async fn get_checkpoint_idx_by_l2_height(&self, height: i64) -> Result<Option<i64>, DbErr> {
+        Ok(self
+            .state()
+            .checkpoints
+            .values()
+            .find(|checkpoint| checkpoint.l2_start <= height && height <= checkpoint.l2_end)
+            .map(|checkpoint| checkpoint.idx))
+    }
+
+    async fn get_paginated_checkpoints(
+        &self,
+        current_page: u64,
+        page_size: u64,
+        absolute_first_page: u64,
+        order: Option<&str>,
+    ) -> Result<PaginatedData<RpcCheckpointInfoCheckpointExp>, DbErr> {
+        let state = self.state();
+        let mut checkpoints: Vec<_> = state.checkpoints.values().cloned().collect();
+        if resolve_order(order) == Order::Desc {
+            checkpoints.reverse();
+        }
+        let page = paginate(&checkpoints, current_page, page_size, absolute_first_page)?;
+        Ok(PaginatedData {
+            current_page: page.current_page,
+            total_pages: page.total_pages,
+            absolute_first_page: page.absolute_first_page,
+            items: page.items.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    async fn get_total_checkpoint_count(&self) -> u64 {
+        self.state().checkpoints.len() as u64
+    }
+
+    async fn get_checkpoint_counts_by_status(&self) -> Result<Vec<(String, u64)>, DbErr> {
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for checkpoint in self.state().checkpoints.values() {
+            *counts.entry(checkpoint.status.clone()).or_default() += 1;
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn get_checkpoints_in_range(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr> {
+        Ok(self
+            .state()
+            .checkpoints
+            .range(from..=to)
+            .map(|(_, checkpoint)| checkpoint.clone())
+            .collect())
+    }
+
+    async fn get_latest_checkpoint_index(&self) -> Option<i64> {
+        self.state().checkpoints.keys().next_back().copied()
+    }
+
+    async fn get_checkpoint_idxs_by_status(&self, statuses: &[&str]) -> Result<Vec<i64>, DbErr> {
+        Ok(self
+            .state()
+            .checkpoints
+            .values()
+            .filter(|checkpoint| statuses.contains(&checkpoint.status.as_str()))
+            .map(|checkpoint| checkpoint.idx)
+            .collect())
+    }
+
+    async fn get_stalled_checkpoints(
+        &self,
+        statuses: &[&str],
+        entered_before: i64,
+        limit: u64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr> {
+        let mut stalled: Vec<checkpoint::Model> = self
+            .state()
+            .checkpoints
+            .values()
+            .filter(|checkpoint| statuses.contains(&checkpoint.status.as_str()))
+            .filter(|checkpoint| {
+                checkpoint
+                    .status_updated_at
+                    .is_some_and(|entered_at| entered_at < entered_before)
+            })
+            .cloned()
+            .collect();
+        stalled.sort_by_key(|checkpoint| (checkpoint.status_updated_at, checkpoint.idx));
+        stalled.truncate(limit as usize);
+        Ok(stalled)
+    }
+
+    async fn set_checkpoint_confirmed_at(
+        &self,
+        checkpoint_idx: i64,
+        txid: &str,
+        confirmed_at: i64,
+    ) -> Result<bool, DbErr> {
+        let mut state = self.state();
+        match state.checkpoints.get_mut(&checkpoint_idx) {
+            Some(checkpoint)
+                if checkpoint.checkpoint_txid == txid && checkpoint.confirmed_at.is_some() =>
+            {
+                checkpoint.confirmed_at = Some(confirmed_at);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn get_checkpoint_lifecycles_since(
+        &self,
+        since: i64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr> {
+        Ok(self
+            .state()
+            .checkpoints
+            .values()
+            .filter(|checkpoint| {
+                checkpoint.confirmed_at.is_some_and(|at| at >= since)
+                    || checkpoint.finalized_at.is_some_and(|at| at >= since)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn reconcile_checkpoint(
+        &self,
+        checkpoint_idx: i64,
+        checkpoint_from_rpc: RpcCheckpointInfo,
+        events: &dyn CheckpointEvents,
+    ) -> Result<CheckpointReconciliation, DbErr> {
+        let updated: checkpoint::ActiveModel = checkpoint_from_rpc.into();
+        let new_status = updated.status.unwrap();
+        let new_txid = updated.checkpoint_txid.unwrap();
+
+        let mut state = self.state();
+        state.set_cursor(SyncCursor::CheckpointReconciled, checkpoint_idx);
+        let State {
+            checkpoints,
+            reorg_events,
+            ..
+        } = &mut *state;
+        let Some(checkpoint) = checkpoints.get_mut(&checkpoint_idx) else {
+            return Err(DbErr::RecordNotFound(format!(
+                "Checkpoint with idx {} not found",
+                PgU64::i64_to_u64(checkpoint_idx)
+            )));
+        };
+        let change = classify_change(checkpoint, &new_status, &new_txid);
+        match change {
+            CheckpointChange::Unchanged => return Ok(CheckpointReconciliation::Unchanged),
+            CheckpointChange::Rejected { from, to } => {
+                warn!(
+                    "Ignoring invalid status transition {} -> {} for checkpoint {}",
+                    from,
+                    to,
+                    PgU64::i64_to_u64(checkpoint_idx)
+                );
+                return Ok(CheckpointReconciliation::Rejected { from, to });
+            }
+            CheckpointChange::Updated | CheckpointChange::Reorged => (),
+        }
+
+        let reorg_event = (change == CheckpointChange::Reorged).then(|| {
+            let event = reorg_event::Model {
+                id: reorg_events.len() as i64 + 1,
+                checkpoint_idx,
+                old_status: checkpoint.status.clone(),
+                new_status: new_status.clone(),
+                old_txid: checkpoint.checkpoint_txid.clone(),
+                new_txid: new_txid.clone(),
+                detected_at: unix_timestamp(),
+            };
+            reorg_events.push(event.clone());
+            event
+        });
+        let before = checkpoint.clone();
+        let now = unix_timestamp();
+        if checkpoint.status != new_status {
+            checkpoint.status_updated_at = Some(now);
+        }
+        (checkpoint.confirmed_at, checkpoint.finalized_at) =
+            before.lifecycle_after(&new_status, &new_txid, now);
+        checkpoint.status = new_status;
+        checkpoint.checkpoint_txid = new_txid;
+        let after = checkpoint.clone();
+
+        let reconciliation = match reorg_event {
+            Some(event) => {
+                warn!(
+                    "Reorg detected for checkpoint {}: status {} -> {}, txid {} -> {}",
+                    PgU64::i64_to_u64(checkpoint_idx),
+                    event.old_status,
+                    event.new_status,
+                    event.old_txid,
+                    event.new_txid
+                );
+                CheckpointReconciliation::Reorged(event)
+            }
+            None => CheckpointReconciliation::Updated { before, after },
+        };
+        if !events.urls().is_empty() {
+            state.enqueue_deliveries(events.reconciled(&reconciliation), events.urls());
+        }
+        Ok(reconciliation)
+    }
+
+    async fn get_paginated_reorg_events(
+        &self,
+        current_page: u64,
+        page_size: u64,
+    ) -> Result<PaginatedData<RpcReorgEvent>, DbErr> {
+        let state = self.state();
+        let events: Vec<RpcReorgEvent> = state
+            .reorg_events
+            .iter()
+            .rev()
+            .cloned()
+            .map(Into::into)
+            .collect();
+        paginate(&events, current_page, page_size, 1)
+    }
+}
+
+#[async_trait]
+impl BlockRepository for InMemoryRepository {
+    async fn insert_block(
+        &self,
+        rpc_block_header: RpcBlockHeader,
+        checkpoint_idx: i64,
+    ) -> Result<(), DbErr> {
+        let mut active_model: BlockActiveModel = rpc_block_header.into();
+        active_model.checkpoint_idx = Set(checkpoint_idx);
+        let block = active_model.try_into_model()?;
+
+        let mut state = self.state();
+        if state.blocks.contains_key(&block.height) {
+            tracing::debug!(
+                "Block already exists, height={}",
+                PgU64::i64_to_u64(block.height)
+            );
+            return Ok(());
+        }
+        // ensure that blocks exist incrementally and continuously
+        if !state.can_insert_block(block.height) {
+            return Err(block_discontinuity(block.height));
+        }
+        tracing::debug!(
+            "Block inserted & indexed successfully: height={}, block_hash={}",
+            PgU64::i64_to_u64(block.height),
+            block.block_hash
+        );
+        state.advance_cursor(SyncCursor::BlockIngested, block.height);
+        state.blocks.insert(block.height, block);
+        Ok(())
+    }
+
+    async fn get_latest_block_index(&self) -> Option<i64> {
+        self.state().blocks.keys().next_back().copied()
+    }
+
+    async fn can_insert_block(&self, height: i64) -> bool {
+        self.state().can_insert_block(height)
+    }
+
+    async fn get_blocks_in_range(&self, from: i64, to: i64) -> Result<Vec<block::Model>, DbErr> {
+        Ok(self
+            .state()
+            .blocks
+            .range(from..=to)
+            .map(|(_, block)| block.clone())
+            .collect())
+    }
+
+    async fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<block::Model>, DbErr> {
+        Ok(self
+            .state()
+            .blocks
+            .values()
+            .find(|block| block.block_hash == block_hash)
+            .cloned())
+    }
+}
+
+#[async_trait]
+impl L1BlockRepository for InMemoryRepository {
+    async fn insert_l1_blocks(&self, blocks: Vec<l1_block::Model>) -> Result<u64, DbErr> {
+        let mut state = self.state();
+        let mut inserted = 0;
+        for block in blocks {
+            if let Entry::Vacant(entry) = state.l1_blocks.entry(block.height) {
+                entry.insert(block);
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    async fn get_incomplete_l1_ranges(&self, limit: u64) -> Result<Vec<IncompleteL1Range>, DbErr> {
+        let state = self.state();
+        Ok(state
+            .checkpoints
+            .values()
+            .filter(|checkpoint| checkpoint.l1_start <= checkpoint.l1_end)
+            .filter(|checkpoint| {
+                let stored = state
+                    .l1_blocks
+                    .range(checkpoint.l1_start..=checkpoint.l1_end)
+                    .count() as u64;
+                stored < checkpoint.l1_end.abs_diff(checkpoint.l1_start) + 1
+            })
+            .take(limit as usize)
+            .map(|checkpoint| IncompleteL1Range {
+                checkpoint_idx: checkpoint.idx,
+                start: checkpoint.l1_start,
+                end: checkpoint.l1_end,
+            })
+            .collect())
+    }
+
+    async fn get_l1_block_heights_in_range(&self, from: i64, to: i64) -> Result<Vec<i64>, DbErr> {
+        Ok(self
+            .state()
+            .l1_blocks
+            .range(from..=to)
+            .map(|(height, _)| *height)
+            .collect())
+    }
+
+    async fn get_l1_blocks_without_timestamp(
+        &self,
+        limit: u64,
+    ) -> Result<Vec<l1_block::Model>, DbErr> {
+        Ok(self
+            .state()
+            .l1_blocks
+            .values()
+            .filter(|block| block.timestamp.is_none())
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn set_l1_block_timestamp(&self, height: i64, timestamp: i64) -> Result<(), DbErr> {
+        if let Some(block) = self.state().l1_blocks.get_mut(&height) {
+            block.timestamp = Some(timestamp);
+        }
+        Ok(())
+    }
+
+    async fn get_l1_block_by_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<l1_block::Model>, DbErr> {
+        Ok(self
+            .state()
+            .l1_blocks
+            .values()
+            .find(|block| block.block_hash == block_hash)
+            .cloned())
+    }
+
+    async fn get_l1_block_by_height(&self, height: i64) -> Result<Option<l1_block::Model>, DbErr> {
+        Ok(self.state().l1_blocks.get(&height).cloned())
+    }
+}
+
+#[async_trait]
+impl EvmBlockRepository for InMemoryRepository {
+    async fn insert_evm_blocks(&self, blocks: Vec<evm_block::Model>) -> Result<u64, DbErr> {
+        let mut state = self.state();
+        let mut inserted = 0;
+        for block in blocks {
+            if let Entry::Vacant(entry) = state.evm_blocks.entry(block.l2_height) {
+                entry.insert(block);
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    async fn get_blocks_without_evm_block(
+        &self,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<block::Model>, DbErr> {
+        let state = self.state();
+        let from = after.map_or(Bound::Unbounded, Bound::Excluded);
+        Ok(state
+            .blocks
+            .range((from, Bound::Unbounded))
+            .filter(|(height, _)| !state.evm_blocks.contains_key(height))
+            .take(limit as usize)
+            .map(|(_, block)| block.clone())
+            .collect())
+    }
+
+    async fn get_evm_block_by_number(
+        &self,
+        number: i64,
+    ) -> Result<Option<evm_block::Model>, DbErr> {
+        Ok(self
+            .state()
+            .evm_blocks
+            .values()
+            .find(|block| block.evm_block_number == number)
+            .cloned())
+    }
+
+    async fn get_evm_block_by_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<evm_block::Model>, DbErr> {
+        Ok(self
+            .state()
+            .evm_blocks
+            .values()
+            .find(|block| block.evm_block_hash == block_hash)
+            .cloned())
+    }
+}
+
+#[async_trait]
+impl BridgeIntentRepository for InMemoryRepository {
+    async fn insert_bridge_intents(
+        &self,
+        from: i64,
+        to: i64,
+        intents: Vec<bridge_intent::Model>,
+    ) -> Result<u64, DbErr> {
+        let mut state = self.state();
+        state.delete_bridge_intents(from, to);
+        let inserted = intents.len() as u64;
+        for intent in intents {
+            let key = (intent.l2_height, intent.kind.clone(), intent.position);
+            state.bridge_intents.insert(key, intent);
+        }
+        state.advance_cursor(SyncCursor::BridgeScanned, to);
+        Ok(inserted)
+    }
+
+    async fn get_bridge_intents_by_txid(
+        &self,
+        txid: &str,
+    ) -> Result<Vec<bridge_intent::Model>, DbErr> {
+        Ok(self
+            .state()
+            .bridge_intents
+            .values()
+            .filter(|intent| intent.txid.as_deref() == Some(txid))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_bridge_intents_in_range(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<bridge_intent::Model>, DbErr> {
+        Ok(self
+            .state()
+            .bridge_intents
+            .values()
+            .filter(|intent| from <= intent.l2_height && intent.l2_height <= to)
+            .cloned()
+            .collect())
+    }
+
+    async fn insert_bridge_duties(
+        &self,
+        operator_idx: i32,
+        next_index: i64,
+        duties: Vec<bridge_duty::Model>,
+    ) -> Result<u64, DbErr> {
+        let mut state = self.state();
+        let inserted = duties.len() as u64;
+        for duty in duties {
+            let key = (duty.operator_idx, duty.kind.clone(), duty.outpoint.clone());
+            match state.bridge_duties.entry(key) {
+                Entry::Occupied(mut entry) => {
+                    let seen_at = entry.get().seen_at;
+                    entry.insert(bridge_duty::Model { seen_at, ..duty });
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(duty);
+                }
+            }
+        }
+        state.bridge_duty_cursors.insert(operator_idx, next_index);
+        Ok(inserted)
+    }
+
+    async fn get_bridge_duty_cursor(&self, operator_idx: i32) -> Result<Option<i64>, DbErr> {
+        Ok(self.state().bridge_duty_cursors.get(&operator_idx).copied())
+    }
+
+    async fn get_bridge_duties_by_destination(
+        &self,
+        destination: &str,
+    ) -> Result<Vec<bridge_duty::Model>, DbErr> {
+        Ok(self
+            .state()
+            .bridge_duties
+            .values()
+            .filter(|duty| duty.destination == destination)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl BlockFetchJobRepository for InMemoryRepository {
+    async fn enqueue_block_fetch(&self, idx: i64) -> Result<(), DbErr> {
+        let now = unix_timestamp();
+        let mut state = self.state();
+        let job = state
+            .block_fetch_jobs
+            .entry(idx)
+            .or_insert_with(|| block_fetch_job::Model {
+                checkpoint_idx: idx,
+                state: JobState::Queued.state().to_string(),
+                attempts: 0,
+                last_error: None,
+                next_attempt_at: 0,
+                created_at: now,
+                updated_at: now,
+            });
+        if JobState::from_state(&job.state) == Some(JobState::Failed) {
+            job.state = JobState::Queued.state().to_string();
+            job.updated_at = now;
+        }
+        Ok(())
+    }
+
+    async fn claim_block_fetch(&self, now: i64) -> Result<Option<i64>, DbErr> {
+        let mut state = self.state();
+        let job = state.block_fetch_jobs.values_mut().find(|job| {
+            matches!(
+                JobState::from_state(&job.state),
+                Some(JobState::Queued | JobState::Failed)
+            ) && job.next_attempt_at <= now
+        });
+        Ok(job.map(|job| {
+            job.state = JobState::Running.state().to_string();
+            job.attempts = job.attempts.saturating_add(1);
+            job.updated_at = unix_timestamp();
+            job.checkpoint_idx
+        }))
+    }
+
+    async fn finish_block_fetch(&self, idx: i64, error: Option<String>) -> Result<(), DbErr> {
+        let mut state = self.state();
+        let Some(job) = state.block_fetch_jobs.get_mut(&idx) else {
+            return Ok(());
+        };
+        let now = unix_timestamp();
+        let (job_state, next_attempt_at) = match error {
+            Some(_) => (JobState::Failed, now + retry_delay(job.attempts)),
+            None => (JobState::Done, 0),
+        };
+        job.state = job_state.state().to_string();
+        job.last_error = error;
+        job.next_attempt_at = next_attempt_at;
+        job.updated_at = now;
+        Ok(())
+    }
+
+    async fn requeue_block_fetch(&self, idx: i64) -> Result<(), DbErr> {
+        self.state().set_job_state(idx, JobState::Queued);
+        Ok(())
+    }
+
+    async fn requeue_running_block_fetches(&self) -> Result<u64, DbErr> {
+        let mut state = self.state();
+        let running: Vec<i64> = state
+            .block_fetch_jobs
+            .values()
+            .filter(|job| job.state == JobState::Running.state())
+            .map(|job| job.checkpoint_idx)
+            .collect();
+        for idx in &running {
+            state.set_job_state(*idx, JobState::Queued);
+        }
+        Ok(running.len() as u64)
+    }
+
+    async fn get_block_fetch_job_counts(&self) -> Result<BlockFetchJobCounts, DbErr> {
+        let mut counts = BlockFetchJobCounts::default();
+        for job in self.state().block_fetch_jobs.values() {
+            match JobState::from_state(&job.state) {
+                Some(JobState::Queued) => counts.queued += 1,
+                Some(JobState::Running) => counts.running += 1,
+                Some(JobState::Done) => counts.done += 1,
+                Some(JobState::Failed) => counts.failed += 1,
+                None => (),
+            }
+        }
+        Ok(counts)
+    }
+}
+
+#[async_trait]
+impl WebhookDeliveryRepository for InMemoryRepository {
+    async fn enqueue_webhook_event(
+        &self,
+        event: NewWebhookEvent,
+        urls: &[String],
+    ) -> Result<(), DbErr> {
+        self.state().enqueue_deliveries(vec![event], urls);
+        Ok(())
+    }
+
+    async fn get_pending_webhook_deliveries(
+        &self,
+        url: &str,
+        limit: u64,
+    ) -> Result<Vec<webhook_delivery::Model>, DbErr> {
+        Ok(self
+            .state()
+            .webhook_deliveries
+            .values()
+            .filter(|delivery| {
+                delivery.url == url && delivery.state == DeliveryState::Pending.state()
+            })
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn mark_webhook_delivered(&self, id: i64) -> Result<(), DbErr> {
+        if let Some(delivery) = self.state().webhook_deliveries.get_mut(&id) {
+            delivery.state = DeliveryState::Delivered.state().to_string();
+            delivery.attempts = delivery.attempts.saturating_add(1);
+            delivery.last_error = None;
+            delivery.delivered_at = Some(unix_timestamp());
+        }
+        Ok(())
+    }
+
+    async fn mark_webhook_failed(
+        &self,
+        id: i64,
+        error: String,
+        next_attempt_at: i64,
+    ) -> Result<(), DbErr> {
+        if let Some(delivery) = self.state().webhook_deliveries.get_mut(&id) {
+            delivery.attempts = delivery.attempts.saturating_add(1);
+            delivery.last_error = Some(error);
+            delivery.next_attempt_at = next_attempt_at;
+        }
+        Ok(())
+    }
+
+    async fn get_webhook_delivery_counts(&self) -> Result<WebhookDeliveryCounts, DbErr> {
+        let mut counts = WebhookDeliveryCounts::default();
+        for delivery in self.state().webhook_deliveries.values() {
+            match DeliveryState::from_state(&delivery.state) {
+                Some(DeliveryState::Pending) => counts.pending += 1,
+                Some(DeliveryState::Delivered) => counts.delivered += 1,
+                None => (),
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn prune_webhook_deliveries(&self, delivered_before: i64) -> Result<u64, DbErr> {
+        let mut state = self.state();
+        let before = state.webhook_deliveries.len();
+        state.webhook_deliveries.retain(|_, delivery| {
+            delivery.state != DeliveryState::Delivered.state()
+                || delivery
+                    .delivered_at
+                    .is_none_or(|delivered_at| delivered_at >= delivered_before)
+        });
+        Ok((before - state.webhook_deliveries.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl SyncStateRepository for InMemoryRepository {
+    async fn get_sync_cursor(&self, cursor: SyncCursor) -> Result<Option<i64>, DbErr> {
+        Ok(self
+            .state()
+            .sync_state
+            .get(cursor.name())
+            .map(|state| state.value))
+    }
+
+    async fn get_sync_cursors(&self) -> Result<Vec<sync_state::Model>, DbErr> {
+        Ok(self.state().sync_state.values().cloned().collect())
+    }
+
+    async fn declare_index_range(&self, start: i64, end: Option<i64>) -> Result<(), DbErr> {
+        let mut state = self.state();
+        state.set_cursor(SyncCursor::IndexStart, start);
+        match end {
+            Some(end) => state.set_cursor(SyncCursor::IndexEnd, end),
+            None => {
+                state.sync_state.remove(SyncCursor::IndexEnd.name());
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_index_range(&self) -> Result<IndexRange, DbErr> {
+        let state = self.state();
+        Ok(IndexRange {
+            start_checkpoint: PgU64::i64_to_u64(state.index_start_checkpoint()),
+            end_checkpoint: state
+                .sync_state
+                .get(SyncCursor::IndexEnd.name())
+                .map(|state| PgU64::i64_to_u64(state.value)),
+            first_checkpoint: state
+                .checkpoints
+                .keys()
+                .next()
+                .copied()
+                .map(PgU64::i64_to_u64),
+            last_checkpoint: state
+                .checkpoints
+                .keys()
+                .next_back()
+                .copied()
+                .map(PgU64::i64_to_u64),
+            first_block: state.blocks.keys().next().copied().map(PgU64::i64_to_u64),
+            last_block: state
+                .blocks
+                .keys()
+                .next_back()
+                .copied()
+                .map(PgU64::i64_to_u64),
+        })
+    }
+}
+
+#[async_trait]
+impl IntegrityRepository for InMemoryRepository {
+    async fn get_integrity_report(&self) -> Result<IntegrityReport, DbErr> {
+        let state = self.state();
+        let highest_block = state.blocks.keys().next_back().copied();
+
+        let mut block_counts: BTreeMap<i64, u64> = BTreeMap::new();
+        for block in state.blocks.values() {
+            *block_counts.entry(block.checkpoint_idx).or_default() += 1;
+        }
+        let block_count_mismatches = state
+            .checkpoints
+            .values()
+            .filter(|checkpoint| highest_block.is_some_and(|highest| checkpoint.l2_end <= highest))
+            .filter_map(|checkpoint| {
+                let expected = checkpoint.l2_end.abs_diff(checkpoint.l2_start) + 1;
+                let actual = block_counts
+                    .get(&checkpoint.idx)
+                    .copied()
+                    .unwrap_or_default();
+                (expected != actual).then(|| BlockCountMismatch {
+                    checkpoint_idx: PgU64::i64_to_u64(checkpoint.idx),
+                    l2_range: (
+                        PgU64::i64_to_u64(checkpoint.l2_start),
+                        PgU64::i64_to_u64(checkpoint.l2_end),
+                    ),
+                    expected,
+                    actual,
+                })
+            })
+            .collect();
+
+        Ok(IntegrityReport {
+            missing_checkpoints: missing_ranges(
+                state.checkpoints.keys().copied(),
+                state.index_start_checkpoint(),
+            ),
+            missing_blocks: missing_ranges(
+                state.blocks.keys().copied(),
+                state.index_start_height(),
+            ),
+            block_count_mismatches,
+            checked_at: unix_timestamp(),
+        })
+    }
+}
+
+#[async_trait]
+impl MaintenanceRepository for InMemoryRepository {
+    async fn delete_checkpoints(
+        &self,
+        from: i64,
+        to: Option<i64>,
+        with_reorg_events: bool,
+    ) -> Result<PurgeSummary, DbErr> {
+        let in_range = |idx: i64| idx >= from && to.is_none_or(|to| idx <= to);
+        let mut state = self.state();
+
+        let purged: Vec<checkpoint::Model> = state
+            .checkpoints
+            .iter()
+            .filter(|(idx, _)| in_range(**idx))
+            .map(|(_, checkpoint)| checkpoint.clone())
+            .collect();
+        for checkpoint in &purged {
+            state.purge_bridge_intents(checkpoint);
+        }
+        state
+            .evm_blocks
+            .retain(|_, block| !in_range(block.checkpoint_idx));
+        let blocks = state.blocks.len();
+        state
+            .blocks
+            .retain(|_, block| !in_range(block.checkpoint_idx));
+        let l1_blocks = state.l1_blocks.len();
+        state
+            .l1_blocks
+            .retain(|_, block| !in_range(block.checkpoint_idx));
+        let reorg_events = state.reorg_events.len();
+        if with_reorg_events {
+            state
+                .reorg_events
+                .retain(|event| !in_range(event.checkpoint_idx));
+        }
+        let checkpoints = state.checkpoints.len();
+        state.checkpoints.retain(|idx, _| !in_range(*idx));
+        state.block_fetch_jobs.retain(|idx, _| !in_range(*idx));
+        state.rewind_cursors();
+
+        Ok(PurgeSummary {
+            checkpoints: (checkpoints - state.checkpoints.len()) as u64,
+            blocks: (blocks - state.blocks.len()) as u64,
+            l1_blocks: (l1_blocks - state.l1_blocks.len()) as u64,
+            reorg_events: (reorg_events - state.reorg_events.len()) as u64,
+        })
+    }
+
+    async fn delete_checkpoint_blocks(&self, idx: i64) -> Result<u64, DbErr> {
+        let mut state = self.state();
+        if let Some(checkpoint) = state.checkpoints.get(&idx).cloned() {
+            state.purge_bridge_intents(&checkpoint);
+        }
+        state
+            .evm_blocks
+            .retain(|_, block| block.checkpoint_idx != idx);
+        let blocks = state.blocks.len();
+        state.blocks.retain(|_, block| block.checkpoint_idx != idx);
+        state.block_fetch_jobs.remove(&idx);
+        state.rewind_cursors();
+        Ok((blocks - state.blocks.len()) as u64)
+    }
+}
@@ -0,0 +1,869 @@
+//! Storage abstraction used by the indexer and the API.
+//!
+//! [`sql::SqlRepository`] stores everything through sea-orm, [`memory::InMemoryRepository`]
+//! keeps it in process memory so the sync algorithms can run without a database.
+pub mod memory;
+pub mod sql;
+
+use crate::services::pagination::PaginatedData;
+use async_trait::async_trait;
+use model::block::{self, RpcBlockHeader};
+use model::block_fetch_job::BlockFetchJobCounts;
+use model::bridge_duty;
+use model::bridge_intent;
+use model::checkpoint::{self, RpcCheckpointInfo, RpcCheckpointInfoCheckpointExp};
+use model::evm_block;
+use model::integrity::IntegrityReport;
+use model::l1_block::{self, IncompleteL1Range};
+use model::reorg_event::RpcReorgEvent;
+use model::sync_state::{self, IndexRange, SyncCursor};
+use model::webhook_delivery::{self, NewWebhookEvent, WebhookDeliveryCounts};
+use sea_orm::DbErr;
+
+pub use crate::services::checkpoint_service::{CheckpointEvents, CheckpointReconciliation};
+pub use crate::services::maintenance_service::PurgeSummary;
+
+#[async_trait]
+pub trait CheckpointRepository: Send + Sync {
+    async fn checkpoint_exists(&self, idx: i64) -> bool;
+
+    /// Insert a new checkpoint, provided the previous one is already stored or it is the
+    /// first checkpoint of the index, and queue its `events` in the same write
+    async fn insert_checkpoint(&self, checkpoint: RpcCheckpointInfo, events: &dyn CheckpointEvents);
+
+    async fn get_checkpoint_by_idx(
+        &self,
+        idx: i64,
+    ) -> Result<Option<RpcCheckpointInfoCheckpointExp>, DbErr>;
+
+    async fn get_checkpoint_idx_by_block_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<i64>, DbErr>;
+
+    async fn get_checkpoint_idx_by_block_height(
+        &self,
+        block_height: i64,
+    ) -> Result<Option<i64>, DbErr>;
+
+    /// Index of the stored checkpoint whose L2 range covers `height`, whether or not the
+    /// block itself is stored
+    async fn get_checkpoint_idx_by_l2_height(&self, height: i64) -> Result<Option<i64>, DbErr>;
+
+    async fn get_paginated_checkpoints(
+        &self,
+        current_page: u64,
+        page_size: u64,
+        absolute_first_page: u64,
+        order: Option<&str>,
+    ) -> Result<PaginatedData<RpcCheckpointInfoCheckpointExp>, DbErr>;
+
+    async fn get_total_checkpoint_count(&self) -> u64;
+
+    /// Number of checkpoints per status, ordered by status
+    async fn get_checkpoint_counts_by_status(&self) -> Result<Vec<(String, u64)>, DbErr>;
+
+    /// Stored checkpoints from `from` to `to` included, in ascending order
+    async fn get_checkpoints_in_range(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr>;
+
+    async fn get_latest_checkpoint_index(&self) -> Option<i64>;
+
+    /// Indexes of all checkpoints whose status is one of `statuses`, in ascending order
+    async fn get_checkpoint_idxs_by_status(&self, statuses: &[&str]) -> Result<Vec<i64>, DbErr>;
+
+    /// Up to `limit` checkpoints whose status is one of `statuses` and that entered it before
+    /// the UNIX timestamp `entered_before`, longest stalled first
+    async fn get_stalled_checkpoints(
+        &self,
+        statuses: &[&str],
+        entered_before: i64,
+        limit: u64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr>;
+
+    /// Replace the confirmation time of a checkpoint still confirmed by `txid`, e.g. with the
+    /// time of its L1 block. Returns whether the checkpoint was updated.
+    async fn set_checkpoint_confirmed_at(
+        &self,
+        checkpoint_idx: i64,
+        txid: &str,
+        confirmed_at: i64,
+    ) -> Result<bool, DbErr>;
+
+    /// Checkpoints confirmed or finalized at or after the UNIX timestamp `since`, in
+    /// ascending order
+    async fn get_checkpoint_lifecycles_since(
+        &self,
+        since: i64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr>;
+
+    /// Bring the stored checkpoint in line with the fullnode, recording reorgs, and queue the
+    /// `events` of the outcome in the same write
+    async fn reconcile_checkpoint(
+        &self,
+        checkpoint_idx: i64,
+        checkpoint_from_rpc: RpcCheckpointInfo,
+        events: &dyn CheckpointEvents,
+    ) -> Result<CheckpointReconciliation, DbErr>;
+
+    /// Recorded reorg events, most recent first
+    async fn get_paginated_reorg_events(
+        &self,
+        current_page: u64,
+        page_size: u64,
+    ) -> Result<PaginatedData<RpcReorgEvent>, DbErr>;
+}
+
+#[async_trait]
+pub trait BlockRepository: Send + Sync {
+    /// Insert a block, provided the previous height is already stored. A block already stored
+    /// is left as is, a block that does not follow the stored ones is an error.
+    async fn insert_block(
+        &self,
+        rpc_block_header: RpcBlockHeader,
+        checkpoint_idx: i64,
+    ) -> Result<(), DbErr>;
+
+    async fn get_latest_block_index(&self) -> Option<i64>;
+
+    async fn can_insert_block(&self, height: i64) -> bool;
+
+    /// Stored blocks from height `from` to `to` included, in ascending order
+    async fn get_blocks_in_range(&self, from: i64, to: i64) -> Result<Vec<block::Model>, DbErr>;
+
+    async fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<block::Model>, DbErr>;
+}
+
+/// Indexer cursors, moved by the checkpoint and block writes they cover
+#[async_trait]
+pub trait SyncStateRepository: Send + Sync {
+    async fn get_sync_cursor(&self, cursor: SyncCursor) -> Result<Option<i64>, DbErr>;
+
+    async fn get_sync_cursors(&self) -> Result<Vec<sync_state::Model>, DbErr>;
+
+    /// Record the range of checkpoints the indexer is configured for. The continuity checks
+    /// treat `start` as the genesis from then on.
+    async fn declare_index_range(&self, start: i64, end: Option<i64>) -> Result<(), DbErr>;
+
+    /// The declared index range and the checkpoints and blocks actually stored
+    async fn get_index_range(&self) -> Result<IndexRange, DbErr>;
+}
+
+/// Consistency checks over the stored checkpoints and blocks
+#[async_trait]
+pub trait IntegrityRepository: Send + Sync {
+    /// Missing checkpoints and blocks, and checkpoints whose blocks do not match their L2 range
+    async fn get_integrity_report(&self) -> Result<IntegrityReport, DbErr>;
+}
+
+/// Deletion of indexed data so that it can be fetched again, safe to use while the indexer
+/// runs
+#[async_trait]
+pub trait MaintenanceRepository: Send + Sync {
+    /// Delete the checkpoints from `from` to `to` included, or every checkpoint from `from` on
+    /// when `to` is `None`, with their blocks and block fetch jobs, and rewind the cursors.
+    /// Their reorg events are history and are only deleted `with_reorg_events`.
+    async fn delete_checkpoints(
+        &self,
+        from: i64,
+        to: Option<i64>,
+        with_reorg_events: bool,
+    ) -> Result<PurgeSummary, DbErr>;
+
+    /// Delete the blocks of one checkpoint and its block fetch job, so that the indexer
+    /// queues it again, and rewind the block cursor
+    async fn delete_checkpoint_blocks(&self, idx: i64) -> Result<u64, DbErr>;
+}
+
+/// Persisted queue of block fetch jobs, one per checkpoint, so that pending work survives
+/// restarts and requesting a checkpoint twice does not fetch its blocks twice
+#[async_trait]
+pub trait BlockFetchJobRepository: Send + Sync {
+    /// Queue the blocks of checkpoint `idx` when it has no job yet or its job failed. A
+    /// failed job keeps its attempts and is not claimed before its retry time, a pending or
+    /// done job is left as is.
+    async fn enqueue_block_fetch(&self, idx: i64) -> Result<(), DbErr>;
+
+    /// Mark the job with the lowest checkpoint that is queued, or failed and due again at
+    /// `now`, as running and return its checkpoint
+    async fn claim_block_fetch(&self, now: i64) -> Result<Option<i64>, DbErr>;
+
+    /// Mark the job of checkpoint `idx` done, or failed with `error` and due again after
+    /// [`model::block_fetch_job::retry_delay`]
+    async fn finish_block_fetch(&self, idx: i64, error: Option<String>) -> Result<(), DbErr>;
+
+    /// Put the job of checkpoint `idx` back in the queue
+    async fn requeue_block_fetch(&self, idx: i64) -> Result<(), DbErr>;
+
+    /// Put every running job back in the queue, returns how many there were
+    async fn requeue_running_block_fetches(&self) -> Result<u64, DbErr>;
+
+    async fn get_block_fetch_job_counts(&self) -> Result<BlockFetchJobCounts, DbErr>;
+}
+
+/// Persisted outbox of the webhook deliveries, so that events survive restarts and are
+/// retried until the receivers acknowledge them
+#[async_trait]
+pub trait WebhookDeliveryRepository: Send + Sync {
+    /// Queue one delivery of `event` per URL, a no-op without URLs
+    async fn enqueue_webhook_event(
+        &self,
+        event: NewWebhookEvent,
+        urls: &[String],
+    ) -> Result<(), DbErr>;
+
+    /// The oldest `limit` pending deliveries to `url`, in the order they were queued
+    async fn get_pending_webhook_deliveries(
+        &self,
+        url: &str,
+        limit: u64,
+    ) -> Result<Vec<webhook_delivery::Model>, DbErr>;
+
+    /// Record the acknowledgement of delivery `id`
+    async fn mark_webhook_delivered(&self, id: i64) -> Result<(), DbErr>;
+
+    /// Record a failed attempt of delivery `id`, attempted again at `next_attempt_at`
+    async fn mark_webhook_failed(
+        &self,
+        id: i64,
+        error: String,
+        next_attempt_at: i64,
+    ) -> Result<(), DbErr>;
+
+    async fn get_webhook_delivery_counts(&self) -> Result<WebhookDeliveryCounts, DbErr>;
+
+    /// Delete the deliveries acknowledged before `delivered_before`, pending ones are kept.
+    /// Returns the number of deliveries deleted.
+    async fn prune_webhook_deliveries(&self, delivered_before: i64) -> Result<u64, DbErr>;
+}
+
+#[async_trait]
+pub trait L1BlockRepository: Send + Sync {
+    /// Insert `blocks`, skipping the heights already stored. Returns the number of blocks
+    /// inserted.
+    async fn insert_l1_blocks(&self, blocks: Vec<l1_block::Model>) -> Result<u64, DbErr>;
+
+    /// L1 ranges of up to `limit` checkpoints with blocks not stored yet, in checkpoint order
+    async fn get_incomplete_l1_ranges(&self, limit: u64) -> Result<Vec<IncompleteL1Range>, DbErr>;
+
+    /// Heights of the L1 blocks stored from `from` to `to` included, in ascending order
+    async fn get_l1_block_heights_in_range(&self, from: i64, to: i64) -> Result<Vec<i64>, DbErr>;
+
+    /// Up to `limit` L1 blocks whose header time is unknown, lowest first
+    async fn get_l1_blocks_without_timestamp(
+        &self,
+        limit: u64,
+    ) -> Result<Vec<l1_block::Model>, DbErr>;
+
+    async fn set_l1_block_timestamp(&self, height: i64, timestamp: i64) -> Result<(), DbErr>;
+
+    async fn get_l1_block_by_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<l1_block::Model>, DbErr>;
+
+    async fn get_l1_block_by_height(&self, height: i64) -> Result<Option<l1_block::Model>, DbErr>;
+}
+
+/// Links between the L2 blocks and the blocks of the execution layer
+#[async_trait]
+pub trait EvmBlockRepository: Send + Sync {
+    /// Insert `blocks`, skipping the L2 heights already linked. Returns the number of blocks
+    /// inserted.
+    async fn insert_evm_blocks(&self, blocks: Vec<evm_block::Model>) -> Result<u64, DbErr>;
+
+    /// Up to `limit` L2 blocks above height `after` not linked to an EVM block yet, lowest
+    /// first
+    async fn get_blocks_without_evm_block(
+        &self,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<block::Model>, DbErr>;
+
+    async fn get_evm_block_by_number(&self, number: i64)
+        -> Result<Option<evm_block::Model>, DbErr>;
+
+    async fn get_evm_block_by_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<evm_block::Model>, DbErr>;
+}
+
+/// Bridge deposits and withdrawals of the L2 blocks, scanned in height order
+#[async_trait]
+pub trait BridgeIntentRepository: Send + Sync {
+    /// Replace the intents of the blocks scanned from height `from` to `to` included and move
+    /// the bridge cursor to `to`. Returns the number of intents stored.
+    async fn insert_bridge_intents(
+        &self,
+        from: i64,
+        to: i64,
+        intents: Vec<bridge_intent::Model>,
+    ) -> Result<u64, DbErr>;
+
+    /// Withdrawals requested by the EL transaction `txid`, in block order
+    async fn get_bridge_intents_by_txid(
+        &self,
+        txid: &str,
+    ) -> Result<Vec<bridge_intent::Model>, DbErr>;
+
+    /// Intents of the blocks from height `from` to `to` included, in block order
+    async fn get_bridge_intents_in_range(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<bridge_intent::Model>, DbErr>;
+
+    /// Store the duties listed for operator `operator_idx` and move its cursor to
+    /// `next_index`. A duty listed again keeps the time it was first seen. Returns the number
+    /// of duties stored.
+    async fn insert_bridge_duties(
+        &self,
+        operator_idx: i32,
+        next_index: i64,
+        duties: Vec<bridge_duty::Model>,
+    ) -> Result<u64, DbErr>;
+
+    /// `start_index` of the next duty listing of operator `operator_idx`, `None` before the
+    /// first
+    async fn get_bridge_duty_cursor(&self, operator_idx: i32) -> Result<Option<i64>, DbErr>;
+
+    /// Duties paying to or crediting `destination`, by operator
+    async fn get_bridge_duties_by_destination(
+        &self,
+        destination: &str,
+    ) -> Result<Vec<bridge_duty::Model>, DbErr>;
+}
+
+/// Everything the indexer and the API need from storage
+pub trait Repository:
+    CheckpointRepository
+    + BlockRepository
+    + L1BlockRepository
+    + EvmBlockRepository
+    + BridgeIntentRepository
+    + BlockFetchJobRepository
+    + SyncStateRepository
+    + IntegrityRepository
+    + MaintenanceRepository
+    + WebhookDeliveryRepository
+{
+}
+
+impl<T> Repository for T where
+    T: CheckpointRepository
+        + BlockRepository
+        + L1BlockRepository
+        + EvmBlockRepository
+        + BridgeIntentRepository
+        + BlockFetchJobRepository
+        + SyncStateRepository
+        + IntegrityRepository
+        + MaintenanceRepository
+        + WebhookDeliveryRepository
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::utils::unix_timestamp;
+    use crate::testing::{block, checkpoint, repository_tests, Events, TestRepository};
+    use model::block_fetch_job::retry_delay;
+    use model::pgu64::PgU64;
+
+    fn idx(value: u64) -> i64 {
+        PgU64(value).to_i64()
+    }
+
+    /// Checkpoints 0 to 2 covering three blocks each, with their blocks
+    async fn index_three_checkpoints(repository: &impl Repository) {
+        for i in 0..3 {
+            repository
+                .insert_checkpoint(
+                    checkpoint(i, (3 * i, 3 * i + 2), "finalized", "aa"),
+                    &Events::default(),
+                )
+                .await;
+            for height in 3 * i..=3 * i + 2 {
+                repository
+                    .insert_block(block(height), idx(i))
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    async fn claims_block_fetch_jobs_in_checkpoint_order<R: TestRepository>() {
+        let repository = R::open().await;
+        for i in [2, 0, 1] {
+            repository.enqueue_block_fetch(idx(i)).await.unwrap();
+        }
+        // queueing a queued job again changes nothing
+        repository.enqueue_block_fetch(idx(0)).await.unwrap();
+
+        assert_eq!(
+            repository
+                .claim_block_fetch(unix_timestamp())
+                .await
+                .unwrap(),
+            Some(idx(0))
+        );
+        assert_eq!(
+            repository
+                .claim_block_fetch(unix_timestamp())
+                .await
+                .unwrap(),
+            Some(idx(1))
+        );
+        repository
+            .finish_block_fetch(idx(0), Some("unreachable".to_string()))
+            .await
+            .unwrap();
+        repository.finish_block_fetch(idx(1), None).await.unwrap();
+        assert_eq!(
+            repository
+                .claim_block_fetch(unix_timestamp())
+                .await
+                .unwrap(),
+            Some(idx(2))
+        );
+        // the failed job waits for its retry
+        assert_eq!(
+            repository
+                .claim_block_fetch(unix_timestamp())
+                .await
+                .unwrap(),
+            None
+        );
+
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!((counts.running, counts.done, counts.failed), (1, 1, 1));
+        assert_eq!(repository.requeue_running_block_fetches().await.unwrap(), 1);
+        assert_eq!(
+            repository
+                .claim_block_fetch(unix_timestamp())
+                .await
+                .unwrap(),
+            Some(idx(2))
+        );
+    }
+
+    async fn enqueues_only_missing_or_failed_block_fetch_jobs<R: TestRepository>() {
+        let repository = R::open().await;
+        index_three_checkpoints(&repository).await;
+        let far_ahead = unix_timestamp() + 3600;
+        repository.enqueue_block_fetch(idx(0)).await.unwrap();
+        assert_eq!(
+            repository.claim_block_fetch(far_ahead).await.unwrap(),
+            Some(idx(0))
+        );
+        repository.finish_block_fetch(idx(0), None).await.unwrap();
+
+        // a done job stays done
+        repository.enqueue_block_fetch(idx(0)).await.unwrap();
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!((counts.queued, counts.done), (0, 1));
+        assert_eq!(repository.claim_block_fetch(far_ahead).await.unwrap(), None);
+
+        // a failed job is queued again but keeps its retry time
+        repository.enqueue_block_fetch(idx(1)).await.unwrap();
+        let before = unix_timestamp();
+        repository.claim_block_fetch(before).await.unwrap();
+        repository
+            .finish_block_fetch(idx(1), Some("unreachable".to_string()))
+            .await
+            .unwrap();
+        repository.enqueue_block_fetch(idx(1)).await.unwrap();
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!((counts.queued, counts.failed), (1, 0));
+        assert_eq!(
+            repository
+                .claim_block_fetch(before + retry_delay(1) - 1)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            repository.claim_block_fetch(far_ahead).await.unwrap(),
+            Some(idx(1))
+        );
+
+        // refetching the blocks of a checkpoint drops its job so that it is queued again
+        repository.delete_checkpoint_blocks(idx(0)).await.unwrap();
+        repository.enqueue_block_fetch(idx(0)).await.unwrap();
+        assert_eq!(
+            repository.claim_block_fetch(far_ahead).await.unwrap(),
+            Some(idx(0))
+        );
+    }
+
+    async fn retries_failed_block_fetch_jobs_after_a_growing_delay<R: TestRepository>() {
+        let repository = R::open().await;
+        repository.enqueue_block_fetch(idx(0)).await.unwrap();
+
+        let mut due = unix_timestamp();
+        for attempts in 1..=3 {
+            assert_eq!(
+                repository.claim_block_fetch(due).await.unwrap(),
+                Some(idx(0))
+            );
+            let before = unix_timestamp();
+            repository
+                .finish_block_fetch(idx(0), Some("unreachable".to_string()))
+                .await
+                .unwrap();
+            // failed jobs come back on their own, without being queued again
+            assert_eq!(
+                repository
+                    .claim_block_fetch(before + retry_delay(attempts) - 1)
+                    .await
+                    .unwrap(),
+                None
+            );
+            due = unix_timestamp() + retry_delay(attempts);
+        }
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!(counts.failed, 1);
+    }
+
+    async fn refuses_a_block_that_does_not_follow_the_stored_ones<R: TestRepository>() {
+        let repository = R::open().await;
+        repository
+            .insert_checkpoint(checkpoint(0, (0, 4), "finalized", "aa"), &Events::default())
+            .await;
+        repository.insert_block(block(0), idx(0)).await.unwrap();
+
+        let err = repository.insert_block(block(2), idx(0)).await.unwrap_err();
+        assert!(err.to_string().contains("does not follow"), "{}", err);
+        assert_eq!(repository.get_latest_block_index().await, Some(idx(0)));
+
+        repository.insert_block(block(1), idx(0)).await.unwrap();
+        // a stored block is left as is
+        repository.insert_block(block(1), idx(0)).await.unwrap();
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::BlockIngested)
+                .await
+                .unwrap(),
+            Some(idx(1))
+        );
+    }
+
+    async fn moves_the_cursors_with_the_writes<R: TestRepository>() {
+        let repository = R::open().await;
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::CheckpointIngested)
+                .await
+                .unwrap(),
+            None
+        );
+        index_three_checkpoints(&repository).await;
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::CheckpointIngested)
+                .await
+                .unwrap(),
+            Some(idx(2))
+        );
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::BlockIngested)
+                .await
+                .unwrap(),
+            Some(idx(8))
+        );
+
+        repository
+            .declare_index_range(idx(1), Some(idx(5)))
+            .await
+            .unwrap();
+        repository.declare_index_range(idx(2), None).await.unwrap();
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::IndexStart)
+                .await
+                .unwrap(),
+            Some(idx(2))
+        );
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::IndexEnd)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    async fn deletes_checkpoints_with_their_blocks_and_jobs<R: TestRepository>() {
+        let repository = R::open().await;
+        index_three_checkpoints(&repository).await;
+        for i in 0..3 {
+            repository.enqueue_block_fetch(idx(i)).await.unwrap();
+        }
+
+        let summary = repository
+            .delete_checkpoints(idx(1), None, false)
+            .await
+            .unwrap();
+        assert_eq!((summary.checkpoints, summary.blocks), (2, 6));
+
+        assert!(!repository.checkpoint_exists(idx(1)).await);
+        assert!(repository.checkpoint_exists(idx(0)).await);
+        assert!(repository
+            .get_blocks_in_range(idx(3), idx(8))
+            .await
+            .unwrap()
+            .is_empty());
+        let counts = repository.get_block_fetch_job_counts().await.unwrap();
+        assert_eq!(counts.queued, 1);
+        assert_eq!(
+            repository
+                .claim_block_fetch(unix_timestamp())
+                .await
+                .unwrap(),
+            Some(idx(0))
+        );
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::CheckpointIngested)
+                .await
+                .unwrap(),
+            Some(idx(0))
+        );
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::BlockIngested)
+                .await
+                .unwrap(),
+            Some(idx(2))
+        );
+    }
+
+    async fn cursor(repository: &impl Repository, cursor: SyncCursor) -> Option<i64> {
+        repository.get_sync_cursor(cursor).await.unwrap()
+    }
+
+    /// Checkpoints with a recorded reorg event, in ascending order
+    async fn reorged_checkpoints(repository: &impl Repository) -> Vec<u64> {
+        let events = repository.get_paginated_reorg_events(1, 10).await.unwrap();
+        let mut reorged: Vec<u64> = events
+            .items
+            .into_iter()
+            .map(|event| event.checkpoint_idx)
+            .collect();
+        reorged.sort_unstable();
+        reorged
+    }
+
+    async fn deletes_a_range_of_checkpoints_and_rewinds_the_cursors<R: TestRepository>() {
+        let repository = R::open().await;
+        index_three_checkpoints(&repository).await;
+        repository
+            .insert_bridge_intents(idx(0), idx(8), Vec::new())
+            .await
+            .unwrap();
+
+        let summary = repository
+            .delete_checkpoints(idx(1), Some(idx(1)), false)
+            .await
+            .unwrap();
+        assert_eq!((summary.checkpoints, summary.blocks), (1, 3));
+        assert!(repository.checkpoint_exists(idx(0)).await);
+        assert!(!repository.checkpoint_exists(idx(1)).await);
+        assert!(repository.checkpoint_exists(idx(2)).await);
+        assert!(repository
+            .get_blocks_in_range(idx(3), idx(5))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            repository
+                .get_blocks_in_range(idx(6), idx(8))
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+        // the ingestion cursors stay on the checkpoints and blocks after the gap, the bridge
+        // cursor goes back before it so that the blocks are scanned again once refetched
+        assert_eq!(
+            cursor(&repository, SyncCursor::CheckpointIngested).await,
+            Some(idx(2))
+        );
+        assert_eq!(
+            cursor(&repository, SyncCursor::BlockIngested).await,
+            Some(idx(8))
+        );
+        assert_eq!(
+            cursor(&repository, SyncCursor::BridgeScanned).await,
+            Some(idx(2))
+        );
+
+        repository
+            .delete_checkpoints(idx(2), None, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            cursor(&repository, SyncCursor::CheckpointIngested).await,
+            Some(idx(0))
+        );
+        assert_eq!(
+            cursor(&repository, SyncCursor::BlockIngested).await,
+            Some(idx(2))
+        );
+        assert_eq!(
+            cursor(&repository, SyncCursor::BridgeScanned).await,
+            Some(idx(2))
+        );
+    }
+
+    async fn keeps_the_reorg_events_of_deleted_checkpoints_unless_asked<R: TestRepository>() {
+        let repository = R::open().await;
+        for i in 0..3 {
+            repository
+                .insert_checkpoint(
+                    checkpoint(i, (3 * i, 3 * i + 2), "confirmed", "aa"),
+                    &Events::default(),
+                )
+                .await;
+        }
+        for i in [0, 2] {
+            let reconciliation = repository
+                .reconcile_checkpoint(
+                    idx(i),
+                    checkpoint(i, (3 * i, 3 * i + 2), "confirmed", "bb"),
+                    &Events::default(),
+                )
+                .await
+                .unwrap();
+            assert!(matches!(
+                reconciliation,
+                CheckpointReconciliation::Reorged(_)
+            ));
+        }
+
+        let summary = repository
+            .delete_checkpoints(idx(2), None, false)
+            .await
+            .unwrap();
+        assert_eq!((summary.checkpoints, summary.reorg_events), (1, 0));
+        assert_eq!(reorged_checkpoints(&repository).await, [0, 2]);
+
+        let summary = repository
+            .delete_checkpoints(idx(1), None, true)
+            .await
+            .unwrap();
+        assert_eq!((summary.checkpoints, summary.reorg_events), (1, 1));
+        assert_eq!(reorged_checkpoints(&repository).await, [0]);
+    }
+
+    fn hooks() -> Events {
+        Events {
+            urls: vec!["http://a".to_string(), "http://b".to_string()],
+        }
+    }
+
+    /// Event ids of the pending deliveries to `url`, in queue order
+    async fn pending_events(repository: &impl Repository, url: &str) -> Vec<String> {
+        repository
+            .get_pending_webhook_deliveries(url, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|delivery| delivery.event_id)
+            .collect()
+    }
+
+    async fn queues_the_events_of_a_checkpoint_with_it<R: TestRepository>() {
+        let repository = R::open().await;
+        repository
+            .insert_checkpoint(checkpoint(0, (0, 4), "pending", "-"), &hooks())
+            .await;
+        // checkpoint 1 is missing, checkpoint 2 is refused along with its event
+        repository
+            .insert_checkpoint(checkpoint(2, (10, 14), "pending", "-"), &hooks())
+            .await;
+
+        for url in ["http://a", "http://b"] {
+            assert_eq!(pending_events(&repository, url).await, ["created-0"]);
+        }
+        assert!(!repository.checkpoint_exists(idx(2)).await);
+    }
+
+    async fn prunes_the_deliveries_acknowledged_before_the_cutoff<R: TestRepository>() {
+        let repository = R::open().await;
+        repository
+            .insert_checkpoint(checkpoint(0, (0, 4), "pending", "-"), &hooks())
+            .await;
+        let delivered = repository
+            .get_pending_webhook_deliveries("http://a", 10)
+            .await
+            .unwrap()[0]
+            .id;
+        repository.mark_webhook_delivered(delivered).await.unwrap();
+
+        let now = crate::services::utils::unix_timestamp();
+        assert_eq!(
+            repository.prune_webhook_deliveries(now - 60).await.unwrap(),
+            0
+        );
+        assert_eq!(
+            repository.prune_webhook_deliveries(now + 60).await.unwrap(),
+            1
+        );
+        let counts = repository.get_webhook_delivery_counts().await.unwrap();
+        assert_eq!((counts.pending, counts.delivered), (1, 0));
+        assert_eq!(pending_events(&repository, "http://b").await, ["created-0"]);
+    }
+
+    repository_tests!(
+        claims_block_fetch_jobs_in_checkpoint_order,
+        enqueues_only_missing_or_failed_block_fetch_jobs,
+        retries_failed_block_fetch_jobs_after_a_growing_delay,
+        refuses_a_block_that_does_not_follow_the_stored_ones,
+        moves_the_cursors_with_the_writes,
+        deletes_checkpoints_with_their_blocks_and_jobs,
+        deletes_a_range_of_checkpoints_and_rewinds_the_cursors,
+        keeps_the_reorg_events_of_deleted_checkpoints_unless_asked,
+        queues_the_events_of_a_checkpoint_with_it,
+        prunes_the_deliveries_acknowledged_before_the_cutoff,
+    );
+
+    /// The checkpoint and its outbox rows are one transaction: a checkpoint whose events
+    /// cannot be queued is not stored either
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn drops_the_checkpoint_when_its_events_cannot_be_queued() {
+        use sea_orm::ConnectionTrait;
+
+        let repository = crate::repository::sql::SqlRepository::open().await;
+        repository
+            .connection()
+            .execute_unprepared("DROP TABLE webhook_deliveries")
+            .await
+            .unwrap();
+
+        repository
+            .insert_checkpoint(checkpoint(0, (0, 4), "pending", "-"), &hooks())
+            .await;
+
+        assert!(!repository.checkpoint_exists(idx(0)).await);
+        assert_eq!(
+            repository
+                .get_sync_cursor(SyncCursor::CheckpointIngested)
+                .await
+                .unwrap(),
+            None
+        );
+        // without events to queue the same checkpoint is stored
+        repository
+            .insert_checkpoint(checkpoint(0, (0, 4), "pending", "-"), &Events::default())
+            .await;
+        assert!(repository.checkpoint_exists(idx(0)).await);
+    }
+}
@@ -0,0 +1,482 @@
+use super::{
+    BlockFetchJobRepository, BlockRepository, BridgeIntentRepository, CheckpointEvents,
+    CheckpointReconciliation, CheckpointRepository, EvmBlockRepository, IntegrityRepository,
+    L1BlockRepository, MaintenanceRepository, PurgeSummary, SyncStateRepository,
+    WebhookDeliveryRepository,
+};
+use crate::services::{
+    block_fetch_job_service::BlockFetchJobService, block_service::BlockService,
+    bridge_duty_service::BridgeDutyService, bridge_intent_service::BridgeIntentService,
+    checkpoint_service::CheckpointService, evm_block_service::EvmBlockService,
+    integrity_service::IntegrityService, l1_block_service::L1BlockService,
+    maintenance_service::MaintenanceService, pagination::PaginatedData,
+    sync_state_service::SyncStateService, webhook_delivery_service::WebhookDeliveryService,
+};
+use async_trait::async_trait;
+use model::block::{self, RpcBlockHeader};
+use model::block_fetch_job::BlockFetchJobCounts;
+use model::bridge_duty;
+use model::bridge_intent;
+use model::checkpoint::{self, RpcCheckpointInfo, RpcCheckpointInfoCheckpointExp};
+use model::evm_block;
+use model::integrity::IntegrityReport;
+use model::l1_block::{self, IncompleteL1Range};
+use model::reorg_event::RpcReorgEvent;
+use model::sync_state::{self, IndexRange, SyncCursor};
+use model::webhook_delivery::{self, NewWebhookEvent, WebhookDeliveryCounts};
+use sea_orm::{DatabaseConnection, DbErr};
+
+/// Repository backed by a sea-orm connection (PostgreSQL or SQLite)
+#[derive(Clone)]
+pub struct SqlRepository {
+    db: DatabaseConnection,
+}
+
+impl SqlRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub fn connection(&self) -> &DatabaseConnection {
+        &self.db
+    }
+
+    fn checkpoints(&self) -> CheckpointService<'_> {
+        CheckpointService::new(&self.db)
+    }
+
+    fn blocks(&self) -> BlockService<'_> {
+        BlockService::new(&self.db)
+    }
+
+    fn l1_blocks(&self) -> L1BlockService<'_> {
+        L1BlockService::new(&self.db)
+    }
+
+    fn evm_blocks(&self) -> EvmBlockService<'_> {
+        EvmBlockService::new(&self.db)
+    }
+
+    fn bridge_intents(&self) -> BridgeIntentService<'_> {
+        BridgeIntentService::new(&self.db)
+    }
+
+    fn bridge_duties(&self) -> BridgeDutyService<'_> {
+        BridgeDutyService::new(&self.db)
+    }
+
+    fn block_fetch_jobs(&self) -> BlockFetchJobService<'_> {
+        BlockFetchJobService::new(&self.db)
+    }
+
+    fn webhook_deliveries(&self) -> WebhookDeliveryService<'_> {
+        WebhookDeliveryService::new(&self.db)
+    }
+
+    fn sync_state(&self) -> SyncStateService<'_> {
+        SyncStateService::new(&self.db)
+    }
+
+    fn integrity(&self) -> IntegrityService<'_> {
+        IntegrityService::new(&self.db)
+    }
+
+    fn maintenance(&self) -> MaintenanceService<'_> {
+        MaintenanceService::new(&self.db)
+    }
+}
+
+#[async_trait]
+impl CheckpointRepository for SqlRepository {
+    async fn checkpoint_exists(&self, idx: i64) -> bool {
+        self.checkpoints().checkpoint_exists(idx).await
+    }
+
+    async fn insert_checkpoint(
+        &self,
+        checkpoint: RpcCheckpointInfo,
+        events: &dyn CheckpointEvents,
+    ) {
+        self.checkpoints()
+            .insert_checkpoint(checkpoint, events)
+            .await
+    }
+
+    async fn get_checkpoint_by_idx(
+        &self,
+        idx: i64,
+    ) -> Result<Option<RpcCheckpointInfoCheckpointExp>, DbErr> {
+        self.checkpoints().get_checkpoint_by_idx(idx).await
+    }
+
+    async fn get_checkpoint_idx_by_block_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<i64>, DbErr> {
+        self.checkpoints()
+            .get_checkpoint_idx_by_block_hash(block_hash)
+            .await
+    }
+
+    async fn get_checkpoint_idx_by_block_height(
+        &self,
+        block_height: i64,
+    ) -> Result<Option<i64>, DbErr> {
+        self.checkpoints()
+            .get_checkpoint_idx_by_block_height(block_height)
+            .await
+    }
+
+    async fn get_checkpoint_idx_by_l2_height(&self, height: i64) -> Result<Option<i64>, DbErr> {
+        self.checkpoints()
+            .get_checkpoint_idx_by_l2_height(height)
+            .await
+    }
+
+    async fn get_paginated_checkpoints(
+        &self,
+        current_page: u64,
+        page_size: u64,
+        absolute_first_page: u64,
+        order: Option<&str>,
+    ) -> Result<PaginatedData<RpcCheckpointInfoCheckpointExp>, DbErr> {
+        self.checkpoints()
+            .get_paginated_checkpoints(current_page, page_size, absolute_first_page, order)
+            .await
+    }
+
+    async fn get_total_checkpoint_count(&self) -> u64 {
+        self.checkpoints().get_total_checkpoint_count().await
+    }
+
+    async fn get_checkpoint_counts_by_status(&self) -> Result<Vec<(String, u64)>, DbErr> {
+        self.checkpoints().get_checkpoint_counts_by_status().await
+    }
+
+    async fn get_checkpoints_in_range(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr> {
+        self.checkpoints().get_checkpoints_in_range(from, to).await
+    }
+
+    async fn get_latest_checkpoint_index(&self) -> Option<i64> {
+        self.checkpoints().get_latest_checkpoint_index().await
+    }
+
+    async fn get_checkpoint_idxs_by_status(&self, statuses: &[&str]) -> Result<Vec<i64>, DbErr> {
+        self.checkpoints()
+            .get_checkpoint_idxs_by_status(statuses)
+            .await
+    }
+
+    async fn get_stalled_checkpoints(
+        &self,
+        statuses: &[&str],
+        entered_before: i64,
+        limit: u64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr> {
+        self.checkpoints()
+            .get_stalled_checkpoints(statuses, entered_before, limit)
+            .await
+    }
+
+    async fn set_checkpoint_confirmed_at(
+        &self,
+        checkpoint_idx: i64,
+        txid: &str,
+        confirmed_at: i64,
+    ) -> Result<bool, DbErr> {
+        self.checkpoints()
+            .set_checkpoint_confirmed_at(checkpoint_idx, txid, confirmed_at)
+            .await
+    }
+
+    async fn get_checkpoint_lifecycles_since(
+        &self,
+        since: i64,
+    ) -> Result<Vec<checkpoint::Model>, DbErr> {
+        self.checkpoints()
+            .get_checkpoint_lifecycles_since(since)
+            .await
+    }
+
+    async fn reconcile_checkpoint(
+        &self,
+        checkpoint_idx: i64,
+        checkpoint_from_rpc: RpcCheckpointInfo,
+        events: &dyn CheckpointEvents,
+    ) -> Result<CheckpointReconciliation, DbErr> {
+        self.checkpoints()
+            .reconcile_checkpoint(checkpoint_idx, checkpoint_from_rpc, events)
+            .await
+    }
+
+    async fn get_paginated_reorg_events(
+        &self,
+        current_page: u64,
+        page_size: u64,
+    ) -> Result<PaginatedData<RpcReorgEvent>, DbErr> {
+        self.checkpoints()
+            .get_paginated_reorg_events(current_page, page_size)
+            .await
+    }
+}
+
+#[async_trait]
+impl BlockRepository for SqlRepository {
+    async fn insert_block(
+        &self,
+        rpc_block_header: RpcBlockHeader,
+        checkpoint_idx: i64,
+    ) -> Result<(), DbErr> {
+        self.blocks()
+            .insert_block(rpc_block_header, checkpoint_idx)
+            .await
+    }
+
+    async fn get_latest_block_index(&self) -> Option<i64> {
+        self.blocks().get_latest_block_index().await
+    }
+
+    async fn can_insert_block(&self, height: i64) -> bool {
+        self.blocks().can_insert_block(height).await
+    }
+
+    async fn get_blocks_in_range(&self, from: i64, to: i64) -> Result<Vec<block::Model>, DbErr> {
+        self.blocks().get_blocks_in_range(from, to).await
+    }
+
+    async fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<block::Model>, DbErr> {
+        self.blocks().get_block_by_hash(block_hash).await
+    }
+}
+
+#[async_trait]
+impl L1BlockRepository for SqlRepository {
+    async fn insert_l1_blocks(&self, blocks: Vec<l1_block::Model>) -> Result<u64, DbErr> {
+        self.l1_blocks().insert(blocks).await
+    }
+
+    async fn get_incomplete_l1_ranges(&self, limit: u64) -> Result<Vec<IncompleteL1Range>, DbErr> {
+        self.l1_blocks().incomplete_ranges(limit).await
+    }
+
+    async fn get_l1_block_heights_in_range(&self, from: i64, to: i64) -> Result<Vec<i64>, DbErr> {
+        self.l1_blocks().heights_in_range(from, to).await
+    }
+
+    async fn get_l1_blocks_without_timestamp(
+        &self,
+        limit: u64,
+    ) -> Result<Vec<l1_block::Model>, DbErr> {
+        self.l1_blocks().without_timestamp(limit).await
+    }
+
+    async fn set_l1_block_timestamp(&self, height: i64, timestamp: i64) -> Result<(), DbErr> {
+        self.l1_blocks().set_timestamp(height, timestamp).await
+    }
+
+    async fn get_l1_block_by_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<l1_block::Model>, DbErr> {
+        self.l1_blocks().find_by_hash(block_hash).await
+    }
+
+    async fn get_l1_block_by_height(&self, height: i64) -> Result<Option<l1_block::Model>, DbErr> {
+        self.l1_blocks().find_by_height(height).await
+    }
+}
+
+#[async_trait]
+impl EvmBlockRepository for SqlRepository {
+    async fn insert_evm_blocks(&self, blocks: Vec<evm_block::Model>) -> Result<u64, DbErr> {
+        self.evm_blocks().insert(blocks).await
+    }
+
+    async fn get_blocks_without_evm_block(
+        &self,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<block::Model>, DbErr> {
+        self.evm_blocks().unlinked_blocks(after, limit).await
+    }
+
+    async fn get_evm_block_by_number(
+        &self,
+        number: i64,
+    ) -> Result<Option<evm_block::Model>, DbErr> {
+        self.evm_blocks().find_by_number(number).await
+    }
+
+    async fn get_evm_block_by_hash(
+        &self,
+        block_hash: &str,
+    ) -> Result<Option<evm_block::Model>, DbErr> {
+        self.evm_blocks().find_by_hash(block_hash).await
+    }
+}
+
+#[async_trait]
+impl BridgeIntentRepository for SqlRepository {
+    async fn insert_bridge_intents(
+        &self,
+        from: i64,
+        to: i64,
+        intents: Vec<bridge_intent::Model>,
+    ) -> Result<u64, DbErr> {
+        self.bridge_intents().insert(from, to, intents).await
+    }
+
+    async fn get_bridge_intents_by_txid(
+        &self,
+        txid: &str,
+    ) -> Result<Vec<bridge_intent::Model>, DbErr> {
+        self.bridge_intents().find_by_txid(txid).await
+    }
+
+    async fn get_bridge_intents_in_range(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<bridge_intent::Model>, DbErr> {
+        self.bridge_intents().in_range(from, to).await
+    }
+
+    async fn insert_bridge_duties(
+        &self,
+        operator_idx: i32,
+        next_index: i64,
+        duties: Vec<bridge_duty::Model>,
+    ) -> Result<u64, DbErr> {
+        self.bridge_duties()
+            .insert(operator_idx, next_index, duties)
+            .await
+    }
+
+    async fn get_bridge_duty_cursor(&self, operator_idx: i32) -> Result<Option<i64>, DbErr> {
+        self.bridge_duties().next_index(operator_idx).await
+    }
+
+    async fn get_bridge_duties_by_destination(
+        &self,
+        destination: &str,
+    ) -> Result<Vec<bridge_duty::Model>, DbErr> {
+        self.bridge_duties().find_by_destination(destination).await
+    }
+}
+
+#[async_trait]
+impl BlockFetchJobRepository for SqlRepository {
+    async fn enqueue_block_fetch(&self, idx: i64) -> Result<(), DbErr> {
+        self.block_fetch_jobs().enqueue(idx).await
+    }
+
+    async fn claim_block_fetch(&self, now: i64) -> Result<Option<i64>, DbErr> {
+        self.block_fetch_jobs().claim_next(now).await
+    }
+
+    async fn finish_block_fetch(&self, idx: i64, error: Option<String>) -> Result<(), DbErr> {
+        self.block_fetch_jobs().finish(idx, error).await
+    }
+
+    async fn requeue_block_fetch(&self, idx: i64) -> Result<(), DbErr> {
+        self.block_fetch_jobs().requeue(idx).await
+    }
+
+    async fn requeue_running_block_fetches(&self) -> Result<u64, DbErr> {
+        self.block_fetch_jobs().requeue_running().await
+    }
+
+    async fn get_block_fetch_job_counts(&self) -> Result<BlockFetchJobCounts, DbErr> {
+        self.block_fetch_jobs().counts().await
+    }
+}
+
+#[async_trait]
+impl WebhookDeliveryRepository for SqlRepository {
+    async fn enqueue_webhook_event(
+        &self,
+        event: NewWebhookEvent,
+        urls: &[String],
+    ) -> Result<(), DbErr> {
+        self.webhook_deliveries().enqueue(event, urls).await
+    }
+
+    async fn get_pending_webhook_deliveries(
+        &self,
+        url: &str,
+        limit: u64,
+    ) -> Result<Vec<webhook_delivery::Model>, DbErr> {
+        self.webhook_deliveries().pending(url, limit).await
+    }
+
+    async fn mark_webhook_delivered(&self, id: i64) -> Result<(), DbErr> {
+        self.webhook_deliveries().mark_delivered(id).await
+    }
+
+    async fn mark_webhook_failed(
+        &self,
+        id: i64,
+        error: String,
+        next_attempt_at: i64,
+    ) -> Result<(), DbErr> {
+        self.webhook_deliveries()
+            .mark_failed(id, error, next_attempt_at)
+            .await
+    }
+
+    async fn get_webhook_delivery_counts(&self) -> Result<WebhookDeliveryCounts, DbErr> {
+        self.webhook_deliveries().counts().await
+    }
+
+    async fn prune_webhook_deliveries(&self, delivered_before: i64) -> Result<u64, DbErr> {
+        self.webhook_deliveries().prune(delivered_before).await
+    }
+}
+
+#[async_trait]
+impl SyncStateRepository for SqlRepository {
+    async fn get_sync_cursor(&self, cursor: SyncCursor) -> Result<Option<i64>, DbErr> {
+        self.sync_state().get_cursor(cursor).await
+    }
+
+    async fn get_sync_cursors(&self) -> Result<Vec<sync_state::Model>, DbErr> {
+        self.sync_state().get_cursors().await
+    }
+
+    async fn declare_index_range(&self, start: i64, end: Option<i64>) -> Result<(), DbErr> {
+        self.sync_state().declare_index_range(start, end).await
+    }
+
+    async fn get_index_range(&self) -> Result<IndexRange, DbErr> {
+        self.sync_state().get_index_range().await
+    }
+}
+
+#[async_trait]
+impl IntegrityRepository for SqlRepository {
+    async fn get_integrity_report(&self) -> Result<IntegrityReport, DbErr> {
+        self.integrity().get_integrity_report().await
+    }
+}
+
+#[async_trait]
+impl MaintenanceRepository for SqlRepository {
+    async fn delete_checkpoints(
+        &self,
+        from: i64,
+        to: Option<i64>,
+        with_reorg_events: bool,
+    ) -> Result<PurgeSummary, DbErr> {
+        self.maintenance()
+            .delete_checkpoints(from, to, with_reorg_events)
+            .await
+    }
+
+    async fn delete_checkpoint_blocks(&self, idx: i64) -> Result<u64, DbErr> {
+        self.maintenance().delete_checkpoint_blocks(idx).await
+    }
+}
@@ -0,0 +1,154 @@
+use super::utils::unix_timestamp;
+use model::block_fetch_job::{
+    retry_delay, ActiveModel, BlockFetchJobCounts, Column, Entity as BlockFetchJob, JobState,
+};
+use sea_orm::{
+    prelude::*, sea_query::OnConflict, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    Order, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+/// Persisted queue of the checkpoints whose blocks are to be fetched, one job per checkpoint
+pub struct BlockFetchJobService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> BlockFetchJobService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Queue the blocks of checkpoint `idx` for fetching. A job is created when there is none,
+    /// a failed job is queued again with its attempts and retry time, and nothing changes
+    /// while the job is pending or done.
+    pub async fn enqueue(&self, idx: i64) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        match BlockFetchJob::find_by_id(idx).one(&txn).await? {
+            None => {
+                let now = unix_timestamp();
+                let job = ActiveModel {
+                    checkpoint_idx: Set(idx),
+                    state: Set(JobState::Queued.state().to_string()),
+                    attempts: Set(0),
+                    last_error: Set(None),
+                    next_attempt_at: Set(0),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                };
+                BlockFetchJob::insert(job)
+                    .on_conflict(
+                        OnConflict::column(Column::CheckpointIdx)
+                            .do_nothing()
+                            .to_owned(),
+                    )
+                    .exec_without_returning(&txn)
+                    .await?;
+            }
+            Some(job) if JobState::from_state(&job.state) == Some(JobState::Failed) => {
+                set_state(&txn, idx, JobState::Queued).await?
+            }
+            Some(_) => (),
+        }
+        txn.commit().await
+    }
+
+    /// Move the job with the lowest checkpoint that is queued, or failed and due again at
+    /// `now`, to running and return its checkpoint
+    pub async fn claim_next(&self, now: i64) -> Result<Option<i64>, DbErr> {
+        let txn = self.db.begin().await?;
+        let Some(job) = BlockFetchJob::find()
+            .filter(Column::State.is_in([JobState::Queued.state(), JobState::Failed.state()]))
+            .filter(Column::NextAttemptAt.lte(now))
+            .order_by(Column::CheckpointIdx, Order::Asc)
+            .limit(1)
+            .one(&txn)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let claimed = ActiveModel {
+            checkpoint_idx: Set(job.checkpoint_idx),
+            state: Set(JobState::Running.state().to_string()),
+            attempts: Set(job.attempts.saturating_add(1)),
+            updated_at: Set(unix_timestamp()),
+            ..Default::default()
+        };
+        BlockFetchJob::update(claimed).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(Some(job.checkpoint_idx))
+    }
+
+    /// Mark the job of checkpoint `idx` done, or failed with `error` and due again after
+    /// [`retry_delay`] for its attempts
+    pub async fn finish(&self, idx: i64, error: Option<String>) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        let Some(job) = BlockFetchJob::find_by_id(idx).one(&txn).await? else {
+            return Ok(());
+        };
+        let now = unix_timestamp();
+        let (state, next_attempt_at) = match error {
+            Some(_) => (JobState::Failed, now + retry_delay(job.attempts)),
+            None => (JobState::Done, 0),
+        };
+        let finished = ActiveModel {
+            checkpoint_idx: Set(idx),
+            state: Set(state.state().to_string()),
+            last_error: Set(error),
+            next_attempt_at: Set(next_attempt_at),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        BlockFetchJob::update(finished).exec(&txn).await?;
+        txn.commit().await
+    }
+
+    /// Put the job of checkpoint `idx` back in the queue without counting it as failed
+    pub async fn requeue(&self, idx: i64) -> Result<(), DbErr> {
+        set_state(self.db, idx, JobState::Queued).await
+    }
+
+    /// Put every running job back in the queue, returns how many there were
+    pub async fn requeue_running(&self) -> Result<u64, DbErr> {
+        Ok(BlockFetchJob::update_many()
+            .col_expr(Column::State, Expr::value(JobState::Queued.state()))
+            .col_expr(Column::UpdatedAt, Expr::value(unix_timestamp()))
+            .filter(Column::State.eq(JobState::Running.state()))
+            .exec(self.db)
+            .await?
+            .rows_affected)
+    }
+
+    /// Number of jobs in each state
+    pub async fn counts(&self) -> Result<BlockFetchJobCounts, DbErr> {
+        let rows = BlockFetchJob::find()
+            .select_only()
+            .column(Column::State)
+            .column_as(Column::CheckpointIdx.count(), "count")
+            .group_by(Column::State)
+            .into_tuple::<(String, i64)>()
+            .all(self.db)
+            .await?;
+
+        let mut counts = BlockFetchJobCounts::default();
+        for (state, count) in rows {
+            let count = count as u64;
+            match JobState::from_state(&state) {
+                Some(JobState::Queued) => counts.queued = count,
+                Some(JobState::Running) => counts.running = count,
+                Some(JobState::Done) => counts.done = count,
+                Some(JobState::Failed) => counts.failed = count,
+                None => (),
+            }
+        }
+        Ok(counts)
+    }
+}
+
+async fn set_state<C: ConnectionTrait>(conn: &C, idx: i64, state: JobState) -> Result<(), DbErr> {
+    BlockFetchJob::update_many()
+        .col_expr(Column::State, Expr::value(state.state()))
+        .col_expr(Column::UpdatedAt, Expr::value(unix_timestamp()))
+        .filter(Column::CheckpointIdx.eq(idx))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
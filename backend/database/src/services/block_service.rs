@@ -1,6 +1,13 @@
-use model::block::{ActiveModel as BlockActiveModel, Entity as Block, RpcBlockHeader};
+use super::sync_state_service::{advance_cursor, index_start_height};
+use model::block::{
+    ActiveModel as BlockActiveModel, Entity as Block, Model as BlockModel, RpcBlockHeader,
+};
 use model::pgu64::PgU64;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, Set};
+use model::sync_state::SyncCursor;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, Order, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
 use tracing::error;
 
 /// Wrapper around the database connection
@@ -13,7 +20,12 @@ impl<'a> BlockService<'a> {
         Self { db }
     }
 
-    pub async fn insert_block(&self, rpc_block_header: RpcBlockHeader, checkpoint_idx: i64) {
+    /// Insert a block, an error if its height does not follow the stored blocks
+    pub async fn insert_block(
+        &self,
+        rpc_block_header: RpcBlockHeader,
+        checkpoint_idx: i64,
+    ) -> Result<(), DbErr> {
         // Use `From` to convert `RpcBlockHeader` into an `ActiveModel`
         let mut active_model: BlockActiveModel = rpc_block_header.into();
 
@@ -23,33 +35,35 @@ impl<'a> BlockService<'a> {
         // If block already exists locally do nothing
         if self.block_exists(height).await {
             tracing::debug!("Block already exists, height={}", PgU64::i64_to_u64(height));
-            return;
+            return Ok(());
         }
         // ensure that blocks exist incrementally and continuously
-        let can_insert_block = self.can_insert_block(height).await;
-        if !can_insert_block {
-            panic!("last_block_height does not match the expected height!");
+        if !self.can_insert_block(height).await {
+            return Err(block_discontinuity(height));
         }
 
         active_model.checkpoint_idx = Set(checkpoint_idx);
+        self.insert_block_and_advance_cursor(active_model, height)
+            .await?;
+        metrics::counter!("explorer_blocks_ingested_total").increment(1);
+        tracing::debug!(
+            "Block inserted & indexed successfully: height={}, block_hash={}",
+            PgU64::i64_to_u64(height),
+            block_id
+        );
+        Ok(())
+    }
 
-        // Insert the block using the Entity::insert() method
-        match Block::insert(active_model).exec(self.db).await {
-            Ok(_) => {
-                tracing::debug!(
-                    "Block inserted & indexed successfully: height={}, block_hash={}",
-                    PgU64::i64_to_u64(height),
-                    block_id
-                );
-            }
-            Err(err) => {
-                tracing::error!(
-                    "Error inserting block with height {}: {:?}",
-                    PgU64::i64_to_u64(height),
-                    err
-                );
-            }
-        }
+    /// Insert the block and move the block cursor in the same transaction
+    async fn insert_block_and_advance_cursor(
+        &self,
+        active_model: BlockActiveModel,
+        height: i64,
+    ) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        Block::insert(active_model).exec(&txn).await?;
+        advance_cursor(&txn, SyncCursor::BlockIngested, height).await?;
+        txn.commit().await
     }
 
     /// Get the latest checkpoint index stored in the database
@@ -72,6 +86,23 @@ impl<'a> BlockService<'a> {
         }
     }
 
+    /// Get the blocks from height `from` to `to` included, in ascending order
+    pub async fn get_blocks_in_range(&self, from: i64, to: i64) -> Result<Vec<BlockModel>, DbErr> {
+        Block::find()
+            .filter(model::block::Column::Height.between(from, to))
+            .order_by(model::block::Column::Height, Order::Asc)
+            .all(self.db)
+            .await
+    }
+
+    /// Get the block with hash `block_hash`
+    pub async fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<BlockModel>, DbErr> {
+        Block::find()
+            .filter(model::block::Column::BlockHash.eq(block_hash))
+            .one(self.db)
+            .await
+    }
+
     async fn block_exists(&self, height: i64) -> bool {
         Block::find()
             .filter(model::block::Column::Height.eq(height))
@@ -82,8 +113,15 @@ impl<'a> BlockService<'a> {
     }
 
     async fn prev_block_exists(&self, height: i64) -> bool {
-        if height == i64::MIN {
-            // return true for the genesis block
+        let index_start = match index_start_height(self.db).await {
+            Ok(index_start) => index_start,
+            Err(err) => {
+                error!("Failed to read the start of the index: {:?}", err);
+                return false;
+            }
+        };
+        if height == index_start {
+            // return true for the first block of the index
             return true;
         }
         self.block_exists(height - 1).await
@@ -93,6 +131,7 @@ impl<'a> BlockService<'a> {
     /// The conditions it should meet are:
     ///     1. The block table should be empty
     ///     2. or, the block table should have the previous block
+    ///     3. or, it is the first block of the index
     pub async fn can_insert_block(&self, height: i64) -> bool {
         if self.get_latest_block_index().await.is_none() {
             return true;
@@ -100,3 +139,11 @@ impl<'a> BlockService<'a> {
         self.prev_block_exists(height).await
     }
 }
+
+/// Error of a block whose previous height is not stored
+pub(crate) fn block_discontinuity(height: i64) -> DbErr {
+    DbErr::Custom(format!(
+        "Block {} does not follow the stored blocks",
+        PgU64::i64_to_u64(height)
+    ))
+}
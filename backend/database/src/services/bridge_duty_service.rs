@@ -0,0 +1,78 @@
+use super::utils::unix_timestamp;
+use model::bridge_duty::{ActiveModel, Column, Entity as BridgeDuty, Model};
+use model::bridge_duty_cursor::{
+    ActiveModel as CursorActiveModel, Column as CursorColumn, Entity as BridgeDutyCursor,
+};
+use sea_orm::{
+    prelude::*, sea_query::OnConflict, DatabaseConnection, DbErr, EntityTrait, Order, QueryOrder,
+    Set, TransactionTrait,
+};
+
+/// Duties of the bridge operators, keyed by operator, kind and outpoint, and the index each
+/// operator's duties were listed up to
+pub struct BridgeDutyService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> BridgeDutyService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Store the duties of operator `operator_idx` and move its cursor to `next_index` in the
+    /// same transaction. A duty listed again keeps the time it was first seen.
+    pub async fn insert(
+        &self,
+        operator_idx: i32,
+        next_index: i64,
+        duties: Vec<Model>,
+    ) -> Result<u64, DbErr> {
+        let txn = self.db.begin().await?;
+        let inserted = if duties.is_empty() {
+            0
+        } else {
+            BridgeDuty::insert_many(duties.into_iter().map(Into::<ActiveModel>::into))
+                .on_conflict(
+                    OnConflict::columns([Column::OperatorIdx, Column::Kind, Column::Outpoint])
+                        .update_columns([Column::Amount, Column::Destination, Column::ExecDeadline])
+                        .to_owned(),
+                )
+                .exec_without_returning(&txn)
+                .await?
+        };
+        let cursor = CursorActiveModel {
+            operator_idx: Set(operator_idx),
+            next_index: Set(next_index),
+            updated_at: Set(unix_timestamp()),
+        };
+        BridgeDutyCursor::insert(cursor)
+            .on_conflict(
+                OnConflict::column(CursorColumn::OperatorIdx)
+                    .update_columns([CursorColumn::NextIndex, CursorColumn::UpdatedAt])
+                    .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(inserted)
+    }
+
+    /// `start_index` of the next listing of operator `operator_idx`, `None` before the first
+    pub async fn next_index(&self, operator_idx: i32) -> Result<Option<i64>, DbErr> {
+        Ok(BridgeDutyCursor::find_by_id(operator_idx)
+            .one(self.db)
+            .await?
+            .map(|cursor| cursor.next_index))
+    }
+
+    /// Duties paying to or crediting `destination`, by operator
+    pub async fn find_by_destination(&self, destination: &str) -> Result<Vec<Model>, DbErr> {
+        BridgeDuty::find()
+            .filter(Column::Destination.eq(destination))
+            .order_by(Column::OperatorIdx, Order::Asc)
+            .order_by(Column::Kind, Order::Asc)
+            .order_by(Column::Outpoint, Order::Asc)
+            .all(self.db)
+            .await
+    }
+}
@@ -0,0 +1,71 @@
+use super::sync_state_service::advance_cursor;
+use model::bridge_intent::{ActiveModel, Column, Entity as BridgeIntent, Model};
+use model::sync_state::SyncCursor;
+use sea_orm::{
+    prelude::*, sea_query::OnConflict, DatabaseConnection, DbErr, EntityTrait, Order, QueryOrder,
+    TransactionTrait,
+};
+
+/// Bridge deposits and withdrawals of the L2 blocks, keyed by block and position
+pub struct BridgeIntentService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> BridgeIntentService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Store the intents of the blocks scanned from height `from` to `to` included and move
+    /// the bridge cursor to `to` in the same transaction. The intents stored for these blocks
+    /// are replaced, including those a block scanned again no longer has.
+    pub async fn insert(&self, from: i64, to: i64, intents: Vec<Model>) -> Result<u64, DbErr> {
+        let txn = self.db.begin().await?;
+        BridgeIntent::delete_many()
+            .filter(Column::L2Height.between(from, to))
+            .exec(&txn)
+            .await?;
+        let inserted = if intents.is_empty() {
+            0
+        } else {
+            BridgeIntent::insert_many(intents.into_iter().map(Into::<ActiveModel>::into))
+                .on_conflict(
+                    OnConflict::columns([Column::L2Height, Column::Kind, Column::Position])
+                        .update_columns([
+                            Column::L2BlockHash,
+                            Column::Amount,
+                            Column::Destination,
+                            Column::Txid,
+                            Column::DepositIdx,
+                        ])
+                        .to_owned(),
+                )
+                .exec_without_returning(&txn)
+                .await?
+        };
+        advance_cursor(&txn, SyncCursor::BridgeScanned, to).await?;
+        txn.commit().await?;
+        Ok(inserted)
+    }
+
+    /// Withdrawals requested by transaction `txid`, in block order
+    pub async fn find_by_txid(&self, txid: &str) -> Result<Vec<Model>, DbErr> {
+        BridgeIntent::find()
+            .filter(Column::Txid.eq(txid))
+            .order_by(Column::L2Height, Order::Asc)
+            .order_by(Column::Position, Order::Asc)
+            .all(self.db)
+            .await
+    }
+
+    /// Intents of the blocks from height `from` to `to` included, in block order
+    pub async fn in_range(&self, from: i64, to: i64) -> Result<Vec<Model>, DbErr> {
+        BridgeIntent::find()
+            .filter(Column::L2Height.between(from, to))
+            .order_by(Column::L2Height, Order::Asc)
+            .order_by(Column::Kind, Order::Asc)
+            .order_by(Column::Position, Order::Asc)
+            .all(self.db)
+            .await
+    }
+}
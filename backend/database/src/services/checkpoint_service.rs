@@ -1,17 +1,94 @@
-use super::utils::resolve_order;
-use crate::services::pagination::PaginatedData;
+use super::sync_state_service::{advance_cursor, index_start_checkpoint, set_cursor};
+use super::utils::{resolve_order, unix_timestamp};
+use super::webhook_delivery_service::enqueue_deliveries;
+use crate::services::pagination::{page_offset, PaginatedData};
 use model::pgu64::PgU64;
 use model::{
     block::Entity as Block,
     checkpoint::{
-        ActiveModel, Entity as Checkpoint, RpcCheckpointInfo, RpcCheckpointInfoCheckpointExp,
+        ActiveModel, CheckpointState, Entity as Checkpoint, Model, RpcCheckpointInfo,
+        RpcCheckpointInfoCheckpointExp, StateTransition,
     },
+    reorg_event::{
+        ActiveModel as ReorgEventActiveModel, Entity as ReorgEvent, Model as ReorgEventModel,
+        RpcReorgEvent,
+    },
+    sync_state::SyncCursor,
+    webhook_delivery::NewWebhookEvent,
 };
 use sea_orm::{
-    prelude::*, ColumnTrait, DatabaseConnection, EntityTrait, Order, QueryFilter, QueryOrder,
-    QuerySelect,
+    prelude::*, sea_query::Expr, ColumnTrait, Condition, DatabaseConnection, EntityTrait, NotSet,
+    Order, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait, TryIntoModel,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// Outcome of reconciling a stored checkpoint with the fullnode's current view of it
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckpointReconciliation {
+    /// Status and txid already match the fullnode
+    Unchanged,
+    /// The checkpoint moved forward (e.g. pending -> confirmed) or got its first txid
+    Updated { before: Model, after: Model },
+    /// The checkpoint moved backward or its txid was replaced, which only an L1 reorg can do
+    Reorged(ReorgEventModel),
+    /// The fullnode reported a transition the checkpoint lifecycle does not allow, the stored
+    /// checkpoint was left untouched
+    Rejected {
+        from: CheckpointState,
+        to: CheckpointState,
+    },
+}
+
+/// Builds the webhook events of the checkpoint writes. They are queued in the transaction of
+/// the write, so that the events of a stored write are never lost and no event is queued for
+/// a write that failed.
+pub trait CheckpointEvents: Send + Sync {
+    /// URLs every event is queued for, no event is built without any
+    fn urls(&self) -> &[String];
+
+    /// Events of a checkpoint stored for the first time
+    fn created(&self, checkpoint: &Model) -> Vec<NewWebhookEvent>;
+
+    /// Events of the outcome of a reconciliation
+    fn reconciled(&self, reconciliation: &CheckpointReconciliation) -> Vec<NewWebhookEvent>;
+}
+
+/// How reconciling a stored checkpoint with the fullnode changes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CheckpointChange {
+    Unchanged,
+    Updated,
+    Reorged,
+    Rejected {
+        from: CheckpointState,
+        to: CheckpointState,
+    },
+}
+
+/// Classify moving `checkpoint` to `new_status`/`new_txid` according to the allowed
+/// [`CheckpointState`] transitions. Replacing an already known txid is a reorg as well.
+pub(crate) fn classify_change(
+    checkpoint: &Model,
+    new_status: &str,
+    new_txid: &str,
+) -> CheckpointChange {
+    if checkpoint.status == new_status && checkpoint.checkpoint_txid == new_txid {
+        return CheckpointChange::Unchanged;
+    }
+    let from = CheckpointState::from_status(&checkpoint.status);
+    let to = CheckpointState::from_status(new_status);
+    let txid_replaced = checkpoint.checkpoint_txid != "-" && checkpoint.checkpoint_txid != new_txid;
+
+    match from.transition_to(to) {
+        // a finalized checkpoint cannot be reorged, not even its txid
+        Some(_) if from.is_final() && txid_replaced => CheckpointChange::Rejected { from, to },
+        None => CheckpointChange::Rejected { from, to },
+        Some(StateTransition::Revert) => CheckpointChange::Reorged,
+        Some(_) if txid_replaced => CheckpointChange::Reorged,
+        Some(_) => CheckpointChange::Updated,
+    }
+}
+
 pub struct CheckpointService<'a> {
     pub db: &'a DatabaseConnection,
 }
@@ -30,12 +107,32 @@ impl<'a> CheckpointService<'a> {
             .unwrap_or(false)
     }
 
-    /// Insert a new checkpoint into the database
-    pub async fn insert_checkpoint(&self, checkpoint: RpcCheckpointInfo) {
+    /// Insert a new checkpoint into the database, along with its `checkpoint.created` events
+    pub async fn insert_checkpoint(
+        &self,
+        checkpoint: RpcCheckpointInfo,
+        events: &dyn CheckpointEvents,
+    ) {
         let idx: i64 = PgU64(checkpoint.idx).to_i64();
 
-        // for the first checkpoint, no need to check the previous checkpoint
-        if idx > i64::MIN {
+        let index_start = match index_start_checkpoint(self.db).await {
+            Ok(index_start) => index_start,
+            Err(err) => {
+                error!("Error reading the start of the index: {:?}", err);
+                return;
+            }
+        };
+        if idx < index_start {
+            error!(
+                "Cannot insert checkpoint with idx {}: the index starts at checkpoint {}",
+                checkpoint.idx,
+                PgU64::i64_to_u64(index_start)
+            );
+            return;
+        }
+
+        // for the first checkpoint of the index, no need to check the previous checkpoint
+        if idx > index_start {
             if let Some(previous_idx) = idx.checked_sub(1) {
                 let previous_checkpoint_exists = self.checkpoint_exists(previous_idx).await;
 
@@ -51,13 +148,22 @@ impl<'a> CheckpointService<'a> {
             }
         }
 
-        // Insert the checkpoint
-        let active_model: ActiveModel = checkpoint.into();
-        match Checkpoint::insert(active_model).exec(self.db).await {
-            Ok(_) => info!(
-                "Checkpoint with idx {} inserted successfully",
-                PgU64::i64_to_u64(idx)
-            ),
+        // Insert the checkpoint and move the ingestion cursor along with it
+        let mut active_model: ActiveModel = checkpoint.into();
+        let now = unix_timestamp();
+        active_model.status_updated_at = Set(Some(now));
+        active_model.first_seen_at = Set(Some(now));
+        match self
+            .insert_checkpoint_and_advance_cursor(active_model, idx, events)
+            .await
+        {
+            Ok(_) => {
+                metrics::counter!("explorer_checkpoints_ingested_total").increment(1);
+                info!(
+                    "Checkpoint with idx {} inserted successfully",
+                    PgU64::i64_to_u64(idx)
+                )
+            }
             Err(err) => error!(
                 "Error inserting checkpoint with idx {}: {:?}",
                 PgU64::i64_to_u64(idx),
@@ -66,20 +172,32 @@ impl<'a> CheckpointService<'a> {
         }
     }
 
+    async fn insert_checkpoint_and_advance_cursor(
+        &self,
+        active_model: ActiveModel,
+        idx: i64,
+        events: &dyn CheckpointEvents,
+    ) -> Result<(), DbErr> {
+        let checkpoint = active_model.clone().try_into_model()?;
+        let txn = self.db.begin().await?;
+        Checkpoint::insert(active_model).exec(&txn).await?;
+        advance_cursor(&txn, SyncCursor::CheckpointIngested, idx).await?;
+        if !events.urls().is_empty() {
+            enqueue_deliveries(&txn, events.created(&checkpoint), events.urls()).await?;
+        }
+        txn.commit().await
+    }
+
     /// Fetch a checkpoint by its index
-    pub async fn get_checkpoint_by_idx(&self, idx: i64) -> Option<RpcCheckpointInfoCheckpointExp> {
-        match Checkpoint::find()
+    pub async fn get_checkpoint_by_idx(
+        &self,
+        idx: i64,
+    ) -> Result<Option<RpcCheckpointInfoCheckpointExp>, DbErr> {
+        Ok(Checkpoint::find()
             .filter(model::checkpoint::Column::Idx.eq(idx))
             .one(self.db)
-            .await
-        {
-            Ok(Some(checkpoint)) => Some(checkpoint.into()),
-            Ok(None) => None,
-            Err(err) => {
-                error!("Error fetching checkpoint by idx: {:?}", err);
-                None
-            }
-        }
+            .await?
+            .map(Into::into))
     }
 
     /// Fetch a checkpoint by its L2 block ID
@@ -139,6 +257,16 @@ impl<'a> CheckpointService<'a> {
             }
         }
     }
+    /// Index of the checkpoint whose L2 range covers the given height
+    pub async fn get_checkpoint_idx_by_l2_height(&self, height: i64) -> Result<Option<i64>, DbErr> {
+        Ok(Checkpoint::find()
+            .filter(model::checkpoint::Column::L2Start.lte(height))
+            .filter(model::checkpoint::Column::L2End.gte(height))
+            .one(self.db)
+            .await?
+            .map(|checkpoint| checkpoint.idx))
+    }
+
     // TODO: move this out of db and have a separate pagination wrapper module
     pub async fn get_paginated_checkpoints(
         &self,
@@ -146,36 +274,29 @@ impl<'a> CheckpointService<'a> {
         page_size: u64,
         absolute_first_page: u64,
         order: Option<&str>,
-    ) -> PaginatedData<RpcCheckpointInfoCheckpointExp> {
-        let total_checkpoints = self.get_total_checkpoint_count().await;
+    ) -> Result<PaginatedData<RpcCheckpointInfoCheckpointExp>, DbErr> {
+        let total_checkpoints = Checkpoint::find().count(self.db).await?;
         let total_pages = (total_checkpoints as f64 / page_size as f64).ceil() as u64;
-        let offset = (current_page - absolute_first_page) * page_size; // Adjust based on the first page
+        let offset = page_offset(current_page, page_size, absolute_first_page)?;
         let order = resolve_order(order);
-        // Convert `u64` to `i64` for compatibility with PostgreSQL
-        let offset = offset.try_into().ok();
-        let limit = page_size.try_into().ok();
 
-        let items = match Checkpoint::find()
+        let items = Checkpoint::find()
             .filter(Expr::col(model::checkpoint::Column::Idx).is_not_null()) // Ensure idx is not NULL
             .order_by(model::checkpoint::Column::Idx, order) // Sort numerically
-            .offset(offset)
-            .limit(limit)
+            .offset(Some(offset))
+            .limit(Some(page_size))
             .all(self.db)
-            .await
-        {
-            Ok(checkpoints) => checkpoints.into_iter().map(Into::into).collect(),
-            Err(err) => {
-                error!("Error fetching paginated checkpoints: {:?}", err);
-                vec![]
-            }
-        };
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
 
-        PaginatedData {
+        Ok(PaginatedData {
             current_page,
             total_pages,
             absolute_first_page,
             items,
-        }
+        })
     }
 
     /// Get the total count of checkpoints in the database
@@ -191,6 +312,32 @@ impl<'a> CheckpointService<'a> {
         }
     }
 
+    /// Number of checkpoints per value of the `status` column
+    pub async fn get_checkpoint_counts_by_status(&self) -> Result<Vec<(String, u64)>, DbErr> {
+        let counts = Checkpoint::find()
+            .select_only()
+            .column(model::checkpoint::Column::Status)
+            .column_as(model::checkpoint::Column::Idx.count(), "count")
+            .group_by(model::checkpoint::Column::Status)
+            .order_by(model::checkpoint::Column::Status, Order::Asc)
+            .into_tuple::<(String, i64)>()
+            .all(self.db)
+            .await?;
+        Ok(counts
+            .into_iter()
+            .map(|(status, count)| (status, count as u64))
+            .collect())
+    }
+
+    /// Get the checkpoints from `from` to `to` included, in ascending order
+    pub async fn get_checkpoints_in_range(&self, from: i64, to: i64) -> Result<Vec<Model>, DbErr> {
+        Checkpoint::find()
+            .filter(model::checkpoint::Column::Idx.between(from, to))
+            .order_by(model::checkpoint::Column::Idx, Order::Asc)
+            .all(self.db)
+            .await
+    }
+
     /// Get the latest checkpoint index stored in the database
     pub async fn get_latest_checkpoint_index(&self) -> Option<i64> {
         use sea_orm::entity::prelude::*;
@@ -211,82 +358,192 @@ impl<'a> CheckpointService<'a> {
         }
     }
 
-    /// Get the earliest checkpoint index whose status is either `Pending` or `Confirmed` or `-`
-    pub async fn get_earliest_unfinalized_checkpoint_idx(&self) -> Option<i64> {
-        // add the condition to check no checkpoint at all
-        self.get_latest_checkpoint_index().await?;
-        match Checkpoint::find()
-            .filter(
-                model::checkpoint::Column::Status
-                    .eq("Pending")
-                    .or(model::checkpoint::Column::Status.eq("Confirmed"))
-                    .or(model::checkpoint::Column::Status.eq("-")),
-            )
+    /// Get the indexes of all checkpoints whose status is one of `statuses`, in ascending order
+    pub async fn get_checkpoint_idxs_by_status(
+        &self,
+        statuses: &[&str],
+    ) -> Result<Vec<i64>, DbErr> {
+        Checkpoint::find()
+            .select_only()
+            .column(model::checkpoint::Column::Idx)
+            .filter(model::checkpoint::Column::Status.is_in(statuses.iter().copied()))
             .order_by(model::checkpoint::Column::Idx, Order::Asc)
-            .one(self.db)
+            .into_tuple::<i64>()
+            .all(self.db)
             .await
-        {
-            Ok(Some(checkpoint)) => Some(checkpoint.idx),
-            Ok(None) => None,
-            Err(err) => {
-                error!("Error fetching earliest unfinalized checkpoint: {:?}", err);
-                None
-            }
-        }
     }
-    /// Get the earliest checkpoint index whose status is `Pending`
-    pub async fn get_earliest_pending_checkpoint_idx(&self) -> Option<i64> {
-        // add the condition to check no checkpoint at all
-        self.get_latest_checkpoint_index().await?;
-        match Checkpoint::find()
-            .filter(model::checkpoint::Column::Status.eq("Pending"))
+
+    /// Get up to `limit` checkpoints whose status is one of `statuses` and that entered it
+    /// before `entered_before`, longest stalled first
+    pub async fn get_stalled_checkpoints(
+        &self,
+        statuses: &[&str],
+        entered_before: i64,
+        limit: u64,
+    ) -> Result<Vec<Model>, DbErr> {
+        Checkpoint::find()
+            .filter(model::checkpoint::Column::Status.is_in(statuses.iter().copied()))
+            .filter(model::checkpoint::Column::StatusUpdatedAt.lt(entered_before))
+            .order_by(model::checkpoint::Column::StatusUpdatedAt, Order::Asc)
             .order_by(model::checkpoint::Column::Idx, Order::Asc)
-            .one(self.db)
+            .limit(limit)
+            .all(self.db)
             .await
-        {
-            Ok(Some(checkpoint)) => Some(checkpoint.idx),
-            Ok(None) => None,
-            Err(err) => {
-                error!("Error fetching earliest pending checkpoint: {:?}", err);
-                None
-            }
-        }
     }
-    /// Get the earliest checkpoint index whose status is `Pending`
-    pub async fn get_earliest_confirmed_checkpoint_idx(&self) -> Option<i64> {
-        // add the condition to check no checkpoint at all
-        self.get_latest_checkpoint_index().await?;
-        match Checkpoint::find()
-            .filter(model::checkpoint::Column::Status.eq("Confirmed"))
+
+    /// Replace the confirmation time of a checkpoint with `confirmed_at`, as long as it is
+    /// still confirmed by `txid`. Returns whether the checkpoint was updated.
+    pub async fn set_checkpoint_confirmed_at(
+        &self,
+        checkpoint_idx: i64,
+        txid: &str,
+        confirmed_at: i64,
+    ) -> Result<bool, DbErr> {
+        let result = Checkpoint::update_many()
+            .col_expr(
+                model::checkpoint::Column::ConfirmedAt,
+                Expr::value(confirmed_at),
+            )
+            .filter(model::checkpoint::Column::Idx.eq(checkpoint_idx))
+            .filter(model::checkpoint::Column::CheckpointTxid.eq(txid))
+            .filter(model::checkpoint::Column::ConfirmedAt.is_not_null())
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Get the checkpoints confirmed or finalized at or after `since`, in ascending order
+    pub async fn get_checkpoint_lifecycles_since(&self, since: i64) -> Result<Vec<Model>, DbErr> {
+        Checkpoint::find()
+            .filter(
+                Condition::any()
+                    .add(model::checkpoint::Column::ConfirmedAt.gte(since))
+                    .add(model::checkpoint::Column::FinalizedAt.gte(since)),
+            )
             .order_by(model::checkpoint::Column::Idx, Order::Asc)
-            .one(self.db)
+            .all(self.db)
             .await
-        {
-            Ok(Some(checkpoint)) => Some(checkpoint.idx),
-            Ok(None) => None,
-            Err(err) => {
-                error!("Error fetching earliest confirmed checkpoint: {:?}", err);
-                None
-            }
-        }
     }
-    /// Get the earliest checkpoint index whose status is `Pending`
-    pub async fn get_last_finalized_checkpoint_idx(&self) -> Option<i64> {
-        // add the condition to check no checkpoint at all
-        self.get_latest_checkpoint_index().await?;
-        match Checkpoint::find()
-            .filter(model::checkpoint::Column::Status.eq("Finalized"))
-            .order_by(model::checkpoint::Column::Idx, Order::Desc)
-            .one(self.db)
-            .await
-        {
-            Ok(Some(checkpoint)) => Some(checkpoint.idx),
-            Ok(None) => None,
-            Err(err) => {
-                error!("Error fetching last finalized checkpoint: {:?}", err);
-                None
+
+    /// Bring the stored checkpoint in line with `checkpoint_from_rpc`.
+    ///
+    /// Unlike `update_checkpoint`, this accepts backward status transitions and txid changes,
+    /// recording them as reorg events. The update, the event, its webhook events and the
+    /// reconciliation cursor are written in one transaction so readers never observe one
+    /// without the others.
+    pub async fn reconcile_checkpoint(
+        &self,
+        checkpoint_idx: i64,
+        checkpoint_from_rpc: RpcCheckpointInfo,
+        events: &dyn CheckpointEvents,
+    ) -> Result<CheckpointReconciliation, DbErr> {
+        let txn = self.db.begin().await?;
+
+        let Some(checkpoint) = Checkpoint::find_by_id(checkpoint_idx).one(&txn).await? else {
+            return Err(DbErr::RecordNotFound(format!(
+                "Checkpoint with idx {} not found",
+                PgU64::i64_to_u64(checkpoint_idx)
+            )));
+        };
+
+        let updated: ActiveModel = checkpoint_from_rpc.into();
+        let new_status = updated.status.clone().unwrap();
+        let new_txid = updated.checkpoint_txid.clone().unwrap();
+        set_cursor(&txn, SyncCursor::CheckpointReconciled, checkpoint_idx).await?;
+        let change = classify_change(&checkpoint, &new_status, &new_txid);
+        match change {
+            CheckpointChange::Unchanged => {
+                txn.commit().await?;
+                return Ok(CheckpointReconciliation::Unchanged);
             }
+            CheckpointChange::Rejected { from, to } => {
+                txn.commit().await?;
+                warn!(
+                    "Ignoring invalid status transition {} -> {} for checkpoint {}",
+                    from,
+                    to,
+                    PgU64::i64_to_u64(checkpoint_idx)
+                );
+                return Ok(CheckpointReconciliation::Rejected { from, to });
+            }
+            CheckpointChange::Updated | CheckpointChange::Reorged => (),
+        }
+
+        let reorg_event = if change == CheckpointChange::Reorged {
+            let event = ReorgEventActiveModel {
+                id: NotSet,
+                checkpoint_idx: Set(checkpoint_idx),
+                old_status: Set(checkpoint.status.clone()),
+                new_status: Set(new_status.clone()),
+                old_txid: Set(checkpoint.checkpoint_txid.clone()),
+                new_txid: Set(new_txid.clone()),
+                detected_at: Set(unix_timestamp()),
+            };
+            Some(event.insert(&txn).await?)
+        } else {
+            None
+        };
+
+        let before = checkpoint.clone();
+        let now = unix_timestamp();
+        let (confirmed_at, finalized_at) = before.lifecycle_after(&new_status, &new_txid, now);
+        let mut active_model: ActiveModel = checkpoint.into();
+        if before.status != new_status {
+            active_model.status_updated_at = Set(Some(now));
         }
+        active_model.confirmed_at = Set(confirmed_at);
+        active_model.finalized_at = Set(finalized_at);
+        active_model.status = Set(new_status);
+        active_model.checkpoint_txid = Set(new_txid);
+        let after = active_model.update(&txn).await?;
+        let reconciliation = match reorg_event {
+            Some(event) => CheckpointReconciliation::Reorged(event),
+            None => CheckpointReconciliation::Updated { before, after },
+        };
+        if !events.urls().is_empty() {
+            enqueue_deliveries(&txn, events.reconciled(&reconciliation), events.urls()).await?;
+        }
+        txn.commit().await?;
+
+        if let CheckpointReconciliation::Reorged(event) = &reconciliation {
+            warn!(
+                "Reorg detected for checkpoint {}: status {} -> {}, txid {} -> {}",
+                PgU64::i64_to_u64(checkpoint_idx),
+                event.old_status,
+                event.new_status,
+                event.old_txid,
+                event.new_txid
+            );
+        }
+        Ok(reconciliation)
+    }
+
+    /// Fetch the recorded reorg events, most recent first
+    pub async fn get_paginated_reorg_events(
+        &self,
+        current_page: u64,
+        page_size: u64,
+    ) -> Result<PaginatedData<RpcReorgEvent>, DbErr> {
+        let total_events = ReorgEvent::find().count(self.db).await?;
+        let total_pages = (total_events as f64 / page_size as f64).ceil() as u64;
+        let offset = page_offset(current_page, page_size, 1)?;
+
+        let items = ReorgEvent::find()
+            .order_by(model::reorg_event::Column::Id, Order::Desc)
+            .offset(Some(offset))
+            .limit(Some(page_size))
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(PaginatedData {
+            current_page,
+            total_pages,
+            absolute_first_page: 1,
+            items,
+        })
     }
 
     /// Update the status of a checkpoint
@@ -301,9 +558,21 @@ impl<'a> CheckpointService<'a> {
             .await
         {
             Ok(Some(checkpoint)) => {
+                let before = checkpoint.clone();
                 let mut active_model: ActiveModel = checkpoint.into();
                 let updated_checkpoint: ActiveModel = updated_checkpoint.into();
                 let status = updated_checkpoint.status.clone();
+                let now = unix_timestamp();
+                if status.as_ref() != &before.status {
+                    active_model.status_updated_at = Set(Some(now));
+                }
+                let (confirmed_at, finalized_at) = before.lifecycle_after(
+                    status.as_ref(),
+                    updated_checkpoint.checkpoint_txid.as_ref(),
+                    now,
+                );
+                active_model.confirmed_at = Set(confirmed_at);
+                active_model.finalized_at = Set(finalized_at);
                 active_model.status = status;
                 active_model.checkpoint_txid = updated_checkpoint.checkpoint_txid;
 
@@ -346,3 +615,91 @@ impl<'a> CheckpointService<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CheckpointState::*;
+
+    fn checkpoint(state: CheckpointState, txid: &str) -> Model {
+        Model {
+            idx: 0,
+            l1_start: 0,
+            l1_end: 0,
+            l2_start: 0,
+            l2_end: 0,
+            checkpoint_txid: txid.to_string(),
+            status: state.status().to_string(),
+            status_updated_at: None,
+            first_seen_at: None,
+            confirmed_at: None,
+            finalized_at: None,
+        }
+    }
+
+    #[test]
+    fn classifies_the_changes_of_status_and_txid() {
+        let rejected = |from, to| CheckpointChange::Rejected { from, to };
+        let cases = [
+            // same status and txid
+            ((Pending, "-"), (Pending, "-"), CheckpointChange::Unchanged),
+            (
+                (Finalized, "aa"),
+                (Finalized, "aa"),
+                CheckpointChange::Unchanged,
+            ),
+            // forward, with the first txid or the same one
+            ((Unknown, "-"), (Confirmed, "aa"), CheckpointChange::Updated),
+            ((Pending, "-"), (Pending, "aa"), CheckpointChange::Updated),
+            ((Pending, "-"), (Confirmed, "aa"), CheckpointChange::Updated),
+            (
+                (Confirmed, "aa"),
+                (Finalized, "aa"),
+                CheckpointChange::Updated,
+            ),
+            // a replaced txid or a confirmed checkpoint back to pending
+            ((Pending, "aa"), (Pending, "bb"), CheckpointChange::Reorged),
+            (
+                (Confirmed, "aa"),
+                (Confirmed, "bb"),
+                CheckpointChange::Reorged,
+            ),
+            (
+                (Confirmed, "aa"),
+                (Finalized, "bb"),
+                CheckpointChange::Reorged,
+            ),
+            ((Confirmed, "aa"), (Pending, "-"), CheckpointChange::Reorged),
+            (
+                (Confirmed, "aa"),
+                (Pending, "aa"),
+                CheckpointChange::Reorged,
+            ),
+            // transitions the lifecycle does not allow, and reorgs of a finalized checkpoint
+            ((Pending, "-"), (Unknown, "-"), rejected(Pending, Unknown)),
+            (
+                (Finalized, "aa"),
+                (Confirmed, "aa"),
+                rejected(Finalized, Confirmed),
+            ),
+            (
+                (Finalized, "aa"),
+                (Pending, "-"),
+                rejected(Finalized, Pending),
+            ),
+            (
+                (Finalized, "aa"),
+                (Finalized, "bb"),
+                rejected(Finalized, Finalized),
+            ),
+        ];
+        for ((from, old_txid), (to, new_txid), expected) in cases {
+            let change = classify_change(&checkpoint(from, old_txid), to.status(), new_txid);
+            assert_eq!(
+                change, expected,
+                "{} {} -> {} {}",
+                from, old_txid, to, new_txid
+            );
+        }
+    }
+}
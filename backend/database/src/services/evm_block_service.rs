@@ -0,0 +1,67 @@
+use model::block::Model as BlockModel;
+use model::evm_block::{Column, Entity as EvmBlock, Model};
+use sea_orm::{
+    prelude::*, sea_query::OnConflict, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    Statement,
+};
+
+/// Execution-layer blocks of the L2 blocks, keyed by L2 height
+pub struct EvmBlockService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> EvmBlockService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Insert `blocks`, skipping the L2 heights already linked. Returns the number of blocks
+    /// inserted.
+    pub async fn insert(&self, blocks: Vec<Model>) -> Result<u64, DbErr> {
+        if blocks.is_empty() {
+            return Ok(0);
+        }
+        let blocks = blocks
+            .into_iter()
+            .map(Into::<model::evm_block::ActiveModel>::into);
+        EvmBlock::insert_many(blocks)
+            .on_conflict(OnConflict::column(Column::L2Height).do_nothing().to_owned())
+            .exec_without_returning(self.db)
+            .await
+    }
+
+    /// Up to `limit` L2 blocks above height `after` without an EVM block, lowest first
+    pub async fn unlinked_blocks(
+        &self,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<BlockModel>, DbErr> {
+        let after = after
+            .map(|height| format!("AND b.height > {}", height))
+            .unwrap_or_default();
+        let sql = format!(
+            "SELECT b.block_hash, b.height, b.checkpoint_idx, b.exec_segment_hash \
+             FROM blocks b LEFT JOIN evm_blocks e ON e.l2_height = b.height \
+             WHERE e.l2_height IS NULL {} \
+             ORDER BY b.height \
+             LIMIT {}",
+            after, limit
+        );
+        let stmt = Statement::from_string(self.db.get_database_backend(), sql);
+        BlockModel::find_by_statement(stmt).all(self.db).await
+    }
+
+    pub async fn find_by_number(&self, number: i64) -> Result<Option<Model>, DbErr> {
+        EvmBlock::find()
+            .filter(Column::EvmBlockNumber.eq(number))
+            .one(self.db)
+            .await
+    }
+
+    pub async fn find_by_hash(&self, block_hash: &str) -> Result<Option<Model>, DbErr> {
+        EvmBlock::find()
+            .filter(Column::EvmBlockHash.eq(block_hash))
+            .one(self.db)
+            .await
+    }
+}
@@ -0,0 +1,116 @@
+use super::sync_state_service::{index_start_checkpoint, index_start_height};
+use super::utils::unix_timestamp;
+use model::integrity::{BlockCountMismatch, IntegrityReport, MissingRange};
+use model::pgu64::PgU64;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, QueryResult, Statement};
+
+/// Finds holes in the indexed checkpoints and blocks
+pub struct IntegrityService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> IntegrityService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Run every check against the current state of the database
+    pub async fn get_integrity_report(&self) -> Result<IntegrityReport, DbErr> {
+        let first_checkpoint = index_start_checkpoint(self.db).await?;
+        let first_block = index_start_height(self.db).await?;
+        Ok(IntegrityReport {
+            missing_checkpoints: self
+                .find_missing_ranges("checkpoints", "idx", first_checkpoint)
+                .await?,
+            missing_blocks: self
+                .find_missing_ranges("blocks", "height", first_block)
+                .await?,
+            block_count_mismatches: self.find_block_count_mismatches().await?,
+            checked_at: unix_timestamp(),
+        })
+    }
+
+    /// Ranges of values missing from `column`, from `first` (the start of the index) up to
+    /// the highest stored value
+    async fn find_missing_ranges(
+        &self,
+        table: &str,
+        column: &str,
+        first: i64,
+    ) -> Result<Vec<MissingRange>, DbErr> {
+        let mut ranges = Vec::new();
+
+        let lowest = self
+            .query_all(format!("SELECT MIN({column}) AS lowest FROM {table}"))
+            .await?
+            .first()
+            .map(|row| row.try_get::<Option<i64>>("", "lowest"))
+            .transpose()?
+            .flatten();
+        let Some(lowest) = lowest else {
+            // nothing indexed yet
+            return Ok(ranges);
+        };
+        if lowest > first {
+            ranges.push(MissingRange {
+                start: PgU64::i64_to_u64(first),
+                end: PgU64::i64_to_u64(lowest - 1),
+            });
+        }
+
+        // every stored value without a successor opens a gap that ends right before the
+        // next stored value
+        let rows = self
+            .query_all(format!(
+                "SELECT cur.{column} + 1 AS gap_start, \
+                 (SELECT MIN(nxt.{column}) FROM {table} nxt WHERE nxt.{column} > cur.{column}) - 1 AS gap_end \
+                 FROM {table} cur \
+                 WHERE cur.{column} < (SELECT MAX({column}) FROM {table}) \
+                 AND NOT EXISTS (SELECT 1 FROM {table} nxt WHERE nxt.{column} = cur.{column} + 1) \
+                 ORDER BY gap_start"
+            ))
+            .await?;
+        for row in rows {
+            ranges.push(MissingRange {
+                start: PgU64::i64_to_u64(row.try_get("", "gap_start")?),
+                end: PgU64::i64_to_u64(row.try_get("", "gap_end")?),
+            });
+        }
+        Ok(ranges)
+    }
+
+    /// Checkpoints, up to the highest stored block, whose block count differs from the
+    /// length of their L2 range
+    async fn find_block_count_mismatches(&self) -> Result<Vec<BlockCountMismatch>, DbErr> {
+        let rows = self
+            .query_all(
+                "SELECT c.idx, c.l2_start, c.l2_end, COUNT(b.height) AS block_count \
+                 FROM checkpoints c LEFT JOIN blocks b ON b.checkpoint_idx = c.idx \
+                 WHERE c.l2_end <= (SELECT MAX(height) FROM blocks) \
+                 GROUP BY c.idx, c.l2_start, c.l2_end \
+                 HAVING COUNT(b.height) <> c.l2_end - c.l2_start + 1 \
+                 ORDER BY c.idx"
+                    .to_string(),
+            )
+            .await?;
+
+        let mut mismatches = Vec::with_capacity(rows.len());
+        for row in rows {
+            let l2_start: i64 = row.try_get("", "l2_start")?;
+            let l2_end: i64 = row.try_get("", "l2_end")?;
+            let block_count: i64 = row.try_get("", "block_count")?;
+            mismatches.push(BlockCountMismatch {
+                checkpoint_idx: PgU64::i64_to_u64(row.try_get("", "idx")?),
+                l2_range: (PgU64::i64_to_u64(l2_start), PgU64::i64_to_u64(l2_end)),
+                expected: l2_end.abs_diff(l2_start) + 1,
+                actual: block_count as u64,
+            });
+        }
+        Ok(mismatches)
+    }
+
+    async fn query_all(&self, sql: String) -> Result<Vec<QueryResult>, DbErr> {
+        let stmt = Statement::from_string(self.db.get_database_backend(), sql);
+        self.db.query_all(stmt).await
+    }
+}
@@ -0,0 +1,98 @@
+use model::l1_block::{Column, Entity as L1Block, IncompleteL1Range, Model};
+use sea_orm::{
+    prelude::*, sea_query::OnConflict, DatabaseConnection, DbErr, EntityTrait, Order, QueryOrder,
+    QuerySelect, Statement,
+};
+
+/// L1 blocks covered by the checkpoints, keyed by height
+pub struct L1BlockService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> L1BlockService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Insert `blocks`, skipping the heights already stored. Returns the number of blocks
+    /// inserted.
+    pub async fn insert(&self, blocks: Vec<Model>) -> Result<u64, DbErr> {
+        if blocks.is_empty() {
+            return Ok(0);
+        }
+        let blocks = blocks
+            .into_iter()
+            .map(Into::<model::l1_block::ActiveModel>::into);
+        L1Block::insert_many(blocks)
+            .on_conflict(OnConflict::column(Column::Height).do_nothing().to_owned())
+            .exec_without_returning(self.db)
+            .await
+    }
+
+    /// L1 ranges of up to `limit` checkpoints with blocks not stored yet, in checkpoint order
+    pub async fn incomplete_ranges(&self, limit: u64) -> Result<Vec<IncompleteL1Range>, DbErr> {
+        let sql = format!(
+            "SELECT c.idx, c.l1_start, c.l1_end \
+             FROM checkpoints c LEFT JOIN l1_blocks l ON l.height BETWEEN c.l1_start AND c.l1_end \
+             GROUP BY c.idx, c.l1_start, c.l1_end \
+             HAVING COUNT(l.height) < c.l1_end - c.l1_start + 1 \
+             ORDER BY c.idx \
+             LIMIT {}",
+            limit
+        );
+        let stmt = Statement::from_string(self.db.get_database_backend(), sql);
+        let rows = self.db.query_all(stmt).await?;
+
+        let mut ranges = Vec::with_capacity(rows.len());
+        for row in rows {
+            ranges.push(IncompleteL1Range {
+                checkpoint_idx: row.try_get("", "idx")?,
+                start: row.try_get("", "l1_start")?,
+                end: row.try_get("", "l1_end")?,
+            });
+        }
+        Ok(ranges)
+    }
+
+    /// Heights stored from `from` to `to` included, in ascending order
+    pub async fn heights_in_range(&self, from: i64, to: i64) -> Result<Vec<i64>, DbErr> {
+        L1Block::find()
+            .select_only()
+            .column(Column::Height)
+            .filter(Column::Height.between(from, to))
+            .order_by(Column::Height, Order::Asc)
+            .into_tuple::<i64>()
+            .all(self.db)
+            .await
+    }
+
+    /// Up to `limit` blocks whose header time was not looked up yet, lowest first
+    pub async fn without_timestamp(&self, limit: u64) -> Result<Vec<Model>, DbErr> {
+        L1Block::find()
+            .filter(Column::Timestamp.is_null())
+            .order_by(Column::Height, Order::Asc)
+            .limit(limit)
+            .all(self.db)
+            .await
+    }
+
+    pub async fn set_timestamp(&self, height: i64, timestamp: i64) -> Result<(), DbErr> {
+        L1Block::update_many()
+            .col_expr(Column::Timestamp, Expr::value(timestamp))
+            .filter(Column::Height.eq(height))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_hash(&self, block_hash: &str) -> Result<Option<Model>, DbErr> {
+        L1Block::find()
+            .filter(Column::BlockHash.eq(block_hash))
+            .one(self.db)
+            .await
+    }
+
+    pub async fn find_by_height(&self, height: i64) -> Result<Option<Model>, DbErr> {
+        L1Block::find_by_id(height).one(self.db).await
+    }
+}
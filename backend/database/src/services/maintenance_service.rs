@@ -0,0 +1,191 @@
+use super::sync_state_service::{clear_cursor, set_cursor};
+use model::block::{self, Entity as Block};
+use model::block_fetch_job::{self, Entity as BlockFetchJob};
+use model::bridge_intent::{self, Entity as BridgeIntent};
+use model::checkpoint::{self, Entity as Checkpoint};
+use model::evm_block::{self, Entity as EvmBlock};
+use model::l1_block::{self, Entity as L1Block};
+use model::reorg_event::{self, Entity as ReorgEvent};
+use model::sync_state::{Entity as SyncState, SyncCursor};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, TransactionTrait,
+};
+
+/// Rows removed by a maintenance operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeSummary {
+    pub checkpoints: u64,
+    pub blocks: u64,
+    pub l1_blocks: u64,
+    pub reorg_events: u64,
+}
+
+/// Deletes indexed data so that it can be fetched again.
+///
+/// Every operation runs in one transaction and moves the ingestion cursors back to the data
+/// that is left, so a running indexer re-fetches a purged tail on its own.
+pub struct MaintenanceService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> MaintenanceService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Delete the checkpoints from `from` to `to` included, or every checkpoint from `from` on
+    /// when `to` is `None`, along with their blocks, L1 and EVM blocks, bridge intents and
+    /// block fetch jobs. Their reorg events are kept, as the only record of the reorgs, unless
+    /// `with_reorg_events`.
+    pub async fn delete_checkpoints(
+        &self,
+        from: i64,
+        to: Option<i64>,
+        with_reorg_events: bool,
+    ) -> Result<PurgeSummary, DbErr> {
+        let txn = self.db.begin().await?;
+        let l2_range = Checkpoint::find()
+            .filter(in_range(checkpoint::Column::Idx, from, to))
+            .select_only()
+            .column_as(checkpoint::Column::L2Start.min(), "start")
+            .column_as(checkpoint::Column::L2End.max(), "end")
+            .into_tuple::<(Option<i64>, Option<i64>)>()
+            .one(&txn)
+            .await?;
+        if let Some((Some(start), Some(end))) = l2_range {
+            delete_bridge_intents(&txn, start, end).await?;
+        }
+        EvmBlock::delete_many()
+            .filter(in_range(evm_block::Column::CheckpointIdx, from, to))
+            .exec(&txn)
+            .await?;
+        let blocks = Block::delete_many()
+            .filter(in_range(block::Column::CheckpointIdx, from, to))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+        let l1_blocks = L1Block::delete_many()
+            .filter(in_range(l1_block::Column::CheckpointIdx, from, to))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+        let reorg_events = if with_reorg_events {
+            ReorgEvent::delete_many()
+                .filter(in_range(reorg_event::Column::CheckpointIdx, from, to))
+                .exec(&txn)
+                .await?
+                .rows_affected
+        } else {
+            0
+        };
+        let checkpoints = Checkpoint::delete_many()
+            .filter(in_range(checkpoint::Column::Idx, from, to))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+        BlockFetchJob::delete_many()
+            .filter(in_range(block_fetch_job::Column::CheckpointIdx, from, to))
+            .exec(&txn)
+            .await?;
+        rewind_cursors(&txn).await?;
+        txn.commit().await?;
+
+        Ok(PurgeSummary {
+            checkpoints,
+            blocks,
+            l1_blocks,
+            reorg_events,
+        })
+    }
+
+    /// Delete the blocks of the checkpoint `idx`, their bridge intents and its block fetch
+    /// job, keeping the checkpoint itself
+    pub async fn delete_checkpoint_blocks(&self, idx: i64) -> Result<u64, DbErr> {
+        let txn = self.db.begin().await?;
+        if let Some(checkpoint) = Checkpoint::find_by_id(idx).one(&txn).await? {
+            delete_bridge_intents(&txn, checkpoint.l2_start, checkpoint.l2_end).await?;
+        }
+        EvmBlock::delete_many()
+            .filter(evm_block::Column::CheckpointIdx.eq(idx))
+            .exec(&txn)
+            .await?;
+        let blocks = Block::delete_many()
+            .filter(block::Column::CheckpointIdx.eq(idx))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+        // the job is done, the indexer only queues the checkpoint again without one
+        BlockFetchJob::delete_by_id(idx).exec(&txn).await?;
+        rewind_cursors(&txn).await?;
+        txn.commit().await?;
+        Ok(blocks)
+    }
+}
+
+/// Values of `column` from `from` to `to` included, or from `from` on
+fn in_range<C: ColumnTrait>(column: C, from: i64, to: Option<i64>) -> Condition {
+    let condition = Condition::all().add(column.gte(from));
+    match to {
+        Some(to) => condition.add(column.lte(to)),
+        None => condition,
+    }
+}
+
+/// Delete the bridge intents of the blocks from height `start` to `end` included and move
+/// the bridge cursor before them, so that they are scanned again once the blocks are back.
+/// Runs before [`rewind_cursors`], which only moves the cursor further back.
+async fn delete_bridge_intents<C: ConnectionTrait>(
+    conn: &C,
+    start: i64,
+    end: i64,
+) -> Result<(), DbErr> {
+    BridgeIntent::delete_many()
+        .filter(bridge_intent::Column::L2Height.between(start, end))
+        .exec(conn)
+        .await?;
+    // the cursor is cleared when the range starts at height 0
+    rewind_cursor(conn, SyncCursor::BridgeScanned, start.checked_sub(1)).await
+}
+
+/// Move the ingestion cursors back to the highest checkpoint and block left, and the
+/// reconciliation and bridge cursors along with them
+async fn rewind_cursors<C: ConnectionTrait>(conn: &C) -> Result<(), DbErr> {
+    let last_checkpoint = Checkpoint::find()
+        .select_only()
+        .column_as(checkpoint::Column::Idx.max(), "last")
+        .into_tuple::<Option<i64>>()
+        .one(conn)
+        .await?
+        .flatten();
+    let last_block = Block::find()
+        .select_only()
+        .column_as(block::Column::Height.max(), "last")
+        .into_tuple::<Option<i64>>()
+        .one(conn)
+        .await?
+        .flatten();
+
+    rewind_cursor(conn, SyncCursor::CheckpointIngested, last_checkpoint).await?;
+    rewind_cursor(conn, SyncCursor::CheckpointReconciled, last_checkpoint).await?;
+    rewind_cursor(conn, SyncCursor::BlockIngested, last_block).await?;
+    rewind_cursor(conn, SyncCursor::BridgeScanned, last_block).await
+}
+
+/// Move `cursor` back to `value` if it is past it, clear it when there is nothing left
+async fn rewind_cursor<C: ConnectionTrait>(
+    conn: &C,
+    cursor: SyncCursor,
+    value: Option<i64>,
+) -> Result<(), DbErr> {
+    let current = SyncState::find_by_id(cursor.name())
+        .one(conn)
+        .await?
+        .map(|state| state.value);
+    match (current, value) {
+        (None, _) => Ok(()),
+        (Some(_), None) => clear_cursor(conn, cursor).await,
+        (Some(current), Some(value)) if current > value => set_cursor(conn, cursor, value).await,
+        (Some(_), Some(_)) => Ok(()),
+    }
+}
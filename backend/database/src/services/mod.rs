@@ -1,4 +1,13 @@
+pub mod block_fetch_job_service;
 pub mod block_service;
+pub mod bridge_duty_service;
+pub mod bridge_intent_service;
 pub mod checkpoint_service;
+pub mod evm_block_service;
+pub mod integrity_service;
+pub mod l1_block_service;
+pub mod maintenance_service;
 pub mod pagination;
+pub mod sync_state_service;
 pub mod utils;
+pub mod webhook_delivery_service;
@@ -1,4 +1,6 @@
+use sea_orm::DbErr;
 use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PaginatedData<T> {
     pub current_page: u64,
@@ -6,3 +8,21 @@ pub struct PaginatedData<T> {
     pub absolute_first_page: u64, // Will be 0 or 1, depending on the context
     pub items: Vec<T>,            // The items for the current page
 }
+
+/// Number of items before `current_page`, an error when the page is before
+/// `absolute_first_page` or too far to be addressed
+pub fn page_offset(
+    current_page: u64,
+    page_size: u64,
+    absolute_first_page: u64,
+) -> Result<u64, DbErr> {
+    current_page
+        .checked_sub(absolute_first_page)
+        .and_then(|pages| pages.checked_mul(page_size))
+        .ok_or_else(|| {
+            DbErr::Custom(format!(
+                "Page {} of {} items is out of range",
+                current_page, page_size
+            ))
+        })
+}
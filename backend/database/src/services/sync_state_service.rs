@@ -0,0 +1,148 @@
+use super::utils::unix_timestamp;
+use model::block::{self, Entity as Block};
+use model::checkpoint::{self, Entity as Checkpoint};
+use model::pgu64::PgU64;
+use model::sync_state::{ActiveModel, Column, Entity as SyncState, IndexRange, Model, SyncCursor};
+use sea_orm::{
+    prelude::*, sea_query::OnConflict, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    Order, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+pub struct SyncStateService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> SyncStateService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Get the value of a cursor, `None` if it was never set
+    pub async fn get_cursor(&self, cursor: SyncCursor) -> Result<Option<i64>, DbErr> {
+        Ok(SyncState::find_by_id(cursor.name())
+            .one(self.db)
+            .await?
+            .map(|state| state.value))
+    }
+
+    /// Get all cursors that have been set
+    pub async fn get_cursors(&self) -> Result<Vec<Model>, DbErr> {
+        SyncState::find()
+            .order_by(Column::Name, Order::Asc)
+            .all(self.db)
+            .await
+    }
+
+    /// Record the range of checkpoints the indexer is configured for, `end` is cleared when
+    /// it follows the fullnode tip
+    pub async fn declare_index_range(&self, start: i64, end: Option<i64>) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        set_cursor(&txn, SyncCursor::IndexStart, start).await?;
+        match end {
+            Some(end) => set_cursor(&txn, SyncCursor::IndexEnd, end).await?,
+            None => clear_cursor(&txn, SyncCursor::IndexEnd).await?,
+        }
+        txn.commit().await
+    }
+
+    /// The declared index range and the checkpoints and blocks actually stored
+    pub async fn get_index_range(&self) -> Result<IndexRange, DbErr> {
+        let (first_checkpoint, last_checkpoint) = Checkpoint::find()
+            .select_only()
+            .column_as(checkpoint::Column::Idx.min(), "first")
+            .column_as(checkpoint::Column::Idx.max(), "last")
+            .into_tuple::<(Option<i64>, Option<i64>)>()
+            .one(self.db)
+            .await?
+            .unwrap_or_default();
+        let (first_block, last_block) = Block::find()
+            .select_only()
+            .column_as(block::Column::Height.min(), "first")
+            .column_as(block::Column::Height.max(), "last")
+            .into_tuple::<(Option<i64>, Option<i64>)>()
+            .one(self.db)
+            .await?
+            .unwrap_or_default();
+
+        Ok(IndexRange {
+            start_checkpoint: PgU64::i64_to_u64(index_start_checkpoint(self.db).await?),
+            end_checkpoint: self
+                .get_cursor(SyncCursor::IndexEnd)
+                .await?
+                .map(PgU64::i64_to_u64),
+            first_checkpoint: first_checkpoint.map(PgU64::i64_to_u64),
+            last_checkpoint: last_checkpoint.map(PgU64::i64_to_u64),
+            first_block: first_block.map(PgU64::i64_to_u64),
+            last_block: last_block.map(PgU64::i64_to_u64),
+        })
+    }
+}
+
+/// First checkpoint of the index, checkpoint 0 unless the indexer declared another start
+pub(crate) async fn index_start_checkpoint<C: ConnectionTrait>(conn: &C) -> Result<i64, DbErr> {
+    Ok(SyncState::find_by_id(SyncCursor::IndexStart.name())
+        .one(conn)
+        .await?
+        .map_or(i64::MIN, |state| state.value))
+}
+
+/// First block height of the index, the start of the L2 range of the first checkpoint.
+/// Block 0 while that checkpoint is not stored yet.
+pub(crate) async fn index_start_height<C: ConnectionTrait>(conn: &C) -> Result<i64, DbErr> {
+    let start = index_start_checkpoint(conn).await?;
+    if start == i64::MIN {
+        return Ok(i64::MIN);
+    }
+    Ok(Checkpoint::find_by_id(start)
+        .one(conn)
+        .await?
+        .map_or(i64::MIN, |checkpoint| checkpoint.l2_start))
+}
+
+/// Set a cursor on the given connection, so that callers can move it in the same
+/// transaction as the data it covers
+pub(crate) async fn set_cursor<C: ConnectionTrait>(
+    conn: &C,
+    cursor: SyncCursor,
+    value: i64,
+) -> Result<(), DbErr> {
+    let state = ActiveModel {
+        name: Set(cursor.name().to_string()),
+        value: Set(value),
+        updated_at: Set(unix_timestamp()),
+    };
+    SyncState::insert(state)
+        .on_conflict(
+            OnConflict::column(Column::Name)
+                .update_columns([Column::Value, Column::UpdatedAt])
+                .to_owned(),
+        )
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Remove a cursor, it reads as never set afterwards
+pub(crate) async fn clear_cursor<C: ConnectionTrait>(
+    conn: &C,
+    cursor: SyncCursor,
+) -> Result<(), DbErr> {
+    SyncState::delete_by_id(cursor.name()).exec(conn).await?;
+    Ok(())
+}
+
+/// Move a cursor forward to `value`, leaving it untouched if it is already past it
+pub(crate) async fn advance_cursor<C: ConnectionTrait>(
+    conn: &C,
+    cursor: SyncCursor,
+    value: i64,
+) -> Result<(), DbErr> {
+    let current = SyncState::find_by_id(cursor.name())
+        .one(conn)
+        .await?
+        .map(|state| state.value);
+    if current.is_some_and(|current| current >= value) {
+        return Ok(());
+    }
+    set_cursor(conn, cursor, value).await
+}
@@ -1,7 +1,5 @@
-use crate::connection::DatabaseWrapper;
 use sea_orm::Order;
-use std::sync::Arc;
-use tokio::time::{sleep, Duration};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub(crate) fn resolve_order(order: Option<&str>) -> Order {
     match order {
@@ -11,13 +9,10 @@ pub(crate) fn resolve_order(order: Option<&str>) -> Order {
     }
 }
 
-pub async fn wait_until_migration(database: &Arc<DatabaseWrapper>) {
-    // Wait until the migration is done
-    loop {
-        tracing::info!("Checking if migration is applied...");
-        if database.migrations_applied().await {
-            break;
-        }
-        sleep(Duration::from_secs(5)).await;
-    }
+/// Current UNIX timestamp in seconds, as stored in the `*_at` columns
+pub fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
 }
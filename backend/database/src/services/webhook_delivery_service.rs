@@ -0,0 +1,133 @@
+use super::utils::unix_timestamp;
+use model::webhook_delivery::{
+    ActiveModel, Column, DeliveryState, Entity as WebhookDelivery, Model, NewWebhookEvent,
+    WebhookDeliveryCounts,
+};
+use sea_orm::{
+    prelude::*, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, NotSet, Order, QueryOrder,
+    QuerySelect, Set,
+};
+
+/// Persisted outbox of the webhook deliveries, kept until the receivers acknowledge them and
+/// pruned once acknowledged for long enough
+pub struct WebhookDeliveryService<'a> {
+    pub db: &'a DatabaseConnection,
+}
+
+impl<'a> WebhookDeliveryService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Queue one delivery of `event` per URL, all due now
+    pub async fn enqueue(&self, event: NewWebhookEvent, urls: &[String]) -> Result<(), DbErr> {
+        enqueue_deliveries(self.db, vec![event], urls).await
+    }
+
+    /// The oldest `limit` pending deliveries to `url`, in the order they were queued
+    pub async fn pending(&self, url: &str, limit: u64) -> Result<Vec<Model>, DbErr> {
+        WebhookDelivery::find()
+            .filter(Column::State.eq(DeliveryState::Pending.state()))
+            .filter(Column::Url.eq(url))
+            .order_by(Column::Id, Order::Asc)
+            .limit(limit)
+            .all(self.db)
+            .await
+    }
+
+    /// Record the acknowledgement of delivery `id`
+    pub async fn mark_delivered(&self, id: i64) -> Result<(), DbErr> {
+        WebhookDelivery::update_many()
+            .col_expr(Column::State, Expr::value(DeliveryState::Delivered.state()))
+            .col_expr(Column::Attempts, Expr::col(Column::Attempts).add(1))
+            .col_expr(Column::LastError, Expr::value(Option::<String>::None))
+            .col_expr(Column::DeliveredAt, Expr::value(unix_timestamp()))
+            .filter(Column::Id.eq(id))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Record a failed attempt of delivery `id`, attempted again at `next_attempt_at`
+    pub async fn mark_failed(
+        &self,
+        id: i64,
+        error: String,
+        next_attempt_at: i64,
+    ) -> Result<(), DbErr> {
+        WebhookDelivery::update_many()
+            .col_expr(Column::Attempts, Expr::col(Column::Attempts).add(1))
+            .col_expr(Column::LastError, Expr::value(error))
+            .col_expr(Column::NextAttemptAt, Expr::value(next_attempt_at))
+            .filter(Column::Id.eq(id))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete the deliveries acknowledged before `delivered_before`
+    pub async fn prune(&self, delivered_before: i64) -> Result<u64, DbErr> {
+        let result = WebhookDelivery::delete_many()
+            .filter(Column::State.eq(DeliveryState::Delivered.state()))
+            .filter(Column::DeliveredAt.lt(delivered_before))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Number of deliveries in each state
+    pub async fn counts(&self) -> Result<WebhookDeliveryCounts, DbErr> {
+        let rows = WebhookDelivery::find()
+            .select_only()
+            .column(Column::State)
+            .column_as(Column::Id.count(), "count")
+            .group_by(Column::State)
+            .into_tuple::<(String, i64)>()
+            .all(self.db)
+            .await?;
+
+        let mut counts = WebhookDeliveryCounts::default();
+        for (state, count) in rows {
+            let count = count as u64;
+            match DeliveryState::from_state(&state) {
+                Some(DeliveryState::Pending) => counts.pending = count,
+                Some(DeliveryState::Delivered) => counts.delivered = count,
+                None => (),
+            }
+        }
+        Ok(counts)
+    }
+}
+
+/// Queue one delivery of each of `events` per URL, all due now. Takes a connection so that
+/// the events of a write are queued in its transaction.
+pub(crate) async fn enqueue_deliveries<C: ConnectionTrait>(
+    conn: &C,
+    events: Vec<NewWebhookEvent>,
+    urls: &[String],
+) -> Result<(), DbErr> {
+    if events.is_empty() || urls.is_empty() {
+        return Ok(());
+    }
+    let now = unix_timestamp();
+    let deliveries = events.iter().flat_map(|event| {
+        urls.iter().map(move |url| ActiveModel {
+            id: NotSet,
+            event_id: Set(event.event_id.clone()),
+            event_type: Set(event.event_type.name().to_string()),
+            checkpoint_idx: Set(event.checkpoint_idx),
+            url: Set(url.clone()),
+            payload: Set(event.payload.clone()),
+            state: Set(DeliveryState::Pending.state().to_string()),
+            attempts: Set(0),
+            last_error: Set(None),
+            next_attempt_at: Set(now),
+            created_at: Set(now),
+            delivered_at: Set(None),
+        })
+    });
+    WebhookDelivery::insert_many(deliveries)
+        .exec_without_returning(conn)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Fixtures of the repository tests, which run against the in-memory repository and, with
+//! the `sqlite` feature, against [`SqlRepository`](crate::repository::sql::SqlRepository)
+//! on an in-memory SQLite database.
+use crate::repository::memory::InMemoryRepository;
+use crate::repository::Repository;
+use crate::services::checkpoint_service::{CheckpointEvents, CheckpointReconciliation};
+use model::block::RpcBlockHeader;
+use model::checkpoint::{Model, RpcCheckpointInfo};
+use model::pgu64::PgU64;
+use model::webhook_delivery::{NewWebhookEvent, WebhookEventType};
+use serde_json::json;
+
+/// Repository the shared tests run against, opened empty for each test
+pub(crate) trait TestRepository: Repository + Sized {
+    async fn open() -> Self;
+}
+
+impl TestRepository for InMemoryRepository {
+    async fn open() -> Self {
+        InMemoryRepository::new()
+    }
+}
+
+/// Migrated in-memory SQLite database
+#[cfg(feature = "sqlite")]
+impl TestRepository for crate::repository::sql::SqlRepository {
+    async fn open() -> Self {
+        use crate::connection::{DatabaseOptions, DatabaseWrapper, LevelFilter};
+
+        let options = DatabaseOptions {
+            sql_log_level: LevelFilter::Off,
+            connect_retries: 0,
+            ..Default::default()
+        };
+        let database = DatabaseWrapper::new("sqlite::memory:", None, &options)
+            .await
+            .unwrap();
+        database.apply_migrations().await.unwrap();
+        database.repository()
+    }
+}
+
+/// Checkpoint `idx` covering the L2 heights `l2_range`, without an L1 reference when pending
+pub(crate) fn checkpoint(
+    idx: u64,
+    l2_range: (u64, u64),
+    status: &str,
+    txid: &str,
+) -> RpcCheckpointInfo {
+    let l1_reference = (status != "pending").then(
+        || json!({ "block_height": 101 + idx, "block_id": "l1", "txid": txid, "wtxid": txid }),
+    );
+    serde_json::from_value(json!({
+        "idx": idx,
+        "l1_range": [
+            { "height": 100 + idx, "blkid": format!("{:064x}", 100 + idx) },
+            { "height": 101 + idx, "blkid": format!("{:064x}", 101 + idx) },
+        ],
+        "l2_range": [
+            { "slot": l2_range.0, "blkid": block_hash(l2_range.0) },
+            { "slot": l2_range.1, "blkid": block_hash(l2_range.1) },
+        ],
+        "l1_reference": l1_reference,
+        "confirmation_status": status,
+    }))
+    .unwrap()
+}
+
+/// Header of the block at `height`, chained to the one below
+pub(crate) fn block(height: u64) -> RpcBlockHeader {
+    serde_json::from_value(json!({
+        "block_idx": height,
+        "timestamp": 1_700_000_000_000u64 + height,
+        "block_id": block_hash(height),
+        "prev_block": block_hash(height.wrapping_sub(1)),
+        "l1_segment_hash": format!("{:064x}", 0),
+        "exec_segment_hash": format!("{:064x}", height),
+        "state_root": format!("{:064x}", 0),
+    }))
+    .unwrap()
+}
+
+pub(crate) fn block_hash(height: u64) -> String {
+    format!("{:064x}", height.wrapping_add(1))
+}
+
+/// Queues a `checkpoint.created` event per checkpoint for `urls`, and nothing on
+/// reconciliation
+#[derive(Default)]
+pub(crate) struct Events {
+    pub urls: Vec<String>,
+}
+
+impl CheckpointEvents for Events {
+    fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    fn created(&self, checkpoint: &Model) -> Vec<NewWebhookEvent> {
+        vec![NewWebhookEvent {
+            event_id: format!("created-{}", PgU64::i64_to_u64(checkpoint.idx)),
+            event_type: WebhookEventType::CheckpointCreated,
+            checkpoint_idx: checkpoint.idx,
+            payload: "{}".to_string(),
+        }]
+    }
+
+    fn reconciled(&self, _: &CheckpointReconciliation) -> Vec<NewWebhookEvent> {
+        Vec::new()
+    }
+}
+
+/// Run each `async fn<R: TestRepository>()` of the calling module against the in-memory
+/// repository and, with the `sqlite` feature, against SQLite
+macro_rules! repository_tests {
+    ($($name:ident),* $(,)?) => {
+        mod in_memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name::<$crate::repository::memory::InMemoryRepository>().await
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name::<$crate::repository::sql::SqlRepository>().await
+                }
+            )*
+        }
+    };
+}
+pub(crate) use repository_tests;
@@ -0,0 +1,131 @@
+use crate::fetcher::record_rpc_call;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+use std::time::Instant;
+
+/// A block of the execution layer, as returned by `eth_getBlockByHash`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EvmBlock {
+    #[serde(deserialize_with = "deserialize_quantity")]
+    pub number: u64,
+    pub hash: String,
+}
+
+/// The part of a transaction locating it in the chain, `None` while it is pending
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EvmTransaction {
+    #[serde(default, deserialize_with = "deserialize_optional_quantity")]
+    block_number: Option<u64>,
+}
+
+/// `EvmClient` queries the `eth_` JSON-RPC of the execution client (e.g. Alpen Reth) to link
+/// L2 blocks to EVM blocks
+pub struct EvmClient {
+    client: Client,
+    endpoint: String,
+}
+
+impl EvmClient {
+    /// Creates a new `EvmClient` instance.
+    pub fn new(endpoint: String) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint,
+        }
+    }
+
+    /// Fetches the block whose hash is `block_hash`, `None` if the execution client does not
+    /// know it
+    pub async fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<EvmBlock>> {
+        let result = self
+            .call(
+                "eth_getBlockByHash",
+                json!([with_hex_prefix(block_hash), false]),
+            )
+            .await?;
+        serde_json::from_value(result).context("Failed to deserialize EVM block")
+    }
+
+    /// Fetches the number of the block including transaction `tx_hash`, `None` if the
+    /// transaction is unknown or still pending
+    pub async fn get_transaction_block_number(&self, tx_hash: &str) -> Result<Option<u64>> {
+        let result = self
+            .call(
+                "eth_getTransactionByHash",
+                json!([with_hex_prefix(tx_hash)]),
+            )
+            .await?;
+        let transaction: Option<EvmTransaction> =
+            serde_json::from_value(result).context("Failed to deserialize EVM transaction")?;
+        Ok(transaction.and_then(|transaction| transaction.block_number))
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let started = Instant::now();
+        let result = self.request(method, params).await;
+        record_rpc_call(method, started, result.is_ok());
+        result
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1
+        });
+
+        let mut response: Value = self
+            .client
+            .post(&self.endpoint)
+            .json(&payload)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request for method: {}", method))?
+            .error_for_status()
+            .context("Request returned an error status")?
+            .json()
+            .await
+            .context("Failed to parse JSON response")?;
+
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("{} returned an error: {}", method, error);
+        }
+        Ok(response
+            .get_mut("result")
+            .map(Value::take)
+            .unwrap_or(Value::Null))
+    }
+}
+
+/// `eth_` hashes are 0x-prefixed, the Strata fullnode reports them without a prefix
+pub fn with_hex_prefix(hash: &str) -> String {
+    if hash.starts_with("0x") {
+        hash.to_string()
+    } else {
+        format!("0x{}", hash)
+    }
+}
+
+fn parse_quantity(quantity: &str) -> Result<u64, String> {
+    let digits = quantity
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("quantity {} is not 0x-prefixed", quantity))?;
+    u64::from_str_radix(digits, 16).map_err(|e| format!("invalid quantity {}: {}", quantity, e))
+}
+
+fn deserialize_quantity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let quantity = String::deserialize(deserializer)?;
+    parse_quantity(&quantity).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_optional_quantity<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|quantity| parse_quantity(&quantity).map_err(serde::de::Error::custom))
+        .transpose()
+}
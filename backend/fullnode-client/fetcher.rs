@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde_json::{json, Value};
+use std::time::Instant;
 use tracing::{error, info};
 /// `StrataFetcher` struct for fetching checkpoint and block data
 pub struct StrataFetcher {
@@ -25,6 +26,13 @@ impl StrataFetcher {
     /// # Returns
     /// * `Result<u64>` - Latest index if successful
     pub async fn get_latest_index(&self, method: &str) -> Result<Option<u64>> {
+        let started = Instant::now();
+        let result = self.request_latest_index(method).await;
+        record_rpc_call(method, started, result.is_ok());
+        result
+    }
+
+    async fn request_latest_index(&self, method: &str) -> Result<Option<u64>> {
         let payload = json!({
             "jsonrpc": "2.0",
             "method": method,
@@ -85,6 +93,16 @@ impl StrataFetcher {
     /// # Returns
     /// * `Result<T>` - Fetched data deserialized into the generic type `T`
     pub async fn fetch_data<T>(&self, method: &str, idx: u64) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let started = Instant::now();
+        let result = self.request_data(method, idx).await;
+        record_rpc_call(method, started, result.is_ok());
+        result
+    }
+
+    async fn request_data<T>(&self, method: &str, idx: u64) -> Result<T>
     where
         T: serde::de::DeserializeOwned,
     {
@@ -127,4 +145,96 @@ impl StrataFetcher {
             }
         }
     }
+
+    /// Fetches the data of a given block or transaction id, `None` if the fullnode does not
+    /// have any.
+    ///
+    /// # Parameters
+    /// * `method` - JSON-RPC method name (e.g., `strata_getExecUpdateById`)
+    /// * `id` - Hex encoded id to fetch
+    ///
+    /// # Returns
+    /// * `Result<Option<T>>` - Fetched data deserialized into the generic type `T`
+    pub async fn fetch_data_by_id<T>(&self, method: &str, id: &str) -> Result<Option<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let started = Instant::now();
+        let result = self.request_data_by_id(method, id).await;
+        record_rpc_call(method, started, result.is_ok());
+        result
+    }
+
+    async fn request_data_by_id<T>(&self, method: &str, id: &str) -> Result<Option<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.request_data_with_params(method, json!([id])).await
+    }
+
+    /// Fetches the result of a method taking several parameters, `None` if the fullnode
+    /// does not have any.
+    ///
+    /// # Parameters
+    /// * `method` - JSON-RPC method name (e.g., `strata_getBridgeDuties`)
+    /// * `params` - JSON array of the parameters
+    ///
+    /// # Returns
+    /// * `Result<Option<T>>` - Fetched data deserialized into the generic type `T`
+    pub async fn fetch_data_with_params<T>(&self, method: &str, params: Value) -> Result<Option<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let started = Instant::now();
+        let result = self.request_data_with_params(method, params).await;
+        record_rpc_call(method, started, result.is_ok());
+        result
+    }
+
+    async fn request_data_with_params<T>(&self, method: &str, params: Value) -> Result<Option<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1
+        });
+
+        let response: Value = self
+            .client
+            .post(&self.endpoint)
+            .json(&payload)
+            .send()
+            .await
+            .context("Failed to send request")?
+            .error_for_status()
+            .context("Request returned an error status")?
+            .json()
+            .await
+            .context("Failed to parse JSON response")?;
+
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("{} returned an error for {}: {}", method, params, error);
+        }
+        match response.get("result") {
+            Some(Value::Null) | None => Ok(None),
+            Some(result) => serde_json::from_value::<T>(result.clone())
+                .map(Some)
+                .with_context(|| format!("Failed to deserialize response data for {}", params)),
+        }
+    }
+}
+
+/// Count a JSON-RPC call and its latency per method, failures included
+pub(crate) fn record_rpc_call(method: &str, started: Instant, succeeded: bool) {
+    let method = method.to_string();
+    metrics::counter!("explorer_fullnode_rpc_requests_total", "method" => method.clone())
+        .increment(1);
+    metrics::histogram!("explorer_fullnode_rpc_duration_seconds", "method" => method.clone())
+        .record(started.elapsed().as_secs_f64());
+    if !succeeded {
+        metrics::counter!("explorer_fullnode_rpc_errors_total", "method" => method).increment(1);
+    }
 }
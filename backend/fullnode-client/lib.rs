@@ -1 +1,2 @@
+pub mod evm;
 pub mod fetcher;
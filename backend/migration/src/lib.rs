@@ -2,6 +2,18 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_checkpoint_table;
 mod m20241226_100451_create_blocks_table;
+mod m20261019_090000_create_reorg_events_table;
+mod m20261019_120000_create_sync_state_table;
+mod m20261019_150000_create_block_fetch_jobs_table;
+mod m20261019_180000_create_webhook_deliveries_table;
+mod m20261019_200000_add_checkpoint_status_updated_at;
+mod m20261019_220000_add_checkpoint_lifecycle_timestamps;
+mod m20261019_230000_create_l1_blocks_table;
+mod m20261019_233000_create_evm_blocks_table;
+mod m20261019_235000_create_bridge_intents_table;
+mod m20261020_010000_create_bridge_duties_tables;
+mod m20261020_020000_drop_reorg_events_checkpoint_fk;
+mod m20261020_030000_add_block_fetch_job_next_attempt_at;
 
 pub struct Migrator;
 
@@ -11,6 +23,18 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220101_000001_create_checkpoint_table::Migration),
             Box::new(m20241226_100451_create_blocks_table::Migration),
+            Box::new(m20261019_090000_create_reorg_events_table::Migration),
+            Box::new(m20261019_120000_create_sync_state_table::Migration),
+            Box::new(m20261019_150000_create_block_fetch_jobs_table::Migration),
+            Box::new(m20261019_180000_create_webhook_deliveries_table::Migration),
+            Box::new(m20261019_200000_add_checkpoint_status_updated_at::Migration),
+            Box::new(m20261019_220000_add_checkpoint_lifecycle_timestamps::Migration),
+            Box::new(m20261019_230000_create_l1_blocks_table::Migration),
+            Box::new(m20261019_233000_create_evm_blocks_table::Migration),
+            Box::new(m20261019_235000_create_bridge_intents_table::Migration),
+            Box::new(m20261020_010000_create_bridge_duties_tables::Migration),
+            Box::new(m20261020_020000_drop_reorg_events_checkpoint_fk::Migration),
+            Box::new(m20261020_030000_add_block_fetch_job_next_attempt_at::Migration),
         ]
     }
 }
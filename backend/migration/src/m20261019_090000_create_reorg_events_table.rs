@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReorgEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ReorgEvents::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ReorgEvents::CheckpointIdx)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ReorgEvents::OldStatus).string().not_null())
+                    .col(ColumnDef::new(ReorgEvents::NewStatus).string().not_null())
+                    .col(ColumnDef::new(ReorgEvents::OldTxid).string().not_null())
+                    .col(ColumnDef::new(ReorgEvents::NewTxid).string().not_null())
+                    .col(
+                        ColumnDef::new(ReorgEvents::DetectedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_reorg_events_checkpoint_idx")
+                            .from(ReorgEvents::Table, ReorgEvents::CheckpointIdx)
+                            .to(Checkpoints::Table, Checkpoints::Idx),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_reorg_events_checkpoint_idx")
+                    .table(ReorgEvents::Table)
+                    .col(ReorgEvents::CheckpointIdx)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_reorg_events_checkpoint_idx")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ReorgEvents::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReorgEvents {
+    Table,
+    Id,
+    CheckpointIdx,
+    OldStatus,
+    NewStatus,
+    OldTxid,
+    NewTxid,
+    DetectedAt,
+}
+
+#[derive(DeriveIden)]
+enum Checkpoints {
+    Table,
+    Idx,
+}
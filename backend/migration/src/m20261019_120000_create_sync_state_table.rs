@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SyncState::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SyncState::Name)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SyncState::Value).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(SyncState::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Seed the ingestion cursors from data indexed before this table existed
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let db = manager.get_connection();
+        db.execute_unprepared(&format!(
+            "INSERT INTO sync_state (name, value, updated_at) \
+             SELECT 'last_checkpoint_ingested', MAX(idx), {now} FROM checkpoints HAVING COUNT(*) > 0"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "INSERT INTO sync_state (name, value, updated_at) \
+             SELECT 'last_block_ingested', MAX(height), {now} FROM blocks HAVING COUNT(*) > 0"
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SyncState::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SyncState {
+    Table,
+    Name,
+    Value,
+    UpdatedAt,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlockFetchJobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BlockFetchJobs::CheckpointIdx)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BlockFetchJobs::State).string().not_null())
+                    .col(
+                        ColumnDef::new(BlockFetchJobs::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(BlockFetchJobs::LastError).text().null())
+                    .col(
+                        ColumnDef::new(BlockFetchJobs::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BlockFetchJobs::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The block fetcher claims the lowest queued checkpoint first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_block_fetch_jobs_state")
+                    .table(BlockFetchJobs::Table)
+                    .col(BlockFetchJobs::State)
+                    .col(BlockFetchJobs::CheckpointIdx)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BlockFetchJobs::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlockFetchJobs {
+    Table,
+    CheckpointIdx,
+    State,
+    Attempts,
+    LastError,
+    CreatedAt,
+    UpdatedAt,
+}
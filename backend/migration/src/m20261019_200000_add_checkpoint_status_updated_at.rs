@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Checkpoints::Table)
+                    .add_column(
+                        ColumnDef::new(Checkpoints::StatusUpdatedAt)
+                            .big_integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // When existing checkpoints entered their status is unknown, count from now on
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        manager
+            .get_connection()
+            .execute_unprepared(&format!("UPDATE checkpoints SET status_updated_at = {now}"))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Checkpoints::Table)
+                    .drop_column(Checkpoints::StatusUpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Checkpoints {
+    Table,
+    StatusUpdatedAt,
+}
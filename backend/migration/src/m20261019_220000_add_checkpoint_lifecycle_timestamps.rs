@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One column per statement, SQLite cannot add several at once. The lifecycle of
+        // checkpoints stored before this migration is unknown and stays NULL.
+        for column in [
+            Checkpoints::FirstSeenAt,
+            Checkpoints::ConfirmedAt,
+            Checkpoints::FinalizedAt,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Checkpoints::Table)
+                        .add_column(ColumnDef::new(column).big_integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Lifecycle statistics select checkpoints by when they were confirmed or finalized
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_checkpoints_confirmed_at")
+                    .table(Checkpoints::Table)
+                    .col(Checkpoints::ConfirmedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_checkpoints_finalized_at")
+                    .table(Checkpoints::Table)
+                    .col(Checkpoints::FinalizedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for index in [
+            "idx_checkpoints_confirmed_at",
+            "idx_checkpoints_finalized_at",
+        ] {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(index)
+                        .table(Checkpoints::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        for column in [
+            Checkpoints::FirstSeenAt,
+            Checkpoints::ConfirmedAt,
+            Checkpoints::FinalizedAt,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Checkpoints::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden, Clone, Copy)]
+enum Checkpoints {
+    Table,
+    FirstSeenAt,
+    ConfirmedAt,
+    FinalizedAt,
+}
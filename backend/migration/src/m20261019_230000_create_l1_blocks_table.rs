@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(L1Blocks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(L1Blocks::Height)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(L1Blocks::BlockHash).string().not_null())
+                    .col(ColumnDef::new(L1Blocks::Timestamp).big_integer().null())
+                    .col(
+                        ColumnDef::new(L1Blocks::CheckpointIdx)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_l1_blocks_checkpoint_idx")
+                            .from(L1Blocks::Table, L1Blocks::CheckpointIdx)
+                            .to(Checkpoints::Table, Checkpoints::Idx),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Search looks L1 blocks up by hash, purges delete them by checkpoint
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_l1_blocks_block_hash")
+                    .table(L1Blocks::Table)
+                    .col(L1Blocks::BlockHash)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_l1_blocks_checkpoint_idx")
+                    .table(L1Blocks::Table)
+                    .col(L1Blocks::CheckpointIdx)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(L1Blocks::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum L1Blocks {
+    Table,
+    Height,
+    BlockHash,
+    Timestamp,
+    CheckpointIdx,
+}
+
+#[derive(DeriveIden)]
+enum Checkpoints {
+    Table,
+    Idx,
+}
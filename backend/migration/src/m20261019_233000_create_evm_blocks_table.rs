@@ -0,0 +1,102 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Blocks stored before this migration get their hash from the fullnode when linked
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Blocks::Table)
+                    .add_column(ColumnDef::new(Blocks::ExecSegmentHash).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(EvmBlocks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EvmBlocks::L2Height)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(EvmBlocks::EvmBlockNumber)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(EvmBlocks::EvmBlockHash).string().not_null())
+                    .col(
+                        ColumnDef::new(EvmBlocks::CheckpointIdx)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_evm_blocks_l2_height")
+                            .from(EvmBlocks::Table, EvmBlocks::L2Height)
+                            .to(Blocks::Table, Blocks::Height),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Search looks EVM blocks up by number and hash, purges delete them by checkpoint
+        for (name, column) in [
+            ("idx_evm_blocks_number", EvmBlocks::EvmBlockNumber),
+            ("idx_evm_blocks_hash", EvmBlocks::EvmBlockHash),
+            ("idx_evm_blocks_checkpoint_idx", EvmBlocks::CheckpointIdx),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(EvmBlocks::Table)
+                        .col(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EvmBlocks::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Blocks::Table)
+                    .drop_column(Blocks::ExecSegmentHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden, Clone, Copy)]
+enum EvmBlocks {
+    Table,
+    L2Height,
+    EvmBlockNumber,
+    EvmBlockHash,
+    CheckpointIdx,
+}
+
+#[derive(DeriveIden)]
+enum Blocks {
+    Table,
+    Height,
+    ExecSegmentHash,
+}
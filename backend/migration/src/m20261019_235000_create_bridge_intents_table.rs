@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BridgeIntents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BridgeIntents::L2Height)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BridgeIntents::Kind).string().not_null())
+                    .col(ColumnDef::new(BridgeIntents::Position).integer().not_null())
+                    .col(
+                        ColumnDef::new(BridgeIntents::L2BlockHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BridgeIntents::Amount)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BridgeIntents::Destination)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BridgeIntents::Txid).string().null())
+                    .col(
+                        ColumnDef::new(BridgeIntents::DepositIdx)
+                            .big_integer()
+                            .null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(BridgeIntents::L2Height)
+                            .col(BridgeIntents::Kind)
+                            .col(BridgeIntents::Position),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Withdrawals are looked up by the transaction requesting them
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bridge_intents_txid")
+                    .table(BridgeIntents::Table)
+                    .col(BridgeIntents::Txid)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BridgeIntents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BridgeIntents {
+    Table,
+    L2Height,
+    Kind,
+    Position,
+    L2BlockHash,
+    Amount,
+    Destination,
+    Txid,
+    DepositIdx,
+}
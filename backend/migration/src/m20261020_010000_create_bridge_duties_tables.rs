@@ -0,0 +1,112 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BridgeDuties::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BridgeDuties::OperatorIdx)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BridgeDuties::Kind).string().not_null())
+                    .col(ColumnDef::new(BridgeDuties::Outpoint).string().not_null())
+                    .col(ColumnDef::new(BridgeDuties::Amount).big_integer().null())
+                    .col(
+                        ColumnDef::new(BridgeDuties::Destination)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BridgeDuties::ExecDeadline)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(BridgeDuties::SeenAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(BridgeDuties::OperatorIdx)
+                            .col(BridgeDuties::Kind)
+                            .col(BridgeDuties::Outpoint),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Duties are matched to intents by destination
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bridge_duties_destination")
+                    .table(BridgeDuties::Table)
+                    .col(BridgeDuties::Destination)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BridgeDutyCursors::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BridgeDutyCursors::OperatorIdx)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(BridgeDutyCursors::NextIndex)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BridgeDutyCursors::UpdatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BridgeDutyCursors::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BridgeDuties::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BridgeDuties {
+    Table,
+    OperatorIdx,
+    Kind,
+    Outpoint,
+    Amount,
+    Destination,
+    ExecDeadline,
+    SeenAt,
+}
+
+#[derive(DeriveIden)]
+enum BridgeDutyCursors {
+    Table,
+    OperatorIdx,
+    NextIndex,
+    UpdatedAt,
+}
@@ -0,0 +1,142 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+const FOREIGN_KEY: &str = "fk_reorg_events_checkpoint_idx";
+const INDEX: &str = "idx_reorg_events_checkpoint_idx";
+
+/// Reorg events are the only record of a reorg and outlive the checkpoints they were
+/// detected on when those are deleted for re-indexing, so `checkpoint_idx` no longer
+/// references `checkpoints`
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => rebuild(manager, false).await,
+            _ => {
+                manager
+                    .drop_foreign_key(
+                        ForeignKey::drop()
+                            .name(FOREIGN_KEY)
+                            .table(ReorgEvents::Table)
+                            .to_owned(),
+                    )
+                    .await
+            }
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // the events of deleted checkpoints cannot reference them again
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM reorg_events WHERE checkpoint_idx NOT IN (SELECT idx FROM checkpoints)",
+            )
+            .await?;
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => rebuild(manager, true).await,
+            _ => {
+                manager
+                    .create_foreign_key(
+                        ForeignKey::create()
+                            .name(FOREIGN_KEY)
+                            .from(ReorgEvents::Table, ReorgEvents::CheckpointIdx)
+                            .to(Checkpoints::Table, Checkpoints::Idx)
+                            .to_owned(),
+                    )
+                    .await
+            }
+        }
+    }
+}
+
+/// SQLite cannot add or drop a constraint, the table is copied into a new one with or
+/// without the foreign key
+async fn rebuild(manager: &SchemaManager<'_>, with_foreign_key: bool) -> Result<(), DbErr> {
+    let mut table = Table::create()
+        .table(ReorgEventsRebuilt::Table)
+        .col(
+            ColumnDef::new(ReorgEvents::Id)
+                .big_integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(ReorgEvents::CheckpointIdx)
+                .big_integer()
+                .not_null(),
+        )
+        .col(ColumnDef::new(ReorgEvents::OldStatus).string().not_null())
+        .col(ColumnDef::new(ReorgEvents::NewStatus).string().not_null())
+        .col(ColumnDef::new(ReorgEvents::OldTxid).string().not_null())
+        .col(ColumnDef::new(ReorgEvents::NewTxid).string().not_null())
+        .col(
+            ColumnDef::new(ReorgEvents::DetectedAt)
+                .big_integer()
+                .not_null(),
+        )
+        .to_owned();
+    if with_foreign_key {
+        table.foreign_key(
+            ForeignKey::create()
+                .name(FOREIGN_KEY)
+                .from(ReorgEventsRebuilt::Table, ReorgEvents::CheckpointIdx)
+                .to(Checkpoints::Table, Checkpoints::Idx),
+        );
+    }
+    manager.create_table(table).await?;
+
+    manager
+        .get_connection()
+        .execute_unprepared("INSERT INTO reorg_events_rebuilt SELECT * FROM reorg_events")
+        .await?;
+    manager
+        .drop_table(Table::drop().table(ReorgEvents::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(
+            Table::rename()
+                .table(ReorgEventsRebuilt::Table, ReorgEvents::Table)
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name(INDEX)
+                .table(ReorgEvents::Table)
+                .col(ReorgEvents::CheckpointIdx)
+                .to_owned(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[derive(DeriveIden)]
+enum ReorgEvents {
+    Table,
+    Id,
+    CheckpointIdx,
+    OldStatus,
+    NewStatus,
+    OldTxid,
+    NewTxid,
+    DetectedAt,
+}
+
+#[derive(DeriveIden)]
+enum ReorgEventsRebuilt {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Checkpoints {
+    Table,
+    Idx,
+}
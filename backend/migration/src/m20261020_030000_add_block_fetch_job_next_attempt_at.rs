@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Failed jobs already stored are due right away
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlockFetchJobs::Table)
+                    .add_column(
+                        ColumnDef::new(BlockFetchJobs::NextAttemptAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlockFetchJobs::Table)
+                    .drop_column(BlockFetchJobs::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlockFetchJobs {
+    Table,
+    NextAttemptAt,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// What an audit finding is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSubject {
+    /// A checkpoint, identified by its index
+    Checkpoint,
+    /// A block, identified by its height
+    Block,
+}
+
+/// A stored field that differs from what the fullnode returns.
+/// A `None` value means the record is missing on that side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditDiff {
+    pub subject: AuditSubject,
+    /// Checkpoint index or block height
+    pub id: u64,
+    pub field: String,
+    pub stored: Option<String>,
+    pub fullnode: Option<String>,
+}
+
+/// A checkpoint or block the fullnode could not be asked about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFailure {
+    pub subject: AuditSubject,
+    pub id: u64,
+    pub error: String,
+}
+
+/// Result of comparing a range of stored checkpoints and their blocks with the fullnode
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditReport {
+    /// First and last checkpoint audited
+    pub from_checkpoint: u64,
+    pub to_checkpoint: u64,
+    pub checked_checkpoints: u64,
+    pub checked_blocks: u64,
+    pub diffs: Vec<AuditDiff>,
+    pub failures: Vec<AuditFailure>,
+    /// UNIX timestamps (seconds) of the start and the end of the audit
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+impl AuditReport {
+    /// Whether every audited record matches the fullnode
+    pub fn is_clean(&self) -> bool {
+        self.diffs.is_empty() && self.failures.is_empty()
+    }
+}
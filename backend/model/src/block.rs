@@ -11,6 +11,9 @@ pub struct Model {
     pub block_hash: String,
     pub height: i64,
     pub checkpoint_idx: i64,
+    /// Hash of the execution segment, the hash of the EVM block. Unknown for blocks stored
+    /// before it was recorded.
+    pub exec_segment_hash: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -26,6 +29,7 @@ impl From<RpcBlockHeader> for ActiveModel {
             block_hash: Set(header.block_id),
             height: Set(b_id),
             checkpoint_idx: NotSet,
+            exec_segment_hash: Set(Some(header.exec_segment_hash)),
         }
     }
 }
@@ -54,3 +58,24 @@ pub struct RpcBlockHeader {
     /// The root hash of the state tree
     pub state_root: String,
 }
+
+/// Represents a stored block returned by the API
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcBlock {
+    pub height: u64,
+    pub block_hash: String,
+    pub checkpoint_idx: u64,
+    /// Hash of the execution segment, unknown for blocks stored before it was recorded
+    pub exec_segment_hash: Option<String>,
+}
+
+impl From<Model> for RpcBlock {
+    fn from(model: Model) -> Self {
+        Self {
+            height: PgU64::from_i64(model.height).0,
+            block_hash: model.block_hash,
+            checkpoint_idx: PgU64::from_i64(model.checkpoint_idx).0,
+            exec_segment_hash: model.exec_segment_hash,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A request to fetch the blocks of one checkpoint. There is at most one job per checkpoint,
+/// asking for it again while it is pending is a no-op.
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "block_fetch_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub checkpoint_idx: i64,
+    /// One of the [`JobState`] values
+    pub state: String,
+    /// Number of times the block fetcher picked the job up
+    pub attempts: i32,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    /// UNIX timestamp (seconds) before which a failed job is not claimed again
+    pub next_attempt_at: i64,
+    /// UNIX timestamps (seconds) of the first request and of the last state change
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// Lifecycle of a block fetch job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    /// Waiting for the block fetcher
+    Queued,
+    /// Claimed by the block fetcher, its blocks are being fetched
+    Running,
+    /// Every block of the checkpoint is stored
+    Done,
+    /// The last attempt failed, the job runs again after [`retry_delay`]
+    Failed,
+}
+
+impl JobState {
+    pub const ALL: [JobState; 4] = [
+        JobState::Queued,
+        JobState::Running,
+        JobState::Done,
+        JobState::Failed,
+    ];
+
+    /// Value of the `state` column for this state
+    pub fn state(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+        }
+    }
+
+    /// State of a job from the value of its `state` column
+    pub fn from_state(state: &str) -> Option<Self> {
+        JobState::ALL
+            .into_iter()
+            .find(|job_state| job_state.state() == state)
+    }
+
+    /// Whether the job still has to run
+    pub fn is_pending(&self) -> bool {
+        matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+impl Display for JobState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.state())
+    }
+}
+
+/// Seconds before a job that failed `attempts` times is claimed again, doubled on every
+/// failure up to five minutes
+pub fn retry_delay(attempts: i32) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    (5i64 << exponent).min(300)
+}
+
+/// Number of block fetch jobs in each state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFetchJobCounts {
+    pub queued: u64,
+    pub running: u64,
+    pub done: u64,
+    pub failed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_the_retry_delay_up_to_five_minutes() {
+        let delays: Vec<i64> = (0..=8).map(retry_delay).collect();
+        assert_eq!(delays, [5, 5, 10, 20, 40, 80, 160, 300, 300]);
+        assert_eq!(retry_delay(i32::MAX), 300);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::bridge_intent::{self, IntentKind};
+use crate::pgu64::PgU64;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A duty of a bridge operator, as listed by `strata_getBridgeDuties`: signing a deposit
+/// or paying out a withdrawal
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "bridge_duties")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub operator_idx: i32,
+    /// [`IntentKind`] of the intent the duty serves
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub kind: String,
+    /// Deposit request outpoint of a deposit, deposit outpoint paying out a withdrawal
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub outpoint: String,
+    /// Amount in satoshis of a deposit, unset for withdrawals
+    pub amount: Option<i64>,
+    /// EL address credited by a deposit, L1 descriptor paid by a withdrawal
+    pub destination: String,
+    /// Height by which the operator must pay out a withdrawal, unset for deposits
+    pub exec_deadline: Option<i64>,
+    /// UNIX timestamp (seconds) of the first time the duty was listed
+    pub seen_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl Model {
+    /// Whether the duty serves `intent`: the same destination and, for deposits, the same
+    /// amount. The duties do not carry the L2 transaction, so a destination used by several
+    /// intents matches the duties of all of them.
+    pub fn serves(&self, intent: &bridge_intent::Model) -> bool {
+        self.kind == intent.kind
+            && self.destination == intent.destination
+            && self.amount.is_none_or(|amount| amount == intent.amount)
+    }
+}
+
+/// Duties of one operator, as returned by `strata_getBridgeDuties(operator_idx, start_index)`.
+/// `stop_index` is the `start_index` of the next call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcBridgeDuties {
+    pub duties: Vec<RpcBridgeDuty>,
+    pub start_index: u64,
+    pub stop_index: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload")]
+pub enum RpcBridgeDuty {
+    SignDeposit(RpcDepositInfo),
+    FulfillWithdrawal(RpcWithdrawalInfo),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcDepositInfo {
+    pub deposit_request_outpoint: String,
+    pub el_address: String,
+    pub total_amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcWithdrawalInfo {
+    pub deposit_outpoint: String,
+    pub user_destination: String,
+    pub assigned_operator_idx: u32,
+    pub exec_deadline: u64,
+}
+
+impl RpcBridgeDuty {
+    /// The duty of operator `operator_idx`, first listed at `seen_at`
+    pub fn into_model(self, operator_idx: u32, seen_at: i64) -> Model {
+        let operator_idx = operator_idx as i32;
+        match self {
+            RpcBridgeDuty::SignDeposit(deposit) => Model {
+                operator_idx,
+                kind: IntentKind::Deposit.kind().to_string(),
+                outpoint: deposit.deposit_request_outpoint,
+                amount: Some(PgU64(deposit.total_amount).to_i64()),
+                destination: deposit.el_address,
+                exec_deadline: None,
+                seen_at,
+            },
+            RpcBridgeDuty::FulfillWithdrawal(withdrawal) => Model {
+                operator_idx,
+                kind: IntentKind::Withdrawal.kind().to_string(),
+                outpoint: withdrawal.deposit_outpoint,
+                amount: None,
+                destination: withdrawal.user_destination,
+                exec_deadline: Some(PgU64(withdrawal.exec_deadline).to_i64()),
+                seen_at,
+            },
+        }
+    }
+}
+
+/// A duty returned to the frontend along with the intent it serves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcOperatorDuty {
+    pub operator_idx: u32,
+    pub outpoint: String,
+    pub exec_deadline: Option<u64>,
+    pub seen_at: i64,
+}
+
+impl From<Model> for RpcOperatorDuty {
+    fn from(duty: Model) -> Self {
+        Self {
+            operator_idx: duty.operator_idx as u32,
+            outpoint: duty.outpoint,
+            exec_deadline: duty.exec_deadline.map(PgU64::i64_to_u64),
+            seen_at: duty.seen_at,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `start_index` of the next `strata_getBridgeDuties` call for an operator
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "bridge_duty_cursors")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub operator_idx: i32,
+    pub next_index: i64,
+    /// UNIX timestamp (seconds) of the last update
+    pub updated_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
@@ -0,0 +1,181 @@
+use crate::bridge_duty::{self, RpcOperatorDuty};
+use crate::checkpoint::{self, CheckpointState};
+use crate::pgu64::PgU64;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A bridge deposit or withdrawal intent, as applied or emitted by the execution update of
+/// an L2 block
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "bridge_intents")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub l2_height: i64,
+    /// One of the [`IntentKind`] values
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub kind: String,
+    /// Position of the intent among the intents of its kind in the block
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub position: i32,
+    pub l2_block_hash: String,
+    /// Amount in satoshis
+    pub amount: i64,
+    /// EL address credited by a deposit, L1 descriptor paid by a withdrawal
+    pub destination: String,
+    /// EL transaction requesting a withdrawal, unset for deposits
+    pub txid: Option<String>,
+    /// Index of the deposit in the bridge, unset for withdrawals
+    pub deposit_idx: Option<i64>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// Direction of a bridge intent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntentKind {
+    /// BTC deposited on L1 and minted on L2
+    Deposit,
+    /// BTC burnt on L2 to be paid out on L1
+    Withdrawal,
+}
+
+impl IntentKind {
+    /// Value of the `kind` column for this kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IntentKind::Deposit => "deposit",
+            IntentKind::Withdrawal => "withdrawal",
+        }
+    }
+}
+
+impl Display for IntentKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind())
+    }
+}
+
+/// Execution update of an L2 block, as returned by `strata_getExecUpdateById`, reduced to
+/// the bridge operations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcExecUpdate {
+    pub update_idx: u64,
+    /// Deposits applied to the execution layer by the update
+    #[serde(default)]
+    pub deposits: Vec<RpcDepositIntent>,
+    /// Withdrawals requested on the execution layer during the update
+    #[serde(default)]
+    pub withdrawals: Vec<RpcWithdrawalIntent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcDepositIntent {
+    pub intent_idx: u64,
+    pub amt: u64,
+    pub dest_addr: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcWithdrawalIntent {
+    pub amt: u64,
+    pub destination: String,
+    pub withdrawal_txid: String,
+}
+
+impl RpcExecUpdate {
+    /// Intents of the block at `height` with hash `block_hash` whose update this is
+    pub fn into_intents(self, height: i64, block_hash: &str) -> Vec<Model> {
+        let deposits = self
+            .deposits
+            .into_iter()
+            .enumerate()
+            .map(|(position, deposit)| Model {
+                l2_height: height,
+                kind: IntentKind::Deposit.kind().to_string(),
+                position: position as i32,
+                l2_block_hash: block_hash.to_string(),
+                amount: PgU64(deposit.amt).to_i64(),
+                destination: deposit.dest_addr,
+                txid: None,
+                deposit_idx: Some(PgU64(deposit.intent_idx).to_i64()),
+            });
+        let withdrawals = self
+            .withdrawals
+            .into_iter()
+            .enumerate()
+            .map(|(position, withdrawal)| Model {
+                l2_height: height,
+                kind: IntentKind::Withdrawal.kind().to_string(),
+                position: position as i32,
+                l2_block_hash: block_hash.to_string(),
+                amount: PgU64(withdrawal.amt).to_i64(),
+                destination: withdrawal.destination,
+                txid: Some(normalize_txid(&withdrawal.withdrawal_txid)),
+                deposit_idx: None,
+            });
+        deposits.chain(withdrawals).collect()
+    }
+}
+
+/// Transaction ids are stored lowercase without the 0x prefix, like block hashes
+pub fn normalize_txid(txid: &str) -> String {
+    txid.trim_start_matches("0x").to_ascii_lowercase()
+}
+
+/// A bridge intent returned to the frontend, with the checkpoint containing its block.
+///
+/// The intent is final once its checkpoint is finalized on L1, the checkpoint is unknown
+/// until the explorer indexed it. `duties` are the operator duties listed for it, see
+/// [`bridge_duty::Model::serves`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcBridgeIntent {
+    pub kind: String,
+    pub l2_height: u64,
+    pub l2_block_hash: String,
+    pub position: i32,
+    pub amount: u64,
+    pub destination: String,
+    pub txid: Option<String>,
+    pub deposit_idx: Option<u64>,
+    pub checkpoint_idx: Option<u64>,
+    pub checkpoint_status: CheckpointState,
+    pub finalized: bool,
+    pub duties: Vec<RpcOperatorDuty>,
+}
+
+impl RpcBridgeIntent {
+    pub fn new(
+        intent: Model,
+        checkpoint: Option<&checkpoint::Model>,
+        duties: &[bridge_duty::Model],
+    ) -> Self {
+        let duties = duties
+            .iter()
+            .filter(|duty| duty.serves(&intent))
+            .cloned()
+            .map(Into::into)
+            .collect();
+        let checkpoint_status = checkpoint.map_or(CheckpointState::Unknown, |checkpoint| {
+            CheckpointState::from_status(&checkpoint.status)
+        });
+        Self {
+            kind: intent.kind,
+            l2_height: PgU64::i64_to_u64(intent.l2_height),
+            l2_block_hash: intent.l2_block_hash,
+            position: intent.position,
+            amount: PgU64::i64_to_u64(intent.amount),
+            destination: intent.destination,
+            txid: intent.txid,
+            deposit_idx: intent.deposit_idx.map(PgU64::i64_to_u64),
+            checkpoint_idx: checkpoint.map(|checkpoint| PgU64::i64_to_u64(checkpoint.idx)),
+            checkpoint_status,
+            finalized: checkpoint_status == CheckpointState::Finalized,
+            duties,
+        }
+    }
+}
@@ -47,7 +47,9 @@ pub struct L2BlockCommitment {
     blkid: L2BlockId,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Variants are declared in lifecycle order so that comparing two statuses tells whether
+/// a checkpoint moved forward or backward (the latter only happens on an L1 reorg).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RpcCheckpointConfStatus {
     /// Pending to be posted on L1
@@ -82,6 +84,98 @@ impl Display for RpcCheckpointConfStatus {
     }
 }
 
+/// Lifecycle state of a stored checkpoint. `Unknown` covers checkpoints the fullnode reported
+/// without a confirmation status, stored as "-".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckpointState {
+    Unknown,
+    Pending,
+    Confirmed,
+    Finalized,
+}
+
+/// An allowed move between two checkpoint states
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateTransition {
+    /// The state stays the same
+    Unchanged,
+    /// The checkpoint moved forward in its lifecycle
+    Advance,
+    /// A confirmed checkpoint went back to pending, which only an L1 reorg does
+    Revert,
+}
+
+impl CheckpointState {
+    pub const ALL: [CheckpointState; 4] = [
+        CheckpointState::Unknown,
+        CheckpointState::Pending,
+        CheckpointState::Confirmed,
+        CheckpointState::Finalized,
+    ];
+
+    /// State of a checkpoint from the value of its `status` column
+    pub fn from_status(status: &str) -> Self {
+        status
+            .parse::<RpcCheckpointConfStatus>()
+            .map_or(CheckpointState::Unknown, Into::into)
+    }
+
+    /// Value of the `status` column for this state
+    pub fn status(&self) -> &'static str {
+        match self {
+            CheckpointState::Unknown => "-",
+            CheckpointState::Pending => "Pending",
+            CheckpointState::Confirmed => "Confirmed",
+            CheckpointState::Finalized => "Finalized",
+        }
+    }
+
+    /// A finalized checkpoint can no longer change
+    pub fn is_final(&self) -> bool {
+        *self == CheckpointState::Finalized
+    }
+
+    /// The transition from this state to `next`, `None` if it is not allowed.
+    ///
+    /// Checkpoints only move forward, except for a confirmed checkpoint whose L1 transaction
+    /// is reorged out and goes back to pending. A checkpoint never loses its status and a
+    /// finalized one never leaves that state.
+    pub fn transition_to(&self, next: CheckpointState) -> Option<StateTransition> {
+        use CheckpointState::*;
+        match (*self, next) {
+            (from, to) if from == to => Some(StateTransition::Unchanged),
+            (Unknown, _) | (Pending, Confirmed | Finalized) | (Confirmed, Finalized) => {
+                Some(StateTransition::Advance)
+            }
+            (Confirmed, Pending) => Some(StateTransition::Revert),
+            _ => None,
+        }
+    }
+}
+
+impl From<RpcCheckpointConfStatus> for CheckpointState {
+    fn from(status: RpcCheckpointConfStatus) -> Self {
+        match status {
+            RpcCheckpointConfStatus::Pending => CheckpointState::Pending,
+            RpcCheckpointConfStatus::Confirmed => CheckpointState::Confirmed,
+            RpcCheckpointConfStatus::Finalized => CheckpointState::Finalized,
+        }
+    }
+}
+
+impl Display for CheckpointState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state_str = match self {
+            CheckpointState::Unknown => "unknown",
+            CheckpointState::Pending => "pending",
+            CheckpointState::Confirmed => "confirmed",
+            CheckpointState::Finalized => "finalized",
+        };
+        write!(f, "{}", state_str)
+    }
+}
+
 #[derive(
     Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
 )]
@@ -95,6 +189,44 @@ pub struct Model {
     pub l2_end: i64,
     pub checkpoint_txid: String,
     pub status: String,
+    /// UNIX timestamp (seconds) of when the checkpoint entered its status
+    pub status_updated_at: Option<i64>,
+    /// UNIX timestamps (seconds) of the checkpoint lifecycle, see [`CheckpointLifecycle`]
+    pub first_seen_at: Option<i64>,
+    pub confirmed_at: Option<i64>,
+    pub finalized_at: Option<i64>,
+}
+
+impl Model {
+    /// `confirmed_at` and `finalized_at` once the checkpoint moves to `new_status` and
+    /// `new_txid`, observed at `now`.
+    ///
+    /// A transition is stamped when the explorer observes it. A new L1 transaction confirms
+    /// the checkpoint again, and going back to pending clears both timestamps.
+    pub fn lifecycle_after(
+        &self,
+        new_status: &str,
+        new_txid: &str,
+        now: i64,
+    ) -> (Option<i64>, Option<i64>) {
+        let from = CheckpointState::from_status(&self.status);
+        let to = CheckpointState::from_status(new_status);
+        if to < CheckpointState::Confirmed {
+            return (None, None);
+        }
+        let confirmed_at = if from < CheckpointState::Confirmed || self.checkpoint_txid != new_txid
+        {
+            Some(now)
+        } else {
+            self.confirmed_at
+        };
+        let finalized_at = match (from.is_final(), to.is_final()) {
+            (false, true) => Some(now),
+            (true, true) => self.finalized_at,
+            (_, false) => None,
+        };
+        (confirmed_at, finalized_at)
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -114,8 +246,14 @@ impl From<RpcCheckpointInfo> for ActiveModel {
                 .map_or("-".to_string(), |c| c.txid.clone())), // Extracting `txid`
             status: Set(info
                 .confirmation_status
-                .as_ref()
-                .map_or("-".to_string(), |s| format!("{:?}", s))), // Convert enum to string
+                .map_or(CheckpointState::Unknown, Into::into)
+                .status()
+                .to_string()),
+            // stamped by the repository when the checkpoint is stored
+            status_updated_at: Set(None),
+            first_seen_at: Set(None),
+            confirmed_at: Set(None),
+            finalized_at: Set(None),
         }
     }
 }
@@ -157,3 +295,175 @@ impl From<Model> for RpcCheckpointInfoCheckpointExp {
         }
     }
 }
+
+/// When a checkpoint was first indexed, confirmed on L1 and finalized, and the time spent
+/// between these steps in seconds.
+///
+/// `first_seen_at` is when the explorer stored the checkpoint. `confirmed_at` is the time of
+/// the L1 block including the checkpoint transaction when it is known, otherwise when the
+/// explorer observed the confirmation. `finalized_at` is when the explorer observed the
+/// finalization. Steps that happened before the checkpoint was indexed are unknown, and so
+/// are the durations involving them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointLifecycle {
+    pub idx: u64,
+    pub status: CheckpointState,
+    pub first_seen_at: Option<i64>,
+    pub confirmed_at: Option<i64>,
+    pub finalized_at: Option<i64>,
+    pub time_to_confirmation: Option<u64>,
+    pub confirmation_to_finality: Option<u64>,
+    pub time_to_finality: Option<u64>,
+}
+
+impl From<&Model> for CheckpointLifecycle {
+    fn from(model: &Model) -> Self {
+        Self {
+            idx: PgU64::i64_to_u64(model.idx),
+            status: CheckpointState::from_status(&model.status),
+            first_seen_at: model.first_seen_at,
+            confirmed_at: model.confirmed_at,
+            finalized_at: model.finalized_at,
+            time_to_confirmation: elapsed(model.first_seen_at, model.confirmed_at),
+            confirmation_to_finality: elapsed(model.confirmed_at, model.finalized_at),
+            time_to_finality: elapsed(model.first_seen_at, model.finalized_at),
+        }
+    }
+}
+
+/// Seconds from `start` to `end`, unknown if either is or if `end` comes first, e.g. the L1
+/// block of an old checkpoint predates its indexing
+fn elapsed(start: Option<i64>, end: Option<i64>) -> Option<u64> {
+    let seconds = end?.checked_sub(start?)?;
+    u64::try_from(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CheckpointState::*;
+
+    #[test]
+    fn transitions_between_every_pair_of_states() {
+        let cases = [
+            (Unknown, Unknown, Some(StateTransition::Unchanged)),
+            (Unknown, Pending, Some(StateTransition::Advance)),
+            (Unknown, Confirmed, Some(StateTransition::Advance)),
+            (Unknown, Finalized, Some(StateTransition::Advance)),
+            (Pending, Unknown, None),
+            (Pending, Pending, Some(StateTransition::Unchanged)),
+            (Pending, Confirmed, Some(StateTransition::Advance)),
+            (Pending, Finalized, Some(StateTransition::Advance)),
+            (Confirmed, Unknown, None),
+            (Confirmed, Pending, Some(StateTransition::Revert)),
+            (Confirmed, Confirmed, Some(StateTransition::Unchanged)),
+            (Confirmed, Finalized, Some(StateTransition::Advance)),
+            (Finalized, Unknown, None),
+            (Finalized, Pending, None),
+            (Finalized, Confirmed, None),
+            (Finalized, Finalized, Some(StateTransition::Unchanged)),
+        ];
+        assert_eq!(cases.len(), CheckpointState::ALL.len().pow(2));
+        for (from, to, expected) in cases {
+            assert_eq!(from.transition_to(to), expected, "{} -> {}", from, to);
+        }
+    }
+
+    fn checkpoint(
+        state: CheckpointState,
+        txid: &str,
+        lifecycle: (Option<i64>, Option<i64>),
+    ) -> Model {
+        Model {
+            idx: 0,
+            l1_start: 0,
+            l1_end: 0,
+            l2_start: 0,
+            l2_end: 0,
+            checkpoint_txid: txid.to_string(),
+            status: state.status().to_string(),
+            status_updated_at: None,
+            first_seen_at: Some(10),
+            confirmed_at: lifecycle.0,
+            finalized_at: lifecycle.1,
+        }
+    }
+
+    #[test]
+    fn stamps_the_lifecycle_when_the_transitions_are_observed() {
+        const NOW: i64 = 500;
+        let unstamped = (None, None);
+        let confirmed = (Some(100), None);
+        let finalized = (Some(100), Some(200));
+        let cases = [
+            // not confirmed yet
+            ((Unknown, "-", unstamped), (Pending, "-"), (None, None)),
+            ((Pending, "-", unstamped), (Pending, "aa"), (None, None)),
+            // forward
+            (
+                (Unknown, "-", unstamped),
+                (Confirmed, "aa"),
+                (Some(NOW), None),
+            ),
+            (
+                (Pending, "-", unstamped),
+                (Confirmed, "aa"),
+                (Some(NOW), None),
+            ),
+            (
+                (Pending, "-", unstamped),
+                (Finalized, "aa"),
+                (Some(NOW), Some(NOW)),
+            ),
+            (
+                (Confirmed, "aa", confirmed),
+                (Finalized, "aa"),
+                (Some(100), Some(NOW)),
+            ),
+            // unchanged, a checkpoint stored before the lifecycle was recorded stays unknown
+            (
+                (Confirmed, "aa", confirmed),
+                (Confirmed, "aa"),
+                (Some(100), None),
+            ),
+            (
+                (Confirmed, "aa", unstamped),
+                (Confirmed, "aa"),
+                (None, None),
+            ),
+            (
+                (Finalized, "aa", finalized),
+                (Finalized, "aa"),
+                (Some(100), Some(200)),
+            ),
+            // a new L1 transaction confirms the checkpoint again
+            (
+                (Confirmed, "aa", confirmed),
+                (Confirmed, "bb"),
+                (Some(NOW), None),
+            ),
+            (
+                (Confirmed, "aa", confirmed),
+                (Finalized, "bb"),
+                (Some(NOW), Some(NOW)),
+            ),
+            (
+                (Finalized, "aa", finalized),
+                (Finalized, "bb"),
+                (Some(NOW), Some(200)),
+            ),
+            // back to pending
+            ((Confirmed, "aa", confirmed), (Pending, "-"), (None, None)),
+            ((Finalized, "aa", finalized), (Pending, "-"), (None, None)),
+        ];
+        for ((from, old_txid, lifecycle), (to, new_txid), expected) in cases {
+            let stamped =
+                checkpoint(from, old_txid, lifecycle).lifecycle_after(to.status(), new_txid, NOW);
+            assert_eq!(
+                stamped, expected,
+                "{} {} -> {} {}",
+                from, old_txid, to, new_txid
+            );
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The execution-layer block of an L2 block, as reported by the execution client
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "evm_blocks")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub l2_height: i64,
+    pub evm_block_number: i64,
+    /// 0x-prefixed hash of the EVM block
+    pub evm_block_hash: String,
+    pub checkpoint_idx: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Inclusive range of checkpoint indexes or block heights missing from the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// A checkpoint whose stored blocks do not add up to its L2 range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockCountMismatch {
+    pub checkpoint_idx: u64,
+    /// The L2 height range that the checkpoint covers (start, end)
+    pub l2_range: (u64, u64),
+    /// Number of blocks the L2 range covers
+    pub expected: u64,
+    /// Number of blocks stored for the checkpoint
+    pub actual: u64,
+}
+
+/// Holes and inconsistencies found in the indexed data.
+///
+/// Only the data below the latest stored checkpoint and block is checked, what is beyond
+/// them has simply not been indexed yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub missing_checkpoints: Vec<MissingRange>,
+    pub missing_blocks: Vec<MissingRange>,
+    pub block_count_mismatches: Vec<BlockCountMismatch>,
+    /// UNIX timestamp (seconds) of the check
+    pub checked_at: i64,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing_checkpoints.is_empty()
+            && self.missing_blocks.is_empty()
+            && self.block_count_mismatches.is_empty()
+    }
+}
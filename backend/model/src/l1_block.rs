@@ -0,0 +1,52 @@
+use crate::checkpoint::RpcCheckpointInfo;
+use crate::pgu64::PgU64;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An L1 block covered by a checkpoint. A block at the boundary of two checkpoint ranges
+/// belongs to the first checkpoint that covered it.
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "l1_blocks")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub height: i64,
+    pub block_hash: String,
+    /// UNIX timestamp (seconds) of the block header, unknown until it is looked up
+    pub timestamp: Option<i64>,
+    pub checkpoint_idx: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl Model {
+    /// The first and last L1 blocks of the range of `checkpoint`, whose hashes the fullnode
+    /// reports along with the checkpoint
+    pub fn from_checkpoint_range(checkpoint: &RpcCheckpointInfo) -> Vec<Self> {
+        let (start, end) = &checkpoint.l1_range;
+        let mut blocks = vec![start];
+        if end.height != start.height {
+            blocks.push(end);
+        }
+        blocks
+            .into_iter()
+            .map(|block| Self {
+                height: PgU64(block.height).to_i64(),
+                block_hash: block.blkid.clone(),
+                timestamp: None,
+                checkpoint_idx: PgU64(checkpoint.idx).to_i64(),
+            })
+            .collect()
+    }
+}
+
+/// L1 range of a checkpoint with blocks not stored yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncompleteL1Range {
+    pub checkpoint_idx: i64,
+    /// First and last L1 heights of the checkpoint
+    pub start: i64,
+    pub end: i64,
+}
@@ -1,3 +1,14 @@
+pub mod audit;
 pub mod block;
+pub mod block_fetch_job;
+pub mod bridge_duty;
+pub mod bridge_duty_cursor;
+pub mod bridge_intent;
 pub mod checkpoint;
+pub mod evm_block;
+pub mod integrity;
+pub mod l1_block;
 pub mod pgu64;
+pub mod reorg_event;
+pub mod sync_state;
+pub mod webhook_delivery;
@@ -0,0 +1,47 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Records a checkpoint update that could only have been caused by an L1 reorg,
+/// i.e. a status moving backwards or an already known txid being replaced.
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "reorg_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub checkpoint_idx: i64,
+    pub old_status: String,
+    pub new_status: String,
+    pub old_txid: String,
+    pub new_txid: String,
+    /// UNIX timestamp (seconds) of when the reorg was detected
+    pub detected_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// Represents a reorg event returned to the frontend.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RpcReorgEvent {
+    pub checkpoint_idx: u64,
+    pub old_status: String,
+    pub new_status: String,
+    pub old_txid: String,
+    pub new_txid: String,
+    pub detected_at: i64,
+}
+
+impl From<Model> for RpcReorgEvent {
+    fn from(model: Model) -> Self {
+        Self {
+            checkpoint_idx: crate::pgu64::PgU64::i64_to_u64(model.checkpoint_idx),
+            old_status: model.old_status,
+            new_status: model.new_status,
+            old_txid: model.old_txid,
+            new_txid: model.new_txid,
+            detected_at: model.detected_at,
+        }
+    }
+}
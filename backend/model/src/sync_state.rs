@@ -0,0 +1,118 @@
+use crate::pgu64::PgU64;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A named indexer cursor. Values are stored in the same `PgU64` encoding as the
+/// data they point at.
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "sync_state")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    pub value: i64,
+    /// UNIX timestamp (seconds) of the last update
+    pub updated_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// The cursors tracked in the `sync_state` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncCursor {
+    /// Index of the last checkpoint inserted
+    CheckpointIngested,
+    /// Height of the last block inserted
+    BlockIngested,
+    /// Index of the last checkpoint whose status was reconciled with the fullnode
+    CheckpointReconciled,
+    /// Index of the first checkpoint indexed, declared by the indexer on startup. The
+    /// continuity checks treat it as the genesis.
+    IndexStart,
+    /// Index of the last checkpoint the indexer is configured to index, unset when it
+    /// follows the fullnode tip
+    IndexEnd,
+    /// Height of the last block whose bridge intents were indexed
+    BridgeScanned,
+}
+
+impl SyncCursor {
+    pub const ALL: [SyncCursor; 6] = [
+        SyncCursor::CheckpointIngested,
+        SyncCursor::BlockIngested,
+        SyncCursor::CheckpointReconciled,
+        SyncCursor::IndexStart,
+        SyncCursor::IndexEnd,
+        SyncCursor::BridgeScanned,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SyncCursor::CheckpointIngested => "last_checkpoint_ingested",
+            SyncCursor::BlockIngested => "last_block_ingested",
+            SyncCursor::CheckpointReconciled => "last_checkpoint_reconciled",
+            SyncCursor::IndexStart => "index_start_checkpoint",
+            SyncCursor::IndexEnd => "index_end_checkpoint",
+            SyncCursor::BridgeScanned => "last_block_bridge_scanned",
+        }
+    }
+}
+
+impl Display for SyncCursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Represents a sync cursor returned to the frontend.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RpcSyncCursor {
+    pub name: String,
+    pub value: u64,
+    pub updated_at: i64,
+}
+
+impl From<Model> for RpcSyncCursor {
+    fn from(model: Model) -> Self {
+        Self {
+            name: model.name,
+            value: PgU64::i64_to_u64(model.value),
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+/// Range of checkpoints the indexer is configured to index, along with what is actually
+/// stored, returned to the frontend.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct IndexRange {
+    /// First checkpoint of the index, checkpoints before it are never fetched
+    pub start_checkpoint: u64,
+    /// Last checkpoint of the index, `None` when following the fullnode tip
+    pub end_checkpoint: Option<u64>,
+    /// Lowest and highest checkpoint stored
+    pub first_checkpoint: Option<u64>,
+    pub last_checkpoint: Option<u64>,
+    /// Lowest and highest block height stored
+    pub first_block: Option<u64>,
+    pub last_block: Option<u64>,
+}
+
+impl IndexRange {
+    /// Number of checkpoints the index is behind the fullnode tip, not counting the
+    /// checkpoints after the end of the range
+    pub fn checkpoint_lag(&self, fullnode_tip: u64) -> u64 {
+        let target = match self.end_checkpoint {
+            Some(end) => fullnode_tip.min(end),
+            None => fullnode_tip,
+        };
+        match self.last_checkpoint {
+            Some(last) => target.saturating_sub(last),
+            None if target >= self.start_checkpoint => target - self.start_checkpoint + 1,
+            None => 0,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// One webhook event to POST to one URL. An event sent to several URLs has one delivery per
+/// URL, sharing the same `event_id` and payload.
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior, Serialize, Deserialize,
+)]
+#[sea_orm(table_name = "webhook_deliveries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Identifier of the event, for receivers to drop duplicates
+    pub event_id: String,
+    /// One of the [`WebhookEventType`] values
+    pub event_type: String,
+    pub checkpoint_idx: i64,
+    pub url: String,
+    /// JSON body, POSTed as is
+    pub payload: String,
+    /// One of the [`DeliveryState`] values
+    pub state: String,
+    /// Number of times the delivery was attempted
+    pub attempts: i32,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    /// UNIX timestamps (seconds) of the next attempt, of the event and of the acknowledgement
+    pub next_attempt_at: i64,
+    pub created_at: i64,
+    pub delivered_at: Option<i64>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// Lifecycle of a webhook delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryState {
+    /// Not acknowledged yet, attempted again until it is
+    Pending,
+    /// The receiver answered with a 2xx status
+    Delivered,
+}
+
+impl DeliveryState {
+    pub const ALL: [DeliveryState; 2] = [DeliveryState::Pending, DeliveryState::Delivered];
+
+    /// Value of the `state` column for this state
+    pub fn state(&self) -> &'static str {
+        match self {
+            DeliveryState::Pending => "pending",
+            DeliveryState::Delivered => "delivered",
+        }
+    }
+
+    /// State of a delivery from the value of its `state` column
+    pub fn from_state(state: &str) -> Option<Self> {
+        DeliveryState::ALL
+            .into_iter()
+            .find(|delivery_state| delivery_state.state() == state)
+    }
+}
+
+impl Display for DeliveryState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.state())
+    }
+}
+
+/// Events sent to the webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEventType {
+    /// A checkpoint was indexed for the first time
+    CheckpointCreated,
+    /// A checkpoint moved forward in its lifecycle, e.g. pending -> confirmed
+    StatusChanged,
+    /// A checkpoint got a txid, or a different one
+    TxidChanged,
+    /// A checkpoint moved backward or its txid was replaced by an L1 reorg
+    Reorged,
+    /// A checkpoint stalled in its status, or the index fell behind the fullnode
+    AlertRaised,
+    /// The condition of a raised alert is gone
+    AlertResolved,
+}
+
+impl WebhookEventType {
+    /// Value of the `type` field of the event and of the `event_type` column
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEventType::CheckpointCreated => "checkpoint.created",
+            WebhookEventType::StatusChanged => "checkpoint.status_changed",
+            WebhookEventType::TxidChanged => "checkpoint.txid_changed",
+            WebhookEventType::Reorged => "checkpoint.reorged",
+            WebhookEventType::AlertRaised => "alert.raised",
+            WebhookEventType::AlertResolved => "alert.resolved",
+        }
+    }
+}
+
+impl Display for WebhookEventType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An event to queue for every configured webhook
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewWebhookEvent {
+    pub event_id: String,
+    pub event_type: WebhookEventType,
+    pub checkpoint_idx: i64,
+    pub payload: String,
+}
+
+/// Number of webhook deliveries in each state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookDeliveryCounts {
+    pub pending: u64,
+    pub delivered: u64,
+}
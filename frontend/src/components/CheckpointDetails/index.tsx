@@ -15,8 +15,9 @@ const CheckpointDetails = () => {
   const [checkpoint, setData] = useState<RpcCheckpointInfoCheckpointExp | null>(
     null,
   );
+  const [error, setError] = useState<string | null>(null);
   const [totalPages, setTotalPages] = useState(0);
-  const [firstPage, setFirstPage] = useState(0);
+  const firstPage = 0; // Checkpoints are numbered from 0
   const rowsPerPage = 1; // Fixed value
   const { apiBaseUrl, alpenExplorerBaseUrl, bitcoinExplorerBaseUrl } =
     useConfig();
@@ -33,21 +34,47 @@ const CheckpointDetails = () => {
     console.log("currentPage", currentPage);
     const fetchData = async () => {
       try {
-        const response = await fetch(
-          `${apiBaseUrl}/api/checkpoint?p=${currentPage}`,
-        );
+        const [response, latest] = await Promise.all([
+          fetch(`${apiBaseUrl}/api/checkpoints/${currentPage}`),
+          fetch(`${apiBaseUrl}/api/checkpoints/latest`),
+        ]);
         const result = await response.json();
-        setData(result.result.items[0]);
-        console.log("result", result);
-        setTotalPages(result.result.total_pages);
-        setFirstPage(result.result.absolute_first_page);
+        if (!response.ok) {
+          setData(null);
+          setError(
+            result.error?.message ?? `Failed to load checkpoint ${currentPage}`,
+          );
+          return;
+        }
+        setData(result.result);
+        setError(null);
+        // The highest checkpoint bounds the pagination
+        if (latest.ok) {
+          const latestResult = await latest.json();
+          setTotalPages(latestResult.result.idx);
+        }
       } catch (error) {
         console.error("Error fetching checkpoint data:", error);
+        setData(null);
+        setError("Failed to reach the explorer API");
       }
     };
     if (currentPage >= 0) fetchData();
   }, [currentPage, rowsPerPage]);
 
+  if (error) {
+    return (
+      <>
+        <div className={styles.noData}>{error}</div>
+        <Pagination
+          currentPage={currentPage}
+          firstPage={firstPage}
+          totalPages={totalPages}
+          setPage={setCurrentPage}
+        />
+      </>
+    );
+  }
   if (!checkpoint) {
     return <div className={styles.noData}>Loading...</div>;
   }